@@ -0,0 +1,227 @@
+use std::{
+	hint::black_box,
+	time::{Duration, Instant},
+};
+
+use crate::{
+	cancel::{self, CancelToken},
+	memory::{self, MemoryUsage},
+	runner::{catch_panic, format_duration, format_timeout, with_silent_panics},
+	solution::Runner,
+};
+
+/// How `bench` prints its results
+pub enum BenchFormat {
+	Text,
+	/// One tab separated line per phase, so that runs from different commits
+	/// can be diffed or loaded into a spreadsheet.
+	Tsv,
+}
+
+/// Timings of one phase over all of the iterations
+pub struct Stats {
+	pub min: Duration,
+	pub median: Duration,
+	pub max: Duration,
+	/// Of the last iteration, they all allocate the same
+	pub memory: Option<MemoryUsage>,
+}
+
+impl Stats {
+	fn from_samples(mut samples: Vec<Duration>, memory: Option<MemoryUsage>) -> Stats {
+		samples.sort();
+		Stats {
+			min: samples[0],
+			median: samples[samples.len() / 2],
+			max: samples[samples.len() - 1],
+			memory,
+		}
+	}
+}
+
+/// Parse and part timings of a single day
+pub struct BenchReport {
+	pub day: u32,
+	pub iterations: u32,
+	pub parse: Stats,
+	pub parts: Vec<(u32, Result<Stats, String>)>,
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+	let start = Instant::now();
+	let result = black_box(f());
+	(result, start.elapsed())
+}
+
+pub fn bench_day(
+	solution: &dyn Runner,
+	contents: &str,
+	iterations: u32,
+) -> Result<BenchReport, String> {
+	assert!(iterations > 0, "Need at least one iteration");
+
+	with_silent_panics(|| {
+		let mut parse_samples = Vec::new();
+		let mut parse_memory = None;
+		let mut input = None;
+		for _ in 0..iterations {
+			let ((parsed, usage), duration) =
+				time(|| memory::measure(|| catch_panic(|| solution.parse(contents))));
+			input = Some(parsed?.map_err(|e| e.to_string())?);
+			parse_samples.push(duration);
+			parse_memory = usage;
+		}
+		let input = input.unwrap();
+
+		let mut parts = Vec::new();
+		for part in [1, 2] {
+			let mut solve_samples = Vec::new();
+			let mut solve_memory = None;
+			let mut failure = None;
+			for _ in 0..iterations {
+				let token = CancelToken::with_timeout(cancel::timeout());
+				let ((answer, usage), duration) = time(|| {
+					memory::measure(|| {
+						catch_panic(|| cancel::scope(&token, || solution.solve_with(input.as_ref(), part, &[])))
+					})
+				});
+				// A part can fail by panicking or by returning an error
+				if let Err(e) = answer.and_then(|answer| answer) {
+					failure = match cancel::timeout() {
+						Some(timeout) if token.is_cancelled() => {
+							Some(format!("timed out after {}", format_timeout(timeout)))
+						}
+						_ => Some(e),
+					};
+					break;
+				}
+				solve_samples.push(duration);
+				solve_memory = usage;
+			}
+
+			match failure {
+				Some(e) => parts.push((part, Err(e))),
+				None => parts.push((part, Ok(Stats::from_samples(solve_samples, solve_memory)))),
+			}
+		}
+
+		Ok(BenchReport {
+			day: solution.day(),
+			iterations,
+			parse: Stats::from_samples(parse_samples, parse_memory),
+			parts,
+		})
+	})
+}
+
+fn print_text_row(phase: &str, stats: &Stats) {
+	print!(
+		"{:<8} {:>12} {:>12} {:>12}",
+		phase,
+		format_duration(stats.min),
+		format_duration(stats.median),
+		format_duration(stats.max)
+	);
+	if let Some(usage) = stats.memory {
+		print!(
+			" {:>12} {:>12}",
+			memory::format_bytes(usage.peak),
+			memory::format_bytes(usage.total)
+		);
+	}
+	println!();
+}
+
+fn print_tsv_row(day: u32, phase: &str, stats: &Stats) {
+	print!(
+		"{}\t{}\t{}\t{}\t{}",
+		day,
+		phase,
+		stats.min.as_nanos(),
+		stats.median.as_nanos(),
+		stats.max.as_nanos()
+	);
+	if let Some(usage) = stats.memory {
+		print!("\t{}\t{}", usage.peak, usage.total);
+	}
+	println!();
+}
+
+pub fn print_report(report: &BenchReport, format: &BenchFormat) {
+	match format {
+		BenchFormat::Text => {
+			println!("Day {} ({} iterations)", report.day, report.iterations);
+			print!(
+				"{:<8} {:>12} {:>12} {:>12}",
+				"Phase", "Min", "Median", "Max"
+			);
+			if memory::enabled() {
+				print!(" {:>12} {:>12}", "Peak", "Allocated");
+			}
+			println!();
+			print_text_row("parse", &report.parse);
+			for (part, stats) in &report.parts {
+				let phase = format!("part{}", part);
+				match stats {
+					Ok(stats) => print_text_row(&phase, stats),
+					Err(e) => println!("{:<8} skipped ({})", phase, e),
+				}
+			}
+		}
+		BenchFormat::Tsv => {
+			print!("day\tphase\tmin_ns\tmedian_ns\tmax_ns");
+			if memory::enabled() {
+				print!("\tpeak_bytes\tallocated_bytes");
+			}
+			println!();
+			print_tsv_row(report.day, "parse", &report.parse);
+			for (part, stats) in &report.parts {
+				if let Ok(stats) = stats {
+					print_tsv_row(report.day, &format!("part{}", part), stats);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{day1::Day1, day2::Day2, day25::Day25};
+
+	#[test]
+	fn stats_from_samples() {
+		let samples = [5, 1, 3, 2, 4]
+			.iter()
+			.map(|ms| Duration::from_millis(*ms))
+			.collect();
+		let stats = Stats::from_samples(samples, None);
+		assert_eq!(stats.min, Duration::from_millis(1));
+		assert_eq!(stats.median, Duration::from_millis(3));
+		assert_eq!(stats.max, Duration::from_millis(5));
+	}
+
+	#[test]
+	fn bench_day_times_every_part() {
+		let report = bench_day(&Day1, "1\n2\n3\n4", 3).unwrap();
+		assert_eq!(report.iterations, 3);
+		assert_eq!(report.parts.len(), 2);
+		assert!(report.parts.iter().all(|(_, stats)| stats.is_ok()));
+	}
+
+	#[test]
+	fn bench_day_reports_failing_parts() {
+		let report = bench_day(&Day25, "v>\n>v", 2).unwrap();
+		assert!(report.parts[0].1.is_ok());
+		assert!(report.parts[1].1.is_err());
+		assert!(bench_day(&Day1, "oops", 2).is_err());
+
+		// Surfaces in part 1, but not in part 2 where `up` only aims
+		let report = bench_day(&Day2, "up 1", 2).unwrap();
+		assert_eq!(
+			report.parts[0].1.as_ref().err().unwrap(),
+			"the command on line 1 would take the submarine 1 above the surface"
+		);
+		assert!(report.parts[1].1.is_ok());
+	}
+}
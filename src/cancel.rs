@@ -0,0 +1,140 @@
+// Cooperative cancellation, so that a solver stuck on a bad input can be
+// stopped. The runner makes a token for every part it solves, and the long
+// running loops call `checkpoint`, which unwinds out of the solver once the
+// token is cancelled or its time is up. The runner then reports the part as
+// timed out instead of waiting forever.
+
+use std::{
+	cell::RefCell,
+	panic,
+	sync::{
+		atomic::{AtomicBool, AtomicU64, Ordering},
+		Arc,
+	},
+	time::{Duration, Instant},
+};
+
+// In milliseconds, 0 means no timeout
+static TIMEOUT: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+	static CURRENT: RefCell<Option<CancelToken>> = const { RefCell::new(None) };
+}
+
+/// What a cancelled solver unwinds with
+pub struct Cancelled;
+
+/// Tells a running solver to stop, either when asked to or once its deadline
+/// has passed
+#[derive(Clone, Default)]
+pub struct CancelToken {
+	cancelled: Arc<AtomicBool>,
+	deadline: Option<Instant>,
+}
+
+impl CancelToken {
+	pub fn with_timeout(timeout: Option<Duration>) -> CancelToken {
+		CancelToken {
+			cancelled: Arc::default(),
+			deadline: timeout.map(|timeout| Instant::now() + timeout),
+		}
+	}
+
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		if self.cancelled.load(Ordering::Relaxed) {
+			return true;
+		}
+		match self.deadline {
+			Some(deadline) if Instant::now() >= deadline => {
+				self.cancel();
+				true
+			}
+			_ => false,
+		}
+	}
+}
+
+pub fn set_timeout(timeout: Option<Duration>) {
+	let millis = timeout.map_or(0, |timeout| (timeout.as_millis() as u64).max(1));
+	TIMEOUT.store(millis, Ordering::Relaxed);
+}
+
+pub fn timeout() -> Option<Duration> {
+	match TIMEOUT.load(Ordering::Relaxed) {
+		0 => None,
+		millis => Some(Duration::from_millis(millis)),
+	}
+}
+
+// Puts the previous token back when the scope ends, even if it unwinds
+struct Restore(Option<CancelToken>);
+
+impl Drop for Restore {
+	fn drop(&mut self) {
+		let previous = self.0.take();
+		CURRENT.with(|current| *current.borrow_mut() = previous);
+	}
+}
+
+/// Runs `f` with `token` as the one checked by `checkpoint` on this thread
+pub fn scope<T>(token: &CancelToken, f: impl FnOnce() -> T) -> T {
+	let previous = CURRENT.with(|current| current.borrow_mut().replace(token.clone()));
+	let _restore = Restore(previous);
+	f()
+}
+
+pub fn current() -> Option<CancelToken> {
+	CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Stops the current solver if it has been cancelled. Unwinding skips the panic
+/// hook, so nothing gets printed for it.
+pub fn checkpoint() {
+	let cancelled = CURRENT.with(|current| {
+		current
+			.borrow()
+			.as_ref()
+			.is_some_and(|token| token.is_cancelled())
+	});
+	if cancelled {
+		panic::resume_unwind(Box::new(Cancelled));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::runner::catch_panic;
+
+	#[test]
+	fn checkpoint_stops_cancelled_work() {
+		let token = CancelToken::default();
+		let mut iterations = 0;
+		let result = catch_panic(|| {
+			scope(&token, || loop {
+				checkpoint();
+				iterations += 1;
+				if iterations == 10 {
+					token.cancel();
+				}
+			})
+		});
+		assert!(result.is_err());
+		assert_eq!(iterations, 10);
+		assert!(current().is_none());
+	}
+
+	#[test]
+	fn deadline_cancels() {
+		let token = CancelToken::with_timeout(Some(Duration::ZERO));
+		assert!(token.is_cancelled());
+		assert!(!CancelToken::with_timeout(Some(Duration::from_secs(60))).is_cancelled());
+		assert!(!CancelToken::with_timeout(None).is_cancelled());
+		// Without a token there is nothing to cancel
+		checkpoint();
+	}
+}
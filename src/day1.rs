@@ -1,27 +1,418 @@
-use std::num::ParseIntError;
+//! Day 1: Sonar Sweep
+//!
+//! Counts how often the measured depth increases, either between single
+//! measurements or between sums of three-measurement windows. The depths can
+//! also be streamed from a reader with windows of any length, for logs that
+//! are too big to keep in memory. The report of a part points out where the
+//! increases, plateaus and spikes in the depths are.
 
-pub fn parse_input(input: &str) -> Result<Vec<u32>, ParseIntError> {
-	input.split_whitespace().map(|s| s.parse()).collect()
+use crate::{
+	generate::Rng,
+	json::Json,
+	parse::{ParseError, Source},
+	solution::{parse_param, unknown_param, PuzzleParams, Solution},
+};
+use std::{
+	cmp::Ordering,
+	collections::VecDeque,
+	io::{self, BufRead},
+};
+
+/// Size of the sliding window, 1 for part 1 and 3 for part 2
+pub struct Params {
+	pub window: usize,
+	/// Only used by the report, see `find_spikes`
+	pub spike_threshold: u32,
 }
 
-pub fn part1(depths: &[u32]) -> u32 {
-	let mut count = 0;
-	for i in 1..depths.len() {
-		if depths[i] > depths[i - 1] {
-			count += 1;
+impl PuzzleParams for Params {
+	fn puzzle(part: u32) -> Params {
+		Params {
+			window: if part == 1 { 1 } else { 3 },
+			spike_threshold: 100,
 		}
 	}
-	return count;
+
+	fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+		match name {
+			"window" => self.window = parse_param(name, value)?,
+			"spike_threshold" => self.spike_threshold = parse_param(name, value)?,
+			_ => return Err(unknown_param(name, &["window", "spike_threshold"])),
+		}
+		if self.window == 0 {
+			return Err("the window needs at least one depth".into());
+		}
+		Ok(())
+	}
 }
 
-pub fn part2(depths: &[u32]) -> u32 {
-	let mut count = 0;
-	for i in 2..depths.len() - 1 {
-		if depths[i + 1] > depths[i - 2] {
-			count += 1;
+pub fn parse_input(input: &str) -> Result<Vec<u32>, ParseError> {
+	let source = Source::new(Day1::DAY, input);
+	input.split_whitespace().map(|s| source.number(s)).collect()
+}
+
+/// Reads the depths one line at a time, so that the whole input never has to
+/// be in memory at once. Stops at the first error.
+pub struct Depths<R> {
+	lines: io::Lines<R>,
+	line_number: usize,
+	pending: VecDeque<u32>,
+	failed: bool,
+}
+
+pub fn read_depths<R: BufRead>(reader: R) -> Depths<R> {
+	Depths {
+		lines: reader.lines(),
+		line_number: 0,
+		pending: VecDeque::new(),
+		failed: false,
+	}
+}
+
+impl<R: BufRead> Depths<R> {
+	fn read_line(&mut self) -> Option<Result<(), ParseError>> {
+		let line = self.lines.next()?;
+		self.line_number += 1;
+		let line = match line {
+			Ok(line) => line,
+			Err(e) => {
+				return Some(Err(ParseError {
+					day: Day1::DAY,
+					line: self.line_number,
+					column: 1,
+					message: format!("failed to read the input: {}", e),
+				}))
+			}
+		};
+		// Nothing of a line with an error in it is passed on
+		let source = Source::new(Day1::DAY, &line);
+		let depths: Result<Vec<u32>, ParseError> = line
+			.split_whitespace()
+			.map(|token| source.number(token))
+			.collect();
+		match depths {
+			Ok(depths) => {
+				self.pending.extend(depths);
+				Some(Ok(()))
+			}
+			Err(mut e) => {
+				e.line = self.line_number;
+				Some(Err(e))
+			}
+		}
+	}
+}
+
+impl<R: BufRead> Iterator for Depths<R> {
+	type Item = Result<u32, ParseError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		while self.pending.is_empty() && !self.failed {
+			if let Err(e) = self.read_line()? {
+				self.failed = true;
+				return Some(Err(e));
+			}
+		}
+		self.pending.pop_front().map(Ok)
+	}
+}
+
+/// Compares the sum of every window of depths with the one before it. Two
+/// neighbouring windows share all but their first and last depth, so only
+/// those need comparing and only the last `size` depths are kept around.
+pub struct WindowChanges<I> {
+	depths: I,
+	size: usize,
+	recent: VecDeque<u32>,
+}
+
+pub fn window_changes<I: IntoIterator<Item = u32>>(
+	depths: I,
+	size: usize,
+) -> WindowChanges<I::IntoIter> {
+	assert!(size > 0, "A window needs at least one depth");
+	WindowChanges {
+		depths: depths.into_iter(),
+		size,
+		recent: VecDeque::with_capacity(size),
+	}
+}
+
+impl<I: Iterator<Item = u32>> Iterator for WindowChanges<I> {
+	type Item = Ordering;
+
+	fn next(&mut self) -> Option<Ordering> {
+		while self.recent.len() < self.size {
+			let depth = self.depths.next()?;
+			self.recent.push_back(depth);
+		}
+		let depth = self.depths.next()?;
+		let dropped = self.recent.pop_front().unwrap();
+		self.recent.push_back(depth);
+		Some(depth.cmp(&dropped))
+	}
+}
+
+/// Windows in a row whose sums kept going the same way
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Run {
+	/// Index of the window the run starts from
+	pub start: usize,
+	/// How many times in a row the sum changed in the same direction
+	pub length: usize,
+}
+
+/// Everything one pass over the windows finds out
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SweepReport {
+	pub increases: usize,
+	pub longest_increasing: Run,
+	pub longest_decreasing: Run,
+}
+
+impl SweepReport {
+	/// The earliest run wins when there are several equally long ones
+	pub fn from_changes(changes: impl IntoIterator<Item = Ordering>) -> SweepReport {
+		let mut report = SweepReport::default();
+		let mut increasing = Run::default();
+		let mut decreasing = Run::default();
+		for (i, change) in changes.into_iter().enumerate() {
+			let (current, other, longest) = match change {
+				Ordering::Greater => {
+					report.increases += 1;
+					(
+						&mut increasing,
+						&mut decreasing,
+						&mut report.longest_increasing,
+					)
+				}
+				Ordering::Less => (
+					&mut decreasing,
+					&mut increasing,
+					&mut report.longest_decreasing,
+				),
+				Ordering::Equal => {
+					increasing.length = 0;
+					decreasing.length = 0;
+					continue;
+				}
+			};
+			if current.length == 0 {
+				current.start = i;
+			}
+			current.length += 1;
+			other.length = 0;
+			if current.length > longest.length {
+				*longest = *current;
+			}
+		}
+		report
+	}
+}
+
+pub fn sweep(depths: impl IntoIterator<Item = u32>, window: usize) -> SweepReport {
+	SweepReport::from_changes(window_changes(depths, window))
+}
+
+pub fn sweep_reader(reader: impl BufRead, window: usize) -> Result<SweepReport, ParseError> {
+	let mut error = None;
+	let depths = read_depths(reader).map_while(|depth| depth.map_err(|e| error = Some(e)).ok());
+	let report = sweep(depths, window);
+	match error {
+		Some(e) => Err(e),
+		None => Ok(report),
+	}
+}
+
+pub fn count_increases(depths: &[u32], window: usize) -> u32 {
+	window_changes(depths.iter().copied(), window)
+		.filter(|change| *change == Ordering::Greater)
+		.count() as u32
+}
+
+/// Windows are numbered by their first depth, a change is numbered by the
+/// window it changes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Jump {
+	pub window: usize,
+	pub change: u32,
+}
+
+/// Windows `start..=end` all have the same sum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Plateau {
+	pub start: usize,
+	pub end: usize,
+}
+
+/// A reading that sticks out from both of its neighbours
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spike {
+	pub reading: usize,
+	pub depth: u32,
+	/// Negative for a reading that is shallower than both of its neighbours
+	pub deviation: i64,
+}
+
+/// The odd bits of a sonar sweep, for the report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anomalies {
+	pub window: usize,
+	/// The windows whose sum is bigger than the one of the window before
+	pub increases: Vec<usize>,
+	pub biggest_jump: Option<Jump>,
+	pub plateaus: Vec<Plateau>,
+	pub spikes: Vec<Spike>,
+}
+
+/// A reading breaks the trend if it is deeper (or shallower) than both of its
+/// neighbours by more than `threshold`
+pub fn find_spikes(depths: &[u32], threshold: u32) -> Vec<Spike> {
+	let mut spikes = Vec::new();
+	for i in 1..depths.len().saturating_sub(1) {
+		let before = depths[i] as i64 - depths[i - 1] as i64;
+		let after = depths[i] as i64 - depths[i + 1] as i64;
+		let deviation = if before > 0 {
+			before.min(after)
+		} else {
+			before.max(after)
+		};
+		if before.signum() == after.signum() && deviation.abs() > threshold as i64 {
+			spikes.push(Spike {
+				reading: i,
+				depth: depths[i],
+				deviation,
+			});
 		}
 	}
-	return count;
+	spikes
+}
+
+pub fn find_anomalies(depths: &[u32], window: usize, spike_threshold: u32) -> Anomalies {
+	let mut anomalies = Anomalies {
+		window,
+		increases: Vec::new(),
+		biggest_jump: None,
+		plateaus: Vec::new(),
+		spikes: find_spikes(depths, spike_threshold),
+	};
+	for i in 0..depths.len().saturating_sub(window) {
+		// The windows only differ in their first and last depth
+		let (dropped, added) = (depths[i], depths[i + window]);
+		if added > dropped {
+			anomalies.increases.push(i + 1);
+			let change = added - dropped;
+			if anomalies
+				.biggest_jump
+				.is_none_or(|jump| change > jump.change)
+			{
+				anomalies.biggest_jump = Some(Jump {
+					window: i + 1,
+					change,
+				});
+			}
+		} else if added == dropped {
+			match anomalies.plateaus.last_mut() {
+				Some(plateau) if plateau.end == i => plateau.end = i + 1,
+				_ => anomalies.plateaus.push(Plateau {
+					start: i,
+					end: i + 1,
+				}),
+			}
+		}
+	}
+	anomalies
+}
+
+impl Anomalies {
+	pub fn to_json(&self) -> Json {
+		let numbers = |values: &[usize]| Json::Array(values.iter().map(Json::number).collect());
+		let biggest_jump = match self.biggest_jump {
+			Some(jump) => Json::Object(vec![
+				("window", Json::number(jump.window)),
+				("change", Json::number(jump.change)),
+			]),
+			None => Json::Null,
+		};
+		let plateaus = self
+			.plateaus
+			.iter()
+			.map(|plateau| {
+				Json::Object(vec![
+					("start", Json::number(plateau.start)),
+					("end", Json::number(plateau.end)),
+				])
+			})
+			.collect();
+		let spikes = self
+			.spikes
+			.iter()
+			.map(|spike| {
+				Json::Object(vec![
+					("reading", Json::number(spike.reading)),
+					("depth", Json::number(spike.depth)),
+					("deviation", Json::number(spike.deviation)),
+				])
+			})
+			.collect();
+		Json::Object(vec![
+			("window", Json::number(self.window)),
+			("increases", numbers(&self.increases)),
+			("biggest_jump", biggest_jump),
+			("plateaus", Json::Array(plateaus)),
+			("spikes", Json::Array(spikes)),
+		])
+	}
+}
+
+pub fn part1(depths: &[u32]) -> u32 {
+	count_increases(depths, 1)
+}
+
+pub fn part2(depths: &[u32]) -> u32 {
+	count_increases(depths, 3)
+}
+
+/// Depths mostly go down, but not always
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let mut depth = rng.range(100, 200);
+	let mut depths = Vec::new();
+	for _ in 0..size.max(4) {
+		depths.push(depth.to_string());
+		depth = (depth + rng.range(-15, 25)).max(0);
+	}
+	depths.join("\n") + "\n"
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+	const DAY: u32 = 1;
+
+	type Input = Vec<u32>;
+	type Output1 = u32;
+	type Output2 = u32;
+	type Params = Params;
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, params: &Self::Params) -> Self::Output1 {
+		count_increases(input, params.window)
+	}
+
+	fn part2(input: &Self::Input, params: &Self::Params) -> Self::Output2 {
+		count_increases(input, params.window)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+
+	fn report(input: &Self::Input, _part: u32, params: &Self::Params) -> Option<Json> {
+		Some(find_anomalies(input, params.window, params.spike_threshold).to_json())
+	}
 }
 
 #[cfg(test)]
@@ -41,4 +432,138 @@ mod tests {
 		let result = part2(&input);
 		assert_eq!(result, 5);
 	}
+
+	#[test]
+	fn sweep_example() {
+		let input = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+		let report = sweep_reader(input.as_bytes(), 1).unwrap();
+		assert_eq!(report.increases, 7);
+		assert_eq!(
+			report.longest_increasing,
+			Run {
+				start: 0,
+				length: 3
+			}
+		);
+		assert_eq!(
+			report.longest_decreasing,
+			Run {
+				start: 3,
+				length: 1
+			}
+		);
+
+		// The sums are 607, 618, 618, 617, 647, 716, 769 and 792
+		let report = sweep_reader(input.as_bytes(), 3).unwrap();
+		assert_eq!(report.increases, 5);
+		assert_eq!(
+			report.longest_increasing,
+			Run {
+				start: 3,
+				length: 4
+			}
+		);
+		assert_eq!(
+			report.longest_decreasing,
+			Run {
+				start: 2,
+				length: 1
+			}
+		);
+
+		assert_eq!(
+			sweep_reader(input.as_bytes(), 20).unwrap(),
+			SweepReport::default()
+		);
+	}
+
+	#[test]
+	fn streamed_errors_point_at_their_line() {
+		let error = sweep_reader("1 2\n3\n 4 x5\n6".as_bytes(), 1).unwrap_err();
+		assert_eq!((error.line, error.column), (3, 4));
+		assert_eq!(error.message, "invalid number 'x5'");
+
+		let depths: Vec<_> = read_depths("1\n\n2 3\n".as_bytes()).collect();
+		assert_eq!(depths, vec![Ok(1), Ok(2), Ok(3)]);
+
+		// The good numbers in front of the bad one don't come after the error
+		let depths: Vec<_> = read_depths("100\n200 abc\n300\n".as_bytes()).collect();
+		assert_eq!(depths.len(), 2);
+		assert_eq!(depths[0], Ok(100));
+		assert_eq!(
+			depths[1].as_ref().unwrap_err().message,
+			"invalid number 'abc'"
+		);
+	}
+
+	#[test]
+	fn windows_match_summing_them() {
+		let depths = parse_input(&generate_input(&mut Rng::new(1), 200)).unwrap();
+		for window in 1..=5 {
+			let sums: Vec<u32> = depths.windows(window).map(|w| w.iter().sum()).collect();
+			let increases = sums.windows(2).filter(|pair| pair[1] > pair[0]).count();
+			assert_eq!(count_increases(&depths, window) as usize, increases);
+			assert_eq!(sweep(depths.iter().copied(), window).increases, increases);
+		}
+	}
+
+	#[test]
+	fn anomalies() {
+		let depths = [10, 12, 12, 11, 50, 13, 14, 14, 14, 20];
+		let anomalies = find_anomalies(&depths, 1, 20);
+		assert_eq!(anomalies.increases, vec![1, 4, 6, 9]);
+		assert_eq!(
+			anomalies.biggest_jump,
+			Some(Jump {
+				window: 4,
+				change: 39
+			})
+		);
+		assert_eq!(
+			anomalies.plateaus,
+			vec![Plateau { start: 1, end: 2 }, Plateau { start: 6, end: 8 }]
+		);
+		assert_eq!(
+			anomalies.spikes,
+			vec![Spike {
+				reading: 4,
+				depth: 50,
+				deviation: 37
+			}]
+		);
+		assert!(find_anomalies(&depths, 1, 40).spikes.is_empty());
+
+		assert_eq!(
+			find_spikes(&[100, 20, 100], 50),
+			vec![Spike {
+				reading: 1,
+				depth: 20,
+				deviation: -80
+			}]
+		);
+		assert_eq!(
+			find_anomalies(&depths, 3, 20).increases.len(),
+			count_increases(&depths, 3) as usize
+		);
+	}
+
+	#[test]
+	fn report_json() {
+		let report = Day1::report(&vec![1, 2], 1, &Params::puzzle(1)).unwrap();
+		assert_eq!(
+			report.to_string(),
+			r#"{"window":1,"increases":[1],"biggest_jump":{"window":1,"change":1},"plateaus":[],"spikes":[]}"#
+		);
+		let report = Day1::report(&vec![5], 2, &Params::puzzle(2)).unwrap();
+		assert!(report.to_string().contains(r#""biggest_jump":null"#));
+	}
+
+	#[test]
+	fn window_param() {
+		let mut params = Params::puzzle(2);
+		assert_eq!(params.window, 3);
+		params.set("window", "2").unwrap();
+		assert_eq!(params.window, 2);
+		assert!(params.set("window", "0").is_err());
+	}
 }
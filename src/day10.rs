@@ -1,3 +1,13 @@
+//! Day 10: Syntax Scoring
+//!
+//! Scores lines of brackets that are either corrupted or incomplete.
+
+use crate::{
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
+
 #[derive(PartialEq)]
 enum ChunkVariant {
 	Parenthesis,
@@ -6,23 +16,30 @@ enum ChunkVariant {
 	Pointy,
 }
 
-pub fn parse_input(input: &str) -> Vec<String> {
-	input.lines().map(|s| s.into()).collect()
+pub fn parse_input(input: &str) -> Result<Vec<String>, ParseError> {
+	let source = Source::new(Day10::DAY, input);
+	let mut lines = Vec::new();
+	for line in input.lines() {
+		if let Some((i, c)) = line.char_indices().find(|(_, c)| !"()[]{}<>".contains(*c)) {
+			return Err(source.error(&line[i..], format!("invalid chunk character '{}'", c)));
+		}
+		lines.push(line.into());
+	}
+	Ok(lines)
 }
 
 fn is_opening(c: char) -> bool {
 	c == '(' || c == '[' || c == '{' || c == '<'
 }
 
-// I know that it is not ideal to throw a panic here. But I'm too lazy to do
-// error handling. And the input is guaranteed to be correct to this is fine.
+// Other characters are already rejected while parsing
 fn get_chunk_variant(c: char) -> ChunkVariant {
 	match c {
 		'(' | ')' => ChunkVariant::Parenthesis,
 		'[' | ']' => ChunkVariant::Bracket,
 		'{' | '}' => ChunkVariant::Curly,
 		'<' | '>' => ChunkVariant::Pointy,
-		_ => panic!("Invalid chunk character"),
+		_ => unreachable!("Invalid chunk character"),
 	}
 }
 
@@ -42,7 +59,7 @@ fn find_corrupted_chunk_symbol(line: &str) -> Option<ChunkVariant> {
 	None
 }
 
-pub fn part1(lines: &Vec<String>) -> u32 {
+pub fn part1(lines: &[String]) -> u32 {
 	let mut score = 0;
 	for line in lines {
 		let result = find_corrupted_chunk_symbol(line);
@@ -69,11 +86,11 @@ fn find_unclosed_chunks(line: &str) -> Vec<ChunkVariant> {
 	active_chunks
 }
 
-pub fn part2(lines: &Vec<String>) -> u64 {
+pub fn part2(lines: &[String]) -> u64 {
 	let mut scores = Vec::new();
 	for line in lines {
 		let corrupted = find_corrupted_chunk_symbol(line);
-		if corrupted == None {
+		if corrupted.is_none() {
 			let mut score = 0;
 			let mut unclosed_chunks = find_unclosed_chunks(line);
 			unclosed_chunks.reverse();
@@ -93,6 +110,83 @@ pub fn part2(lines: &Vec<String>) -> u64 {
 	return scores[scores.len() / 2];
 }
 
+/// Chunks are opened and closed at random. Corrupted lines get one of their
+/// closing characters swapped for a wrong one, incomplete lines just stop.
+/// There is always an odd number of incomplete lines, so that part 2 has a
+/// middle score.
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let pairs = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+	let mut incomplete: Vec<bool> = (0..size.max(1)).map(|_| rng.chance(1, 2)).collect();
+	if incomplete
+		.iter()
+		.filter(|is_incomplete| **is_incomplete)
+		.count()
+		% 2
+		== 0
+	{
+		incomplete[0] = !incomplete[0];
+	}
+
+	let mut lines = Vec::new();
+	for is_incomplete in incomplete {
+		let mut line = Vec::new();
+		let mut expected_closing = Vec::new();
+		let mut closing_indices = Vec::new();
+		for _ in 0..rng.range(24, 100) {
+			// Not nesting too deep keeps the completion scores small
+			if expected_closing.is_empty() || (expected_closing.len() < 12 && rng.chance(1, 2)) {
+				let (opening, closing) = *rng.choose(&pairs);
+				line.push(opening);
+				expected_closing.push(closing);
+			} else {
+				closing_indices.push(line.len());
+				line.push(expected_closing.pop().unwrap());
+			}
+		}
+
+		if is_incomplete {
+			if expected_closing.is_empty() {
+				line.push('(');
+			}
+		} else {
+			let i = *rng.choose(&closing_indices);
+			let correct = line[i];
+			while line[i] == correct {
+				line[i] = rng.choose(&pairs).1;
+			}
+		}
+		lines.push(line.into_iter().collect::<String>());
+	}
+	lines.join("\n") + "\n"
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+	const DAY: u32 = 10;
+
+	type Input = Vec<String>;
+	type Output1 = u32;
+	type Output2 = u64;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -132,4 +226,10 @@ mod tests {
 		let result = part2(&input);
 		assert_eq!(result, 288957);
 	}
+
+	#[test]
+	fn parse_error() {
+		let error = parse_input("[()]\n(<x>)").unwrap_err();
+		assert_eq!((error.line, error.column), (2, 3));
+	}
 }
@@ -1,82 +1,67 @@
-use arrayvec::ArrayVec;
-
-pub fn parse_input(input: &str) -> [[u32; 10]; 10] {
-	input
-		.lines()
-		.map(|s| {
-			s.chars()
-				.map(|s| s.to_digit(10).unwrap())
-				.collect::<ArrayVec<u32, 10>>()
-				.into_inner()
-				.unwrap()
-		})
-		.collect::<ArrayVec<[u32; 10], 10>>()
-		.into_inner()
-		.unwrap()
-}
-
-fn _display_grid(grid: &[[u32; 10]; 10]) {
-	for i in 0..grid.len() {
-		for j in 0..grid[i].len() {
-			print!("{:X}", grid[i][j]);
-		}
-		print!("\n");
-	}
-	print!("\n");
+//! Day 11: Dumbo Octopus
+//!
+//! Simulates a grid of octopuses that flash when their energy gets too high.
+
+use crate::{
+	cancel,
+	generate::Rng,
+	grid::Grid,
+	parse::{ParseError, Source},
+	solution::{parse_param, unknown_param, PuzzleParams, Solution},
+	visualize::{Cell, Frame, FrameSink},
+};
+
+pub fn parse_input(input: &str) -> Result<Grid<u32>, ParseError> {
+	let source = Source::new(Day11::DAY, input);
+	Grid::parse(&source, input, |c| c.to_digit(10))
 }
 
-fn bump_energy(grid: &mut [[u32; 10]; 10]) {
-	for i in 0..grid.len() {
-		for j in 0..grid[i].len() {
-			grid[i][j] += 1;
-		}
-	}
+/// Only part 1 stops after a number of steps, part 2 goes on until all of the
+/// octopuses flash at once
+pub struct Params {
+	pub steps: u32,
 }
 
-fn bump_energy_around(grid: &mut [[u32; 10]; 10], i: usize, j: usize) {
-	if j > 0 {
-		grid[i + 0][j - 1] += 1;
-	}
-	if j < 9 {
-		grid[i + 0][j + 1] += 1;
-	}
-	if i > 0 {
-		grid[i - 1][j + 0] += 1;
-	}
-	if i < 9 {
-		grid[i + 1][j + 0] += 1;
+impl PuzzleParams for Params {
+	fn puzzle(_part: u32) -> Params {
+		Params { steps: 100 }
 	}
 
-	if i > 0 && j > 0 {
-		grid[i - 1][j - 1] += 1;
-	}
-	if i < 9 && j > 0 {
-		grid[i + 1][j - 1] += 1;
+	fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+		match name {
+			"steps" => self.steps = parse_param(name, value)?,
+			_ => return Err(unknown_param(name, &["steps"])),
+		}
+		Ok(())
 	}
-	if i > 0 && j < 9 {
-		grid[i - 1][j + 1] += 1;
+}
+
+fn bump_energy(grid: &mut Grid<u32>) {
+	for energy in grid.iter_mut() {
+		*energy += 1;
 	}
-	if i < 9 && j < 9 {
-		grid[i + 1][j + 1] += 1;
+}
+
+fn bump_energy_around(grid: &mut Grid<u32>, x: usize, y: usize) {
+	for neighbour in grid.neighbours8(x, y) {
+		grid[neighbour] += 1;
 	}
 }
 
-fn perform_flashes(grid: &mut [[u32; 10]; 10]) -> u32 {
+fn perform_flashes(grid: &mut Grid<u32>) -> u32 {
 	let mut flashes = 0;
-	let mut has_flashed: [[bool; 10]; 10] = [[false; 10]; 10];
+	let mut has_flashed = Grid::new(grid.width(), grid.height(), false);
 	let mut anyone_flashed = true;
 
 	while anyone_flashed {
 		anyone_flashed = false;
 
-		for i in 0..grid.len() {
-			for j in 0..grid[i].len() {
-				if grid[i][j] > 9 && !has_flashed[i][j] {
-					flashes += 1;
-					has_flashed[i][j] = true;
-					anyone_flashed = true;
-					bump_energy_around(grid, i, j);
-				}
+		for position in grid.positions() {
+			if grid[position] > 9 && !has_flashed[position] {
+				flashes += 1;
+				has_flashed[position] = true;
+				anyone_flashed = true;
+				bump_energy_around(grid, position.0, position.1);
 			}
 		}
 	}
@@ -84,53 +69,145 @@ fn perform_flashes(grid: &mut [[u32; 10]; 10]) -> u32 {
 	return flashes;
 }
 
-fn reset_energy(grid: &mut [[u32; 10]; 10]) {
-	for i in 0..grid.len() {
-		for j in 0..grid[i].len() {
-			if grid[i][j] > 9 {
-				grid[i][j] = 0;
-			}
+fn reset_energy(grid: &mut Grid<u32>) {
+	for energy in grid.iter_mut() {
+		if *energy > 9 {
+			*energy = 0;
 		}
 	}
 }
 
-fn do_step(grid: &mut [[u32; 10]; 10]) -> u32 {
+fn do_step(grid: &mut Grid<u32>) -> u32 {
 	bump_energy(grid);
 	let flashes = perform_flashes(grid);
 	reset_energy(grid);
 	return flashes;
 }
 
-fn has_all_zeros(grid: &[[u32; 10]; 10]) -> bool {
-	for i in 0..grid.len() {
-		for j in 0..grid[i].len() {
-			if grid[i][j] != 0 {
-				return false;
-			}
-		}
-	}
-	return true;
+fn has_all_zeros(grid: &Grid<u32>) -> bool {
+	return grid.iter().all(|energy| *energy == 0);
 }
 
-pub fn part1(grid: &[[u32; 10]; 10]) -> u32 {
+pub fn part1(grid: &Grid<u32>, params: &Params) -> u32 {
 	let mut flashes = 0;
 	let mut active_grid = grid.clone();
-	for _ in 0..100 {
+	for _ in 0..params.steps {
+		cancel::checkpoint();
 		flashes += do_step(&mut active_grid);
 	}
 	return flashes;
 }
 
-pub fn part2(grid: &[[u32; 10]; 10]) -> u32 {
+pub fn part2(grid: &Grid<u32>) -> u32 {
 	let mut active_grid = grid.clone();
 	let mut step = 0;
 	while !has_all_zeros(&active_grid) {
+		cancel::checkpoint();
 		do_step(&mut active_grid);
 		step += 1;
 	}
 	return step;
 }
 
+// Octopuses that just flashed are bright, the rest get bluer as their energy
+// builds up
+fn energy_frame(grid: &Grid<u32>) -> Frame {
+	grid.map(|energy| {
+		let symbol = std::char::from_digit(*energy, 10).unwrap();
+		if *energy == 0 {
+			Cell::new(symbol, [255, 255, 160])
+		} else {
+			let level = *energy as u8 * 20;
+			Cell::new(symbol, [0, level / 2, 40 + level])
+		}
+	})
+}
+
+/// Part 1 shows the given number of steps, part 2 goes on until all of them
+/// flash at once
+pub fn visualize(grid: &Grid<u32>, part: u32, params: &Params, frames: &mut dyn FrameSink) {
+	let mut active_grid = grid.clone();
+	let mut total_flashes = 0;
+	frames.push(
+		&energy_frame(&active_grid),
+		&[("flashes", "0".into()), ("total flashes", "0".into())],
+	);
+	let mut step = 0;
+	while (part == 1 && step < params.steps) || (part != 1 && !has_all_zeros(&active_grid)) {
+		cancel::checkpoint();
+		let flashes = do_step(&mut active_grid);
+		total_flashes += flashes;
+		let counters = [
+			("flashes", flashes.to_string()),
+			("total flashes", total_flashes.to_string()),
+		];
+		frames.push(&energy_frame(&active_grid), &counters);
+		step += 1;
+	}
+}
+
+/// Not every grid of octopuses ends up flashing in sync, so the grids that don't
+/// within a thousand steps are thrown away. Grids with all levels of energy
+/// hardly ever synchronize when they are large, every thrown away grid narrows
+/// down the range of energies a bit.
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let side = size.max(1);
+	let mut spread = 10;
+	loop {
+		let base = rng.below(10);
+		let energies = (0..side * side)
+			.map(|_| ((base + rng.below(spread)) % 10) as u32)
+			.collect();
+		let grid = Grid::from_cells(side, side, energies);
+		let mut active_grid = grid.clone();
+		let synchronizes = (0..1000).any(|_| {
+			do_step(&mut active_grid);
+			has_all_zeros(&active_grid)
+		});
+		if synchronizes {
+			return grid.render(|energy| std::char::from_digit(*energy, 10).unwrap());
+		}
+		spread = (spread - 1).max(4);
+	}
+}
+
+pub struct Day11;
+
+impl Solution for Day11 {
+	const DAY: u32 = 11;
+
+	type Input = Grid<u32>;
+	type Output1 = u32;
+	type Output2 = u32;
+	type Params = Params;
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, params: &Self::Params) -> Self::Output1 {
+		part1(input, params)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+
+	fn visualize(
+		input: &Self::Input,
+		part: u32,
+		params: &Self::Params,
+		frames: &mut dyn FrameSink,
+	) -> bool {
+		visualize(input, part, params, frames);
+		true
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -149,8 +226,16 @@ mod tests {
 			[4, 8, 4, 6, 8, 4, 8, 5, 5, 4],
 			[5, 2, 8, 3, 7, 5, 1, 5, 2, 6],
 		];
-		let result = part1(&input);
+		let result = part1(
+			&Grid::from_cells(10, 10, input.concat()),
+			&Params::puzzle(1),
+		);
 		assert_eq!(result, 1656);
+		let result = part1(
+			&Grid::from_cells(10, 10, input.concat()),
+			&Params { steps: 10 },
+		);
+		assert_eq!(result, 204);
 	}
 
 	#[test]
@@ -167,7 +252,21 @@ mod tests {
 			[4, 8, 4, 6, 8, 4, 8, 5, 5, 4],
 			[5, 2, 8, 3, 7, 5, 1, 5, 2, 6],
 		];
-		let result = part2(&input);
+		let result = part2(&Grid::from_cells(10, 10, input.concat()));
 		assert_eq!(result, 195);
 	}
+
+	#[test]
+	fn visualization_ends_in_sync() {
+		let grid = parse_input("11111\n19991\n19191\n19991\n11111").unwrap();
+		let mut frames = vec![];
+		visualize(&grid, 2, &Params::puzzle(2), &mut frames);
+		assert_eq!(frames.len() as u32, part2(&grid) + 1);
+		assert!(frames.last().unwrap().iter().all(|cell| cell.symbol == '0'));
+		assert_eq!(frames[0][(1, 1)], Cell::new('9', [0, 90, 220]));
+
+		let mut frames = vec![];
+		visualize(&grid, 1, &Params { steps: 5 }, &mut frames);
+		assert_eq!(frames.len(), 6);
+	}
 }
@@ -1,17 +1,36 @@
+//! Day 12: Passage Pathing
+//!
+//! Counts the paths through a cave system, where small caves can only be
+//! visited a limited number of times.
+
+use crate::{
+	cancel,
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
 use std::collections::{HashMap, HashSet};
 
-pub fn parse_input(input: &str) -> Vec<(String, String)> {
+pub fn parse_input(input: &str) -> Result<Vec<(String, String)>, ParseError> {
+	let source = Source::new(Day12::DAY, input);
 	let mut edges: Vec<(String, String)> = Vec::new();
 
 	for line in input.lines() {
-		let (from, to) = line.split_once('-').unwrap();
+		let (from, to) = source.split_once(line, "-")?;
 		edges.push((from.into(), to.into()));
 	}
 
-	return edges;
+	// Every search starts at `start` and needs an `end` to finish
+	for &cave in &["start", "end"] {
+		if !edges.iter().any(|(from, to)| from == cave || to == cave) {
+			return Err(source.error(source.end(), format!("no passage to '{}'", cave)));
+		}
+	}
+
+	return Ok(edges);
 }
 
-fn edges_to_map(edges: &Vec<(String, String)>) -> HashMap<String, Vec<String>> {
+fn edges_to_map(edges: &[(String, String)]) -> HashMap<String, Vec<String>> {
 	let mut map = HashMap::new();
 
 	for line in edges {
@@ -37,17 +56,18 @@ fn can_be_appended_part1(path: &Vec<&str>, node: &str) -> bool {
 	node.to_uppercase() == node || !path.contains(&node)
 }
 
-pub fn part1(edges: &Vec<(String, String)>) -> usize {
+pub fn part1(edges: &[(String, String)]) -> usize {
 	let map = edges_to_map(edges);
 
 	let mut finished_paths: Vec<Vec<&str>> = Vec::new();
 
 	let mut unfinished_paths: Vec<Vec<&str>> = Vec::new();
 	unfinished_paths.push(vec!["start"]);
-	while unfinished_paths.len() > 0 {
+	while !unfinished_paths.is_empty() {
 		let mut new_paths = Vec::new();
 
 		for path in &mut unfinished_paths {
+			cancel::checkpoint();
 			for node in map.get(*path.last().unwrap()).unwrap() {
 				if can_be_appended_part1(path, node) {
 					let mut new_path = path.clone();
@@ -83,7 +103,7 @@ fn can_be_appended_part2(path: &Vec<&str>, node: &str) -> bool {
 	// node that will be added will be correct.
 	let mut uniq = HashSet::new();
 	if path
-		.into_iter()
+		.iter()
 		.all(move |x| x.to_lowercase() != *x || uniq.insert(x))
 	{
 		return true;
@@ -92,17 +112,18 @@ fn can_be_appended_part2(path: &Vec<&str>, node: &str) -> bool {
 	return !path.contains(&node);
 }
 
-pub fn part2(edges: &Vec<(String, String)>) -> usize {
+pub fn part2(edges: &[(String, String)]) -> usize {
 	let map = edges_to_map(edges);
 
 	let mut finished_paths: Vec<Vec<&str>> = Vec::new();
 
 	let mut unfinished_paths: Vec<Vec<&str>> = Vec::new();
 	unfinished_paths.push(vec!["start"]);
-	while unfinished_paths.len() > 0 {
+	while !unfinished_paths.is_empty() {
 		let mut new_paths = Vec::new();
 
 		for path in &mut unfinished_paths {
+			cancel::checkpoint();
 			for node in map.get(*path.last().unwrap()).unwrap() {
 				if can_be_appended_part2(path, node) {
 					let mut new_path = path.clone();
@@ -123,13 +144,99 @@ pub fn part2(edges: &Vec<(String, String)>) -> usize {
 	return finished_paths.len();
 }
 
+/// Makes up a connected cave system with `size` caves besides the start and the
+/// end. About a quarter of them are big, but big caves are never connected to
+/// each other, otherwise there would be endlessly many paths.
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let mut name_length = 2;
+	while 26usize.pow(name_length) < 2 * size {
+		name_length += 1;
+	}
+
+	let mut caves = vec!["start".to_string()];
+	let mut taken: HashSet<String> = ["start", "end"]
+		.iter()
+		.map(|name| name.to_string())
+		.collect();
+	while caves.len() <= size {
+		let name: String = (0..name_length)
+			.map(|_| (b'a' + rng.below(26) as u8) as char)
+			.collect();
+		if taken.insert(name.clone()) {
+			caves.push(if rng.chance(1, 4) {
+				name.to_uppercase()
+			} else {
+				name
+			});
+		}
+	}
+	caves.push("end".to_string());
+
+	let is_big = |cave: usize| caves[cave].to_uppercase() == caves[cave];
+	let mut edges = Vec::new();
+	let mut connected = HashSet::new();
+	let mut connect = |edges: &mut Vec<(usize, usize)>, a: usize, b: usize| {
+		if a != b && !(is_big(a) && is_big(b)) && connected.insert((a.min(b), a.max(b))) {
+			edges.push((a, b));
+		}
+	};
+
+	// Every cave is connected to one that came before it, which makes sure that
+	// everything can be reached from the start
+	for cave in 1..caves.len() {
+		let mut other = rng.below(cave);
+		while is_big(cave) && is_big(other) {
+			other = rng.below(cave);
+		}
+		connect(&mut edges, other, cave);
+	}
+	for _ in 0..size / 2 {
+		let (a, b) = (1 + rng.below(size), 1 + rng.below(size));
+		connect(&mut edges, a, b);
+	}
+
+	let mut lines: Vec<String> = edges
+		.iter()
+		.map(|(a, b)| format!("{}-{}", caves[*a], caves[*b]))
+		.collect();
+	rng.shuffle(&mut lines);
+	lines.join("\n") + "\n"
+}
+
+pub struct Day12;
+
+impl Solution for Day12 {
+	const DAY: u32 = 12;
+
+	type Input = Vec<(String, String)>;
+	type Output1 = usize;
+	type Output2 = usize;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
 	#[test]
 	fn part1_example() {
-		let cave_system = parse_input("start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end");
+		let cave_system = parse_input("start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end").unwrap();
 		let result = part1(&cave_system);
 		assert_eq!(result, 10);
 	}
@@ -138,21 +245,22 @@ mod tests {
 	fn part1_larger_example() {
 		let cave_system = parse_input(
 			"dc-end\nHN-start\nstart-kj\ndc-start\ndc-HN\nLN-dc\nHN-end\nkj-sa\nkj-HN\nkj-dc",
-		);
+		)
+		.unwrap();
 		let result = part1(&cave_system);
 		assert_eq!(result, 19);
 	}
 
 	#[test]
 	fn part1_largest_example() {
-		let cave_system = parse_input("fs-end\nhe-DX\nfs-he\nstart-DX\npj-DX\nend-zg\nzg-sl\nzg-pj\npj-he\nRW-he\nfs-DX\npj-RW\nzg-RW\nstart-pj\nhe-WI\nzg-he\npj-fs\nstart-RW");
+		let cave_system = parse_input("fs-end\nhe-DX\nfs-he\nstart-DX\npj-DX\nend-zg\nzg-sl\nzg-pj\npj-he\nRW-he\nfs-DX\npj-RW\nzg-RW\nstart-pj\nhe-WI\nzg-he\npj-fs\nstart-RW").unwrap();
 		let result = part1(&cave_system);
 		assert_eq!(result, 226);
 	}
 
 	#[test]
 	fn part2_example() {
-		let cave_system = parse_input("start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end");
+		let cave_system = parse_input("start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end").unwrap();
 		let result = part2(&cave_system);
 		assert_eq!(result, 36);
 	}
@@ -161,15 +269,30 @@ mod tests {
 	fn part2_larger_example() {
 		let cave_system = parse_input(
 			"dc-end\nHN-start\nstart-kj\ndc-start\ndc-HN\nLN-dc\nHN-end\nkj-sa\nkj-HN\nkj-dc",
-		);
+		)
+		.unwrap();
 		let result = part2(&cave_system);
 		assert_eq!(result, 103);
 	}
 
 	#[test]
 	fn part2_largest_example() {
-		let cave_system = parse_input("fs-end\nhe-DX\nfs-he\nstart-DX\npj-DX\nend-zg\nzg-sl\nzg-pj\npj-he\nRW-he\nfs-DX\npj-RW\nzg-RW\nstart-pj\nhe-WI\nzg-he\npj-fs\nstart-RW");
+		let cave_system = parse_input("fs-end\nhe-DX\nfs-he\nstart-DX\npj-DX\nend-zg\nzg-sl\nzg-pj\npj-he\nRW-he\nfs-DX\npj-RW\nzg-RW\nstart-pj\nhe-WI\nzg-he\npj-fs\nstart-RW").unwrap();
 		let result = part2(&cave_system);
 		assert_eq!(result, 3509);
 	}
+
+	#[test]
+	fn parse_error() {
+		let error = parse_input("start-A\nA").unwrap_err();
+		assert_eq!((error.line, error.column), (2, 1));
+		assert_eq!(
+			parse_input("").unwrap_err().message,
+			"no passage to 'start'"
+		);
+		assert_eq!(
+			parse_input("start-A").unwrap_err().message,
+			"no passage to 'end'"
+		);
+	}
 }
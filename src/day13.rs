@@ -1,41 +1,65 @@
+//! Day 13: Transparent Origami
+//!
+//! Folds a sheet of transparent paper covered in dots. The second answer is
+//! the code revealed by the dots, rendered as ASCII art.
+
+use crate::{
+	generate::Rng,
+	grid::Grid,
+	parse::{ParseError, Source},
+	solution::{Answer, Solution},
+	visualize::{Cell, Frame, FrameSink},
+};
 use std::collections::HashSet;
 
+/// Column and row of a dot on the transparent paper
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Dot(u32, u32);
+pub struct Dot(pub u32, pub u32);
 
+/// Fold along a vertical (`X`) or horizontal (`Y`) line
 #[derive(Debug)]
 pub enum Fold {
 	X(u32),
 	Y(u32),
 }
 
+/// The dots, followed by the folds in the order they are made
 pub struct InputData {
-	dots: Vec<Dot>,
-	folds: Vec<Fold>,
+	pub dots: Vec<Dot>,
+	pub folds: Vec<Fold>,
 }
 
-fn parse_dot(line: &str) -> Dot {
-	let (x, y) = line.split_once(',').unwrap();
-	return Dot(x.parse().unwrap(), y.parse().unwrap());
+fn parse_dot(source: &Source, line: &str) -> Result<Dot, ParseError> {
+	let (x, y) = source.split_once(line, ",")?;
+	return Ok(Dot(source.number(x)?, source.number(y)?));
 }
 
-fn parse_fold(line: &str) -> Fold {
-	let (axis, coordinate_str) = line.split_once('=').unwrap();
-	let coordinate = coordinate_str.parse().unwrap();
+fn parse_fold(source: &Source, line: &str) -> Result<Fold, ParseError> {
+	let (axis, coordinate_str) = source.split_once(line, "=")?;
+	let coordinate = source.number(coordinate_str)?;
 	match axis {
-		"fold along x" => Fold::X(coordinate),
-		"fold along y" => Fold::Y(coordinate),
-		_ => unreachable!("Unable to parse fold direction"),
+		"fold along x" => Ok(Fold::X(coordinate)),
+		"fold along y" => Ok(Fold::Y(coordinate)),
+		_ => Err(source.error(axis, format!("unknown fold direction '{}'", axis))),
 	}
 }
 
-pub fn parse_input(input: &str) -> InputData {
-	let (dots_section, folds_section) = input.split_once("\n\n").unwrap();
+pub fn parse_input(input: &str) -> Result<InputData, ParseError> {
+	let source = Source::new(Day13::DAY, input);
+	let (dots_section, folds_section) = input
+		.split_once("\n\n")
+		.ok_or_else(|| source.error(source.end(), "expected a blank line before the folds"))?;
 
-	let dots = dots_section.lines().map(parse_dot).collect();
-	let folds = folds_section.lines().map(parse_fold).collect();
+	let dots = dots_section
+		.lines()
+		.map(|line| parse_dot(&source, line))
+		.collect::<Result<_, _>>()?;
+	let folds = folds_section
+		.lines()
+		.map(|line| parse_fold(&source, line))
+		.collect::<Result<_, _>>()?;
 
-	return InputData { dots, folds };
+	return Ok(InputData { dots, folds });
 }
 
 fn perform_fold(dots: &HashSet<Dot>, fold: &Fold) -> HashSet<Dot> {
@@ -70,7 +94,7 @@ pub fn part1(input: &InputData) -> usize {
 		folded_dots.insert(dot.clone());
 	}
 	folded_dots = perform_fold(&folded_dots, &input.folds[0]);
-	folded_dots.len() as usize
+	folded_dots.len()
 }
 
 fn determine_dot_bounds(dots: &HashSet<Dot>) -> (u32, u32, u32, u32) {
@@ -89,21 +113,23 @@ fn determine_dot_bounds(dots: &HashSet<Dot>) -> (u32, u32, u32, u32) {
 	return (min_x, min_y, max_x, max_y);
 }
 
-fn render_dots(dots: &HashSet<Dot>) {
+fn render_dots(dots: &HashSet<Dot>) -> String {
+	let mut rendered = String::new();
 	let (min_x, min_y, max_x, max_y) = determine_dot_bounds(dots);
 	for y in min_y..=max_y {
 		for x in min_x..=max_x {
 			if dots.contains(&Dot(x, y)) {
-				print!("#");
+				rendered.push('#');
 			} else {
-				print!(".");
+				rendered.push('.');
 			}
 		}
-		print!("\n");
+		rendered.push('\n');
 	}
+	return rendered;
 }
 
-pub fn part2(input: &InputData) {
+pub fn part2(input: &InputData) -> String {
 	let mut folded_dots = HashSet::new();
 	for dot in &input.dots {
 		folded_dots.insert(dot.clone());
@@ -111,15 +137,136 @@ pub fn part2(input: &InputData) {
 	for fold in &input.folds {
 		folded_dots = perform_fold(&folded_dots, fold);
 	}
-	render_dots(&folded_dots);
+	render_dots(&folded_dots)
+}
+
+// Dots that don't fit on the sheet would be on a fold line, which doesn't
+// happen in the puzzle
+fn sheet_frame(dots: &HashSet<Dot>, width: u32, height: u32) -> Frame {
+	let mut frame = Grid::new(
+		width as usize,
+		height as usize,
+		Cell::new('.', [240, 240, 230]),
+	);
+	for dot in dots {
+		if let Some(cell) = frame.get_mut(dot.0 as i32, dot.1 as i32) {
+			*cell = Cell::new('#', [20, 20, 120]);
+		}
+	}
+	return frame;
+}
+
+/// The sheet before folding and after every fold of the part, each fold making
+/// the sheet smaller
+pub fn visualize(input: &InputData, part: u32, frames: &mut dyn FrameSink) {
+	let mut folded_dots: HashSet<Dot> = input.dots.iter().cloned().collect();
+	let mut width = input.dots.iter().map(|dot| dot.0 + 1).max().unwrap_or(0);
+	let mut height = input.dots.iter().map(|dot| dot.1 + 1).max().unwrap_or(0);
+	frames.push(
+		&sheet_frame(&folded_dots, width, height),
+		&[("dots", folded_dots.len().to_string())],
+	);
+
+	let fold_count = if part == 1 { 1 } else { input.folds.len() };
+	for fold in input.folds.iter().take(fold_count) {
+		folded_dots = perform_fold(&folded_dots, fold);
+		match fold {
+			Fold::X(x) => width = *x,
+			Fold::Y(y) => height = *y,
+		}
+		frames.push(
+			&sheet_frame(&folded_dots, width, height),
+			&[("dots", folded_dots.len().to_string())],
+		);
+	}
+}
+
+/// Works backwards from a folded sheet of 40x6: every dot starts out on it and
+/// gets mirrored over some of the folds. That way no dot is ever on a fold line.
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let (mut width, mut height) = (40, 6);
+	let mut folds = Vec::new();
+	for i in 0..6 {
+		if i % 2 == 1 {
+			folds.push(Fold::X(width));
+			width = width * 2 + 1;
+		} else {
+			folds.push(Fold::Y(height));
+			height = height * 2 + 1;
+		}
+	}
+	folds.reverse();
+
+	let mut dots = HashSet::new();
+	let mut lines = Vec::new();
+	while lines.len() < size.min((40 * 6) << folds.len()) {
+		let mut dot = Dot(rng.below(40) as u32, rng.below(6) as u32);
+		for fold in folds.iter().rev() {
+			if rng.chance(1, 2) {
+				match fold {
+					Fold::X(x) => dot.0 = 2 * x - dot.0,
+					Fold::Y(y) => dot.1 = 2 * y - dot.1,
+				}
+			}
+		}
+		if dots.insert(dot.clone()) {
+			lines.push(format!("{},{}", dot.0, dot.1));
+		}
+	}
+
+	lines.push(String::new());
+	for fold in folds {
+		lines.push(match fold {
+			Fold::X(x) => format!("fold along x={}", x),
+			Fold::Y(y) => format!("fold along y={}", y),
+		});
+	}
+	lines.join("\n") + "\n"
+}
+
+pub struct Day13;
+
+impl Solution for Day13 {
+	const DAY: u32 = 13;
+
+	type Input = InputData;
+	type Output1 = usize;
+	type Output2 = Answer;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		Answer::Rendering(part2(input))
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+
+	fn visualize(
+		input: &Self::Input,
+		part: u32,
+		_params: &Self::Params,
+		frames: &mut dyn FrameSink,
+	) -> bool {
+		visualize(input, part, frames);
+		true
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::visualize::render_text;
 
-	#[test]
-	fn part1_example() {
+	fn example() -> InputData {
 		let dots = vec![
 			Dot(6, 10),
 			Dot(0, 14),
@@ -141,7 +288,25 @@ mod tests {
 			Dot(9, 0),
 		];
 		let folds = vec![Fold::Y(7), Fold::X(5)];
-		let result = part1(&InputData { dots, folds });
+		InputData { dots, folds }
+	}
+
+	#[test]
+	fn part1_example() {
+		let result = part1(&example());
 		assert_eq!(result, 17);
 	}
+
+	#[test]
+	fn frame_for_every_fold() {
+		let mut frames = vec![];
+		visualize(&example(), 2, &mut frames);
+		assert_eq!(frames.len(), 3);
+		assert_eq!((frames[0].width(), frames[0].height()), (11, 15));
+		assert_eq!((frames[1].width(), frames[1].height()), (11, 7));
+		assert_eq!(
+			render_text(&frames[2]),
+			"#####\n#...#\n#...#\n#...#\n#####\n.....\n.....\n"
+		);
+	}
 }
@@ -1,72 +1,78 @@
+//! Day 14: Extended Polymerization
+//!
+//! Grows a polymer with pair insertion rules and measures how uneven the
+//! element counts end up.
+
+use crate::{
+	cancel,
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::{parse_param, unknown_param, PuzzleParams, Solution},
+};
 use std::collections::HashMap;
 
+/// The starting polymer and the element inserted between each pair
 pub struct InputData {
-	polymer_template: String,
-	rules: HashMap<[char; 2], char>,
+	pub polymer_template: String,
+	pub rules: HashMap<[char; 2], char>,
 }
 
-pub fn parse_input(input: &str) -> InputData {
-	let (polymer_template, rules_section) = input.split_once("\n\n").unwrap();
+pub fn parse_input(input: &str) -> Result<InputData, ParseError> {
+	let source = Source::new(Day14::DAY, input);
+	let (polymer_template, rules_section) = input
+		.split_once("\n\n")
+		.ok_or_else(|| source.error(source.end(), "expected a blank line before the rules"))?;
 	let mut rules = HashMap::new();
 	for line in rules_section.lines() {
-		let (pattern, expansion) = line.split_once(" -> ").unwrap();
-		rules.insert(
-			[
-				pattern.chars().nth(0).unwrap(),
-				pattern.chars().nth(1).unwrap(),
-			],
-			expansion.chars().nth(0).unwrap(),
-		);
+		let (pattern, expansion) = source.split_once(line, " -> ")?;
+		let pattern: Vec<char> = pattern.chars().collect();
+		if pattern.len() != 2 {
+			return Err(source.error(line, "expected a pattern of 2 elements"));
+		}
+		let mut expansion_chars = expansion.chars();
+		let element = match (expansion_chars.next(), expansion_chars.next()) {
+			(Some(element), None) => element,
+			_ => return Err(source.error(expansion, "expected a single element")),
+		};
+		rules.insert([pattern[0], pattern[1]], element);
 	}
-	return InputData {
+	return Ok(InputData {
 		polymer_template: polymer_template.into(),
 		rules,
-	};
+	});
 }
 
-fn naive_expand_polymer(polymer: Vec<char>, rules: &HashMap<[char; 2], char>) -> Vec<char> {
-	let mut new_polymer = Vec::new();
-	for i in 0..polymer.len() - 1 {
-		new_polymer.push(polymer[i]);
-		let pair = [polymer[i], polymer[i + 1]];
-		let rule = rules.get(&pair);
-		if rule != None {
-			new_polymer.push(*rule.unwrap());
+/// How many times the pair insertion rules are applied
+pub struct Params {
+	pub steps: u32,
+}
+
+impl PuzzleParams for Params {
+	fn puzzle(part: u32) -> Params {
+		Params {
+			steps: if part == 1 { 10 } else { 40 },
 		}
 	}
-	new_polymer.push(*polymer.last().unwrap());
-	return new_polymer;
-}
 
-pub fn part1(input: &InputData) -> u32 {
-	let mut polymer = input.polymer_template.chars().collect();
-	for _ in 0..10 {
-		polymer = naive_expand_polymer(polymer, &input.rules);
+	fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+		match name {
+			"steps" => self.steps = parse_param(name, value)?,
+			_ => return Err(unknown_param(name, &["steps"])),
+		}
+		Ok(())
 	}
+}
 
-	let mut element_amounts = HashMap::new();
-	for c in polymer {
-		let amount = element_amounts.entry(c).or_insert(0);
-		*amount += 1;
-	}
-
-	let least_common_element = element_amounts
-		.iter()
-		.min_by(|a, b| a.1.cmp(b.1))
-		.unwrap()
-		.1;
-	let most_common_element = element_amounts
-		.iter()
-		.max_by(|a, b| a.1.cmp(b.1))
-		.unwrap()
-		.1;
+fn most_minus_least_common(element_amounts: &HashMap<char, u64>) -> u64 {
+	let least_common_element = element_amounts.values().min().unwrap();
+	let most_common_element = element_amounts.values().max().unwrap();
 	return most_common_element - least_common_element;
 }
 
 fn expand_polymer(
 	polymer_pairs: &HashMap<[char; 2], u64>,
 	rules: &HashMap<[char; 2], char>,
-) -> HashMap<[char; 2], u64> {
+) -> Option<HashMap<[char; 2], u64>> {
 	let mut new_pairs = HashMap::new();
 	for entry in polymer_pairs {
 		let pair = entry.0;
@@ -74,60 +80,144 @@ fn expand_polymer(
 			let rule = *rules.get(pair).unwrap();
 
 			let left_pair = [pair[0], rule];
-			let left_entry = new_pairs.entry(left_pair).or_insert(0);
-			*left_entry += entry.1;
+			let left_entry = new_pairs.entry(left_pair).or_insert(0u64);
+			*left_entry = left_entry.checked_add(*entry.1)?;
 
 			let right_pair = [rule, pair[1]];
 			let right_entry = new_pairs.entry(right_pair).or_insert(0);
-			*right_entry += entry.1;
+			*right_entry = right_entry.checked_add(*entry.1)?;
 		} else {
 			let new_entry = new_pairs.entry(*entry.0).or_insert(0);
-			*new_entry = *entry.1;
+			*new_entry += *entry.1;
 		}
 	}
-	return new_pairs;
+	return Some(new_pairs);
 }
 
-pub fn part2(input: &InputData) -> u64 {
-	let polymer_template = &input.polymer_template;
+// Only keeps track of how many of each pair there are. Every element is the
+// first one of some pair, except for the last element of the polymer, which
+// stays the same no matter how many times the polymer is expanded. `None` once
+// the polymer gets longer than fits in a `u64`.
+fn count_elements(
+	template: &str,
+	rules: &HashMap<[char; 2], char>,
+	steps: u32,
+) -> Option<HashMap<char, u64>> {
+	let template: Vec<char> = template.chars().collect();
 	let mut polymer_pairs = HashMap::new();
-	for i in 0..polymer_template.len() - 1 {
-		let pair = [
-			polymer_template.chars().nth(i).unwrap(),
-			polymer_template.chars().nth(i + 1).unwrap(),
-		];
-		let entry = polymer_pairs.entry(pair).or_insert(0);
+	for pair in template.windows(2) {
+		let entry = polymer_pairs.entry([pair[0], pair[1]]).or_insert(0);
 		*entry += 1;
 	}
 
-	for _ in 0..40 {
-		polymer_pairs = expand_polymer(&polymer_pairs, &input.rules);
+	for _ in 0..steps {
+		cancel::checkpoint();
+		polymer_pairs = expand_polymer(&polymer_pairs, rules)?;
 	}
 
 	let mut element_amounts = HashMap::new();
-	for entry in polymer_pairs {
-		for c in entry.0 {
-			let amount = element_amounts.entry(c).or_insert(0);
-			*amount += entry.1;
+	for (pair, count) in polymer_pairs {
+		let amount = element_amounts.entry(pair[0]).or_insert(0u64);
+		*amount = amount.checked_add(count)?;
+	}
+	if let Some(last) = template.last() {
+		let amount = element_amounts.entry(*last).or_insert(0);
+		*amount = amount.checked_add(1)?;
+	}
+	return Some(element_amounts);
+}
+
+fn solve(input: &InputData, steps: u32) -> Result<u64, String> {
+	let element_amounts = count_elements(&input.polymer_template, &input.rules, steps)
+		.ok_or_else(|| format!("the polymer is too long to count after {} steps", steps))?;
+	return Ok(most_minus_least_common(&element_amounts));
+}
+
+pub fn part1(input: &InputData, params: &Params) -> Result<u64, String> {
+	return solve(input, params.steps);
+}
+
+pub fn part2(input: &InputData, params: &Params) -> Result<u64, String> {
+	return solve(input, params.steps);
+}
+
+/// Uses the same elements as the puzzle, with a rule for every pair of them
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let elements: Vec<char> = "BCFHKNOPSV".chars().collect();
+	let template: String = (0..size.max(2)).map(|_| *rng.choose(&elements)).collect();
+
+	let mut lines = vec![template, String::new()];
+	for a in &elements {
+		for b in &elements {
+			lines.push(format!("{}{} -> {}", a, b, rng.choose(&elements)));
 		}
 	}
+	lines.join("\n") + "\n"
+}
+
+pub struct Day14;
+
+impl Solution for Day14 {
+	const DAY: u32 = 14;
+
+	type Input = InputData;
+	type Output1 = Result<u64, String>;
+	type Output2 = Result<u64, String>;
+	type Params = Params;
 
-	let least_common_element = element_amounts
-		.iter()
-		.min_by(|a, b| a.1.cmp(b.1))
-		.unwrap()
-		.1;
-	let most_common_element = element_amounts
-		.iter()
-		.max_by(|a, b| a.1.cmp(b.1))
-		.unwrap()
-		.1;
-	return (most_common_element - least_common_element) / 2 + 1;
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, params: &Self::Params) -> Self::Output1 {
+		part1(input, params)
+	}
+
+	fn part2(input: &Self::Input, params: &Self::Params) -> Self::Output2 {
+		part2(input, params)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::differential::{check, compare, shrink_towards, shrink_vec};
+
+	fn naive_expand_polymer(polymer: Vec<char>, rules: &HashMap<[char; 2], char>) -> Vec<char> {
+		let mut new_polymer = Vec::new();
+		for i in 0..polymer.len() - 1 {
+			new_polymer.push(polymer[i]);
+			let pair = [polymer[i], polymer[i + 1]];
+			if let Some(rule) = rules.get(&pair) {
+				new_polymer.push(*rule);
+			}
+		}
+		new_polymer.push(*polymer.last().unwrap());
+		return new_polymer;
+	}
+
+	// Expands the whole polymer, which is only feasible for a few steps
+	fn count_elements_naive(
+		template: &str,
+		rules: &HashMap<[char; 2], char>,
+		steps: u32,
+	) -> HashMap<char, u64> {
+		let mut polymer = template.chars().collect();
+		for _ in 0..steps {
+			polymer = naive_expand_polymer(polymer, rules);
+		}
+
+		let mut element_amounts = HashMap::new();
+		for c in polymer {
+			let amount = element_amounts.entry(c).or_insert(0);
+			*amount += 1;
+		}
+		return element_amounts;
+	}
 
 	#[test]
 	fn part1_example() {
@@ -150,11 +240,14 @@ mod tests {
 			(['C', 'C'], 'N'),
 			(['C', 'N'], 'C'),
 		]);
-		let result = part1(&InputData {
-			polymer_template,
-			rules,
-		});
-		assert_eq!(result, 1588);
+		let result = part1(
+			&InputData {
+				polymer_template,
+				rules,
+			},
+			&Params::puzzle(1),
+		);
+		assert_eq!(result, Ok(1588));
 	}
 
 	#[test]
@@ -178,10 +271,70 @@ mod tests {
 			(['C', 'C'], 'N'),
 			(['C', 'N'], 'C'),
 		]);
-		let result = part2(&InputData {
-			polymer_template,
-			rules,
-		});
-		assert_eq!(result, 2188189693529);
+		let result = part2(
+			&InputData {
+				polymer_template,
+				rules,
+			},
+			&Params::puzzle(2),
+		);
+		assert_eq!(result, Ok(2188189693529));
+	}
+
+	// Rules are left out at random, so that some pairs are never expanded
+	#[test]
+	fn pair_counts_match_expansion() {
+		let elements = ['A', 'B', 'C'];
+		check(
+			14,
+			300,
+			|rng| {
+				let template: Vec<char> = (0..rng.range(1, 8))
+					.map(|_| *rng.choose(&elements))
+					.collect();
+				let mut rules = vec![];
+				for a in elements {
+					for b in elements {
+						if rng.chance(2, 3) {
+							rules.push(([a, b], *rng.choose(&elements)));
+						}
+					}
+				}
+				(template, rules, rng.range(0, 8))
+			},
+			|(template, rules, steps)| {
+				let mut candidates = vec![];
+				for smaller in shrink_vec(template, |_| vec![]) {
+					if !smaller.is_empty() {
+						candidates.push((smaller, rules.clone(), *steps));
+					}
+				}
+				for smaller in shrink_vec(rules, |_| vec![]) {
+					candidates.push((template.clone(), smaller, *steps));
+				}
+				for smaller in shrink_towards(*steps, 0) {
+					candidates.push((template.clone(), rules.clone(), smaller));
+				}
+				candidates
+			},
+			|(template, rules, steps)| {
+				let template: String = template.iter().collect();
+				let rules: HashMap<[char; 2], char> = rules.iter().copied().collect();
+				compare(
+					Some(count_elements_naive(&template, &rules, *steps as u32)),
+					count_elements(&template, &rules, *steps as u32),
+				)
+			},
+		);
+	}
+
+	#[test]
+	fn too_many_steps() {
+		let input = parse_input("NN\n\nNN -> N\n").unwrap();
+		assert_eq!(part1(&input, &Params { steps: 63 }), Ok(0));
+		assert_eq!(
+			part2(&input, &Params { steps: 1_000_000 }).unwrap_err(),
+			"the polymer is too long to count after 1000000 steps"
+		);
 	}
 }
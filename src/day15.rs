@@ -1,110 +1,89 @@
-use priority_queue::PriorityQueue;
-use std::collections::{HashMap, HashSet};
-
-#[derive(Debug)]
-pub struct Grid {
-	rows: u32,
-	cols: u32,
-	data: Vec<u32>,
-	scale: u32,
+//! Day 15: Chiton
+//!
+//! Finds the path of lowest total risk through a cave, and through the same
+//! cave tiled 5 times in each direction.
+
+use crate::{
+	generate::Rng,
+	grid::Grid,
+	parse::{ParseError, Source},
+	search::{astar, SearchResult},
+	solution::Solution,
+};
+
+pub fn parse_input(input: &str) -> Result<Grid<u32>, ParseError> {
+	let source = Source::new(Day15::DAY, input);
+	Grid::parse(&source, input, |c| c.to_digit(10))
 }
 
-impl Grid {
-	fn new(rows: u32, cols: u32, data: Vec<u32>) -> Grid {
-		return Grid {
-			rows,
-			cols,
-			data,
-			scale: 1,
-		};
-	}
-
-	fn get(&self, point: &(u32, u32)) -> u32 {
-		let row = point.0 % self.rows;
-		let col = point.1 % self.cols;
-		let value = self.data[(row * self.cols + col) as usize];
-		return (value + point.0 / self.rows + point.1 / self.cols - 1) % 9 + 1;
-	}
-
-	fn width(&self) -> u32 {
-		self.cols * self.scale
+// Repeats the cave `times` times in both directions, every repetition to the
+// right or down has its risk levels increased by one, wrapping back to 1 after 9
+fn tile_cave(grid: &Grid<u32>, times: usize) -> Grid<u32> {
+	let mut tiled = Grid::new(grid.width() * times, grid.height() * times, 0);
+	for (x, y) in tiled.positions() {
+		let value = grid[(x % grid.width(), y % grid.height())];
+		let increase = (x / grid.width() + y / grid.height()) as u32;
+		tiled[(x, y)] = (value + increase - 1) % 9 + 1;
 	}
+	return tiled;
+}
 
-	fn height(&self) -> u32 {
-		self.rows * self.scale
-	}
+/// Every risk level is at least 1, so the manhattan distance to the bottom
+/// right corner never overestimates the remaining risk
+pub fn find_safest_path(grid: &Grid<u32>) -> SearchResult<(usize, usize)> {
+	let goal = (grid.width() - 1, grid.height() - 1);
+	astar(
+		(0, 0),
+		|&(x, y)| {
+			grid
+				.neighbours4(x, y)
+				.map(|neighbour| (neighbour, grid[neighbour]))
+		},
+		|&(x, y)| ((goal.0 - x) + (goal.1 - y)) as u32,
+		|position| *position == goal,
+	)
+	.expect("The bottom right corner is always reachable")
+}
 
-	fn within_bounds(&self, point: &(i32, i32)) -> bool {
-		point.0 >= 0 && point.1 >= 0 && point.0 < self.height() as i32 && point.1 < self.width() as i32
-	}
+pub fn part1(grid: &Grid<u32>) -> u32 {
+	find_safest_path(grid).cost
 }
 
-pub fn parse_input(input: &str) -> Grid {
-	let mut data = Vec::new();
-	let mut rows = 0;
-	let mut cols = 0;
-	for line in input.lines() {
-		rows += 1;
-		for c in line.chars() {
-			if rows == 1 {
-				cols += 1
-			}
-			data.push(c.to_digit(10).unwrap());
-		}
-	}
-	return Grid::new(rows, cols, data);
+pub fn part2(grid: &Grid<u32>) -> u32 {
+	find_safest_path(&tile_cave(grid, 5)).cost
 }
 
-fn find_neighbours(pos: &(u32, u32), grid: &Grid, offsets: &[(i32, i32)]) -> Vec<(u32, u32)> {
-	let mut neighbours = Vec::new();
-	for offset in offsets {
-		let row = pos.0 as i32 + offset.0;
-		let col = pos.1 as i32 + offset.1;
-		if grid.within_bounds(&(row, col)) {
-			neighbours.push((row as u32, col as u32));
-		}
-	}
-	return neighbours;
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let side = size.max(1);
+	let risks = (0..side * side).map(|_| rng.range(1, 9) as u32).collect();
+	Grid::from_cells(side, side, risks).render(|risk| std::char::from_digit(*risk, 10).unwrap())
 }
 
-fn find_shortest_path_cost(grid: &Grid) -> u32 {
-	let mut total_costs: HashMap<(u32, u32), u32> = HashMap::new();
-	let mut min_pq: PriorityQueue<(u32, u32), i32> = PriorityQueue::new();
-	let mut visited: HashSet<(u32, u32)> = HashSet::new();
-	let neighbour_offsets = [(0, 1), (0, -1), (1, 0), (-1, 0)];
-	min_pq.push((0, 0), 0);
-	total_costs.insert((0, 0), 0);
-
-	while !min_pq.is_empty() {
-		let new_smallest = min_pq.pop().unwrap().0;
-		visited.insert(new_smallest);
-
-		for neighbour in find_neighbours(&new_smallest, grid, &neighbour_offsets) {
-			if visited.contains(&neighbour) {
-				continue;
-			}
-
-			let alt_distance = grid.get(&neighbour);
-			let alt_path = total_costs.get(&new_smallest).unwrap_or(&u32::MAX) + alt_distance;
-			if alt_path < *total_costs.get(&neighbour).unwrap_or(&u32::MAX) {
-				total_costs.insert(neighbour, alt_path);
-				min_pq.push_decrease(neighbour, -(alt_path as i32));
-			}
-		}
+pub struct Day15;
+
+impl Solution for Day15 {
+	const DAY: u32 = 15;
+
+	type Input = Grid<u32>;
+	type Output1 = u32;
+	type Output2 = u32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
 	}
 
-	return *total_costs
-		.get(&(grid.height() - 1, grid.width() - 1))
-		.unwrap();
-}
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
 
-pub fn part1(grid: &Grid) -> u32 {
-	find_shortest_path_cost(grid)
-}
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
 
-pub fn part2(grid: &mut Grid) -> u32 {
-	grid.scale = 5;
-	find_shortest_path_cost(grid)
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
 }
 
 #[cfg(test)]
@@ -124,14 +103,15 @@ mod tests {
 3125421639
 1293138521
 2311944581",
-		);
+		)
+		.unwrap();
 		let result = part1(&grid);
 		assert_eq!(result, 40);
 	}
 
 	#[test]
 	fn part2_example() {
-		let mut grid = parse_input(
+		let grid = parse_input(
 			"1163751742
 1381373672
 2136511328
@@ -142,8 +122,9 @@ mod tests {
 3125421639
 1293138521
 2311944581",
-		);
-		let result = part2(&mut grid);
+		)
+		.unwrap();
+		let result = part2(&grid);
 		assert_eq!(result, 315);
 	}
 }
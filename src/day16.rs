@@ -1,12 +1,31 @@
+//! Day 16: Packet Decoder
+//!
+//! Decodes a hexadecimal BITS transmission into a tree of [`Packet`]s and
+//! evaluates the expression it describes.
+
+use crate::{
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
+
+/// Either a number or the sub-packets of an operator
 pub enum PacketBody {
 	Literal(u64),
 	Operator(Vec<Packet>),
 }
 
+/// A decoded packet, with all of its sub-packets
 pub struct Packet {
-	version: u8,
-	r#type: u8,
-	body: PacketBody,
+	pub version: u8,
+	/// 4 for literals, every other type is an operator
+	pub r#type: u8,
+	pub body: PacketBody,
+}
+
+enum PacketError {
+	Truncated,
+	LiteralOverflow,
 }
 
 fn to_bits(hex: &str) -> String {
@@ -21,79 +40,98 @@ fn to_bits(hex: &str) -> String {
 	return bits;
 }
 
-fn parse_literal_body(bits_str: &str) -> (PacketBody, u32) {
+// Sub-packets are always parsed from the remainder of the bits, so running out
+// of them means the transmission was cut short.
+fn read_bits(bits: &str, start: usize, end: usize) -> Result<&str, PacketError> {
+	bits.get(start..end).ok_or(PacketError::Truncated)
+}
+
+fn read_number(bits: &str, start: usize, end: usize) -> Result<u32, PacketError> {
+	Ok(u32::from_str_radix(read_bits(bits, start, end)?, 2).unwrap())
+}
+
+fn parse_literal_body(bits_str: &str) -> Result<(PacketBody, u32), PacketError> {
 	let mut value_bits = String::new();
 	let mut cursor = 0;
-	let bytes = bits_str.as_bytes();
 	loop {
-		value_bits.push_str(&bits_str[cursor + 1..cursor + 5]);
-		if bytes[cursor] == b'0' {
+		value_bits.push_str(read_bits(bits_str, cursor + 1, cursor + 5)?);
+		if read_bits(bits_str, cursor, cursor + 1)? == "0" {
 			break;
 		}
 		cursor += 5;
 	}
-	let value = u64::from_str_radix(&value_bits, 2).unwrap();
+	let value = u64::from_str_radix(&value_bits, 2).map_err(|_| PacketError::LiteralOverflow)?;
 
-	return (PacketBody::Literal(value), (cursor + 5) as u32);
+	return Ok((PacketBody::Literal(value), (cursor + 5) as u32));
 }
 
-fn parse_operator_body(bits_str: &str) -> (PacketBody, u32) {
+fn parse_operator_body(bits_str: &str) -> Result<(PacketBody, u32), PacketError> {
 	let mut size: usize = 0;
 	let mut packets = Vec::new();
-	let bytes = bits_str.as_bytes();
 	size += 1;
-	if bytes[0] == b'1' {
+	if read_bits(bits_str, 0, 1)? == "1" {
 		size += 11;
-		let count = u32::from_str_radix(&bits_str[1..12], 2).unwrap();
+		let count = read_number(bits_str, 1, 12)?;
 		for _ in 0..count {
-			let (packet, s) = parse_packet(&bits_str[size..]);
+			let (packet, s) = parse_packet(&bits_str[size..])?;
 			packets.push(packet);
 			size += s as usize;
 		}
 	} else {
-		let total_size = u32::from_str_radix(&bits_str[1..16], 2).unwrap();
+		let total_size = read_number(bits_str, 1, 16)?;
 		size += 15;
 		while ((size - 16) as u32) < total_size {
-			let (packet, s) = parse_packet(&bits_str[size..]);
+			let (packet, s) = parse_packet(&bits_str[size..])?;
 			packets.push(packet);
 			size += s as usize;
 		}
 	}
-	return (PacketBody::Operator(packets), size as u32);
+	return Ok((PacketBody::Operator(packets), size as u32));
 }
 
-fn parse_packet(bits: &str) -> (Packet, u32) {
-	let r#type = u8::from_str_radix(&bits[3..6], 2).unwrap();
+fn parse_packet(bits: &str) -> Result<(Packet, u32), PacketError> {
+	let version = read_number(bits, 0, 3)? as u8;
+	let r#type = read_number(bits, 3, 6)? as u8;
 	let (body, body_size) = match r#type {
-		4 => parse_literal_body(&bits[6..]),
-		_ => parse_operator_body(&bits[6..]),
+		4 => parse_literal_body(&bits[6..])?,
+		_ => parse_operator_body(&bits[6..])?,
 	};
 
-	return (
+	return Ok((
 		Packet {
-			version: u8::from_str_radix(&bits[0..3], 2).unwrap(),
+			version,
 			r#type,
 			body,
 		},
 		body_size + 6,
-	);
+	));
 }
 
-pub fn parse_input(input: &str) -> Packet {
-	let (packet, _) = parse_packet(&to_bits(input));
-	return packet;
+pub fn parse_input(input: &str) -> Result<Packet, ParseError> {
+	let source = Source::new(Day16::DAY, input);
+	let hex = input.trim_end();
+	if let Some((i, c)) = hex
+		.char_indices()
+		.find(|(_, c)| !c.is_ascii_hexdigit() || c.is_ascii_lowercase())
+	{
+		return Err(source.error(&hex[i..], format!("invalid hex digit '{}'", c)));
+	}
+	match parse_packet(&to_bits(hex)) {
+		Ok((packet, _)) => Ok(packet),
+		Err(PacketError::Truncated) => Err(source.error(&hex[hex.len()..], "packet is truncated")),
+		Err(PacketError::LiteralOverflow) => {
+			Err(source.error(hex, "literal value does not fit in 64 bits"))
+		}
+	}
 }
 
 fn sum_packet_versions(packet: &Packet) -> u32 {
 	let mut sum: u32 = packet.version.into();
-	match &packet.body {
-		PacketBody::Operator(packets) => {
-			for sub_packet in packets {
-				sum += sum_packet_versions(sub_packet);
-			}
+	if let PacketBody::Operator(packets) = &packet.body {
+		for sub_packet in packets {
+			sum += sum_packet_versions(sub_packet);
 		}
-		_ => (),
-	};
+	}
 	return sum;
 }
 
@@ -130,7 +168,7 @@ fn eval_maximum_packets(packets: &[Packet]) -> u64 {
 	return max;
 }
 fn eval_greater_packets(packets: &[Packet]) -> u64 {
-	let first_packet = packets.get(0).unwrap();
+	let first_packet = packets.first().unwrap();
 	let second_packet = packets.get(1).unwrap();
 	if eval_packet(first_packet) > eval_packet(second_packet) {
 		1
@@ -139,7 +177,7 @@ fn eval_greater_packets(packets: &[Packet]) -> u64 {
 	}
 }
 fn eval_less_packets(packets: &[Packet]) -> u64 {
-	let first_packet = packets.get(0).unwrap();
+	let first_packet = packets.first().unwrap();
 	let second_packet = packets.get(1).unwrap();
 	if eval_packet(first_packet) < eval_packet(second_packet) {
 		1
@@ -148,7 +186,7 @@ fn eval_less_packets(packets: &[Packet]) -> u64 {
 	}
 }
 fn eval_equal_packets(packets: &[Packet]) -> u64 {
-	let first_packet = packets.get(0).unwrap();
+	let first_packet = packets.first().unwrap();
 	let second_packet = packets.get(1).unwrap();
 	if eval_packet(first_packet) == eval_packet(second_packet) {
 		1
@@ -177,91 +215,221 @@ pub fn part2(packet: &Packet) -> u64 {
 	eval_packet(packet)
 }
 
+// Makes up the bits of a packet containing `count` packets in total, itself
+// included. Returns the value of the packet too, so that operators which would
+// overflow can be avoided.
+fn generate_packet(rng: &mut Rng, count: usize) -> (String, u64) {
+	let mut bits = format!("{:03b}", rng.below(8));
+	if count <= 1 {
+		let value = rng.next_u64() >> (32 + rng.below(32));
+		let digits = format!("{:b}", value);
+		let digits = format!("{:0>width$}", digits, width = digits.len().div_ceil(4) * 4);
+		bits.push_str("100");
+		// Groups of 4 bits, every one but the last starting with a 1
+		let group_count = digits.len() / 4;
+		for (i, group) in digits.as_bytes().chunks(4).enumerate() {
+			bits.push(if i + 1 == group_count { '0' } else { '1' });
+			bits.push_str(std::str::from_utf8(group).unwrap());
+		}
+		return (bits, value);
+	}
+
+	let child_count = rng.range(1, (count - 1).min(4) as i32) as usize;
+	let mut child_sizes = vec![1; child_count];
+	for _ in child_count..count - 1 {
+		child_sizes[rng.below(child_count)] += 1;
+	}
+	let mut children_bits = String::new();
+	let mut values = Vec::new();
+	for child_size in child_sizes {
+		let (child_bits, value) = generate_packet(rng, child_size);
+		children_bits.push_str(&child_bits);
+		values.push(value);
+	}
+
+	let sum = values
+		.iter()
+		.try_fold(0u64, |sum, value| sum.checked_add(*value));
+	let product = values
+		.iter()
+		.try_fold(1u64, |product, value| product.checked_mul(*value));
+	let mut types = vec![
+		(2, *values.iter().min().unwrap()),
+		(3, *values.iter().max().unwrap()),
+	];
+	if let Some(sum) = sum {
+		types.push((0, sum));
+	}
+	if let Some(product) = product {
+		types.push((1, product));
+	}
+	if let [a, b] = values[..] {
+		types.push((5, (a > b) as u64));
+		types.push((6, (a < b) as u64));
+		types.push((7, (a == b) as u64));
+	}
+	let (r#type, value) = *rng.choose(&types);
+
+	bits.push_str(&format!("{:03b}", r#type));
+	if rng.chance(1, 2) && children_bits.len() < 1 << 15 {
+		bits.push_str(&format!("0{:015b}", children_bits.len()));
+	} else {
+		bits.push_str(&format!("1{:011b}", child_count));
+	}
+	bits.push_str(&children_bits);
+	(bits, value)
+}
+
+/// The transmission is padded with zeros to whole hex digits
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let (mut bits, _) = generate_packet(rng, size.max(1));
+	while bits.len() % 4 != 0 {
+		bits.push('0');
+	}
+	let hex: String = bits
+		.as_bytes()
+		.chunks(4)
+		.map(|digit| {
+			format!(
+				"{:X}",
+				u8::from_str_radix(std::str::from_utf8(digit).unwrap(), 2).unwrap()
+			)
+		})
+		.collect();
+	hex + "\n"
+}
+
+pub struct Day16;
+
+impl Solution for Day16 {
+	const DAY: u32 = 16;
+
+	type Input = Packet;
+	type Output1 = u32;
+	type Output2 = u64;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
 	#[test]
 	fn part1_example_1() {
-		let packet = parse_input("8A004A801A8002F478");
+		let packet = parse_input("8A004A801A8002F478").unwrap();
 		let result = part1(&packet);
 		assert_eq!(result, 16);
 	}
 
 	#[test]
 	fn part1_example_2() {
-		let packet = parse_input("620080001611562C8802118E34");
+		let packet = parse_input("620080001611562C8802118E34").unwrap();
 		let result = part1(&packet);
 		assert_eq!(result, 12);
 	}
 
 	#[test]
 	fn part1_example_3() {
-		let packet = parse_input("C0015000016115A2E0802F182340");
+		let packet = parse_input("C0015000016115A2E0802F182340").unwrap();
 		let result = part1(&packet);
 		assert_eq!(result, 23);
 	}
 
 	#[test]
 	fn part1_example_4() {
-		let packet = parse_input("A0016C880162017C3686B18A3D4780");
+		let packet = parse_input("A0016C880162017C3686B18A3D4780").unwrap();
 		let result = part1(&packet);
 		assert_eq!(result, 31);
 	}
 
 	#[test]
 	fn part2_example_1() {
-		let packet = parse_input("C200B40A82");
+		let packet = parse_input("C200B40A82").unwrap();
 		let result = part2(&packet);
 		assert_eq!(result, 3);
 	}
 
 	#[test]
 	fn part2_example_2() {
-		let packet = parse_input("04005AC33890");
+		let packet = parse_input("04005AC33890").unwrap();
 		let result = part2(&packet);
 		assert_eq!(result, 54);
 	}
 
 	#[test]
 	fn part2_example_3() {
-		let packet = parse_input("880086C3E88112");
+		let packet = parse_input("880086C3E88112").unwrap();
 		let result = part2(&packet);
 		assert_eq!(result, 7);
 	}
 
 	#[test]
 	fn part2_example_4() {
-		let packet = parse_input("CE00C43D881120");
+		let packet = parse_input("CE00C43D881120").unwrap();
 		let result = part2(&packet);
 		assert_eq!(result, 9);
 	}
 
 	#[test]
 	fn part2_example_5() {
-		let packet = parse_input("D8005AC2A8F0");
+		let packet = parse_input("D8005AC2A8F0").unwrap();
 		let result = part2(&packet);
 		assert_eq!(result, 1);
 	}
 
 	#[test]
 	fn part2_example_6() {
-		let packet = parse_input("F600BC2D8F");
+		let packet = parse_input("F600BC2D8F").unwrap();
 		let result = part2(&packet);
 		assert_eq!(result, 0);
 	}
 
 	#[test]
 	fn part2_example_7() {
-		let packet = parse_input("9C005AC2F8F0");
+		let packet = parse_input("9C005AC2F8F0").unwrap();
 		let result = part2(&packet);
 		assert_eq!(result, 0);
 	}
 
 	#[test]
 	fn part2_example_8() {
-		let packet = parse_input("9C0141080250320F1802104A08");
+		let packet = parse_input("9C0141080250320F1802104A08").unwrap();
 		let result = part2(&packet);
 		assert_eq!(result, 1);
 	}
+
+	#[test]
+	fn parse_error() {
+		let error = parse_input("8A0G").err().unwrap();
+		assert_eq!(error.column, 4);
+		let error = parse_input("8A004A80").err().unwrap();
+		assert_eq!(error.message, "packet is truncated");
+		assert_eq!(error.column, 9);
+	}
+
+	#[test]
+	fn generated_packet_values() {
+		for seed in 0..20 {
+			let (bits, value) = generate_packet(&mut Rng::new(seed), 25);
+			let (packet, length) = parse_packet(&bits).ok().unwrap();
+			assert_eq!(length as usize, bits.len());
+			assert_eq!(part2(&packet), value);
+		}
+	}
 }
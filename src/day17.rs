@@ -1,27 +1,34 @@
+//! Day 17: Trick Shot
+//!
+//! Searches for initial velocities that land a probe in the target area.
+
+use crate::{
+	generate::Rng,
+	parallel,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
+
+/// The target area, both ends of the ranges inclusive
 pub struct Rect {
-	x0: i32,
-	x1: i32,
-	y0: i32,
-	y1: i32,
+	pub x0: i32,
+	pub x1: i32,
+	pub y0: i32,
+	pub y1: i32,
 }
 
-pub fn parse_input(input: &str) -> Rect {
-	let (x_part, y_part) = input
-		.strip_suffix("\n")
-		.or(Some(input))
-		.unwrap()
-		.strip_prefix("target area: ")
-		.unwrap()
-		.split_once(", ")
-		.unwrap();
-	let (x0, x1) = x_part[2..].split_once("..").unwrap();
-	let (y0, y1) = y_part[2..].split_once("..").unwrap();
-	return Rect {
-		x0: x0.parse().unwrap(),
-		x1: x1.parse().unwrap(),
-		y0: y0.parse().unwrap(),
-		y1: y1.parse().unwrap(),
-	};
+pub fn parse_input(input: &str) -> Result<Rect, ParseError> {
+	let source = Source::new(Day17::DAY, input);
+	let line = input.strip_suffix('\n').unwrap_or(input);
+	let (x_part, y_part) = source.split_once(source.strip_prefix(line, "target area: ")?, ", ")?;
+	let (x0, x1) = source.split_once(source.strip_prefix(x_part, "x=")?, "..")?;
+	let (y0, y1) = source.split_once(source.strip_prefix(y_part, "y=")?, "..")?;
+	return Ok(Rect {
+		x0: source.number(x0)?,
+		x1: source.number(x1)?,
+		y0: source.number(y0)?,
+		y1: source.number(y1)?,
+	});
 }
 
 fn sign(x: i32) -> i32 {
@@ -76,16 +83,64 @@ pub fn part1(target: &Rect) -> i32 {
 	return maxy;
 }
 
+/// The x velocities are spread over the threads
 pub fn part2(target: &Rect) -> i32 {
-	let mut count = 0;
-	for vx in 0..=target.x1 {
+	let x_velocities: Vec<i32> = (0..=target.x1).collect();
+	let counts = parallel::map(&x_velocities, |vx| {
+		let mut count = 0;
 		for vy in target.y0..-target.y0 {
-			if simulate(target, vx, vy) >= 0 {
+			if simulate(target, *vx, vy) >= 0 {
 				count += 1;
 			}
 		}
+		count
+	});
+	return counts.iter().sum();
+}
+
+/// The target is to the right and below the launcher, and there is always an
+/// x velocity that makes the probe stop above it, which part 1 relies on
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let size = size.max(2) as i32;
+	let distance = rng.range(size, 2 * size);
+	let mut stop = 0;
+	for velocity in 1.. {
+		stop += velocity;
+		if stop >= distance {
+			break;
+		}
+	}
+	let (x0, x1) = (stop - rng.range(0, size / 2), stop + rng.range(0, size / 2));
+	let y1 = -rng.range(size / 2, size);
+	let y0 = y1 - rng.range(0, size / 2);
+	format!("target area: x={}..{}, y={}..{}\n", x0, x1, y0, y1)
+}
+
+pub struct Day17;
+
+impl Solution for Day17 {
+	const DAY: u32 = 17;
+
+	type Input = Rect;
+	type Output1 = i32;
+	type Output2 = i32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
 	}
-	return count;
 }
 
 #[cfg(test)]
@@ -94,14 +149,14 @@ mod tests {
 
 	#[test]
 	fn part1_example() {
-		let target = parse_input("target area: x=20..30, y=-10..-5");
+		let target = parse_input("target area: x=20..30, y=-10..-5").unwrap();
 		let result = part1(&target);
 		assert_eq!(result, 45);
 	}
 
 	#[test]
 	fn part2_example() {
-		let target = parse_input("target area: x=20..30, y=-10..-5");
+		let target = parse_input("target area: x=20..30, y=-10..-5").unwrap();
 		let result = part2(&target);
 		assert_eq!(result, 112);
 	}
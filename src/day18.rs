@@ -1,20 +1,33 @@
+//! Day 18: Snailfish
+//!
+//! Adds up snailfish numbers. Each number is stored flattened as a list of
+//! `(value, depth)` pairs instead of a tree.
+
 // Solution gotten from: https://github.com/AxlLind/AdventOfCode2021/blob/main/src/bin/18.rs
 
-fn parse_snailfish(line: &str) -> Vec<(i32, u8)> {
+use crate::{
+	generate::Rng,
+	parallel,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
+
+fn parse_snailfish(source: &Source, line: &str) -> Result<Vec<(i32, u8)>, ParseError> {
 	let mut depth = 0;
 	let mut nums = Vec::new();
-	for c in line.chars() {
+	for (i, c) in line.char_indices() {
 		match c {
 			'[' => depth += 1,
 			']' => depth -= 1,
 			',' => {}
-			_ => nums.push(((c as u8 - b'0') as i32, depth)),
+			'0'..='9' => nums.push(((c as u8 - b'0') as i32, depth)),
+			_ => return Err(source.error(&line[i..], format!("unexpected character '{}'", c))),
 		}
 	}
-	return nums;
+	return Ok(nums);
 }
 
-fn find_deepest_index(num: &Vec<(i32, u8)>) -> usize {
+fn find_deepest_index(num: &[(i32, u8)]) -> usize {
 	let mut deepest_index = 0;
 	let mut deepest_depth = 0;
 	for i in 0..num.len() {
@@ -27,15 +40,16 @@ fn find_deepest_index(num: &Vec<(i32, u8)>) -> usize {
 	return deepest_index;
 }
 
-pub fn parse_input(input: &str) -> Vec<Vec<(i32, u8)>> {
+pub fn parse_input(input: &str) -> Result<Vec<Vec<(i32, u8)>>, ParseError> {
+	let source = Source::new(Day18::DAY, input);
 	let mut nums = Vec::new();
 	for line in input.lines() {
-		nums.push(parse_snailfish(line));
+		nums.push(parse_snailfish(&source, line)?);
 	}
-	return nums;
+	return Ok(nums);
 }
 
-fn add_snailfish(a: &Vec<(i32, u8)>, b: &Vec<(i32, u8)>) -> Vec<(i32, u8)> {
+fn add_snailfish(a: &[(i32, u8)], b: &[(i32, u8)]) -> Vec<(i32, u8)> {
 	let mut added = Vec::new();
 	for (num, depth) in a {
 		added.push((*num, depth + 1));
@@ -97,13 +111,13 @@ fn get_magnitude(mut num: Vec<(i32, u8)>) -> i32 {
 	num[0].0
 }
 
-fn add_and_reduce(a: &Vec<(i32, u8)>, b: &Vec<(i32, u8)>) -> Vec<(i32, u8)> {
+fn add_and_reduce(a: &[(i32, u8)], b: &[(i32, u8)]) -> Vec<(i32, u8)> {
 	let mut result = add_snailfish(a, b);
 	reduce_snailfish(&mut result);
 	return result;
 }
 
-fn sum(nums: &Vec<Vec<(i32, u8)>>) -> Vec<(i32, u8)> {
+fn sum(nums: &[Vec<(i32, u8)>]) -> Vec<(i32, u8)> {
 	let mut result = nums[0].clone();
 	for i in 1..nums.len() {
 		result = add_snailfish(&result, &nums[i]);
@@ -112,22 +126,71 @@ fn sum(nums: &Vec<Vec<(i32, u8)>>) -> Vec<(i32, u8)> {
 	return result;
 }
 
-pub fn part1(nums: &Vec<Vec<(i32, u8)>>) -> i32 {
+pub fn part1(nums: &[Vec<(i32, u8)>]) -> i32 {
 	return get_magnitude(sum(nums));
 }
 
-pub fn part2(nums: &Vec<Vec<(i32, u8)>>) -> i32 {
-	let mut max_magnitude = 0;
+/// The numbers are spread over the threads, each one gets added to all of the others
+pub fn part2(nums: &[Vec<(i32, u8)>]) -> i32 {
 	let n = nums.len();
-	for i in 0..n {
+	let max_magnitudes = parallel::map(nums, |a| {
+		let mut max_magnitude = 0;
 		for j in 0..n - 1 {
-			let a = &nums[i];
 			let b = &nums[j];
 			max_magnitude = max_magnitude.max(get_magnitude(add_and_reduce(a, b)));
 			max_magnitude = max_magnitude.max(get_magnitude(add_and_reduce(b, a)));
 		}
+		max_magnitude
+	});
+	return max_magnitudes.into_iter().max().unwrap_or(0);
+}
+
+// Pairs are nested at most 4 deep, like in a reduced number
+fn generate_snailfish(rng: &mut Rng, depth: u8) -> String {
+	let element = |rng: &mut Rng| {
+		if depth == 4 || rng.chance(1, 3) {
+			rng.below(10).to_string()
+		} else {
+			generate_snailfish(rng, depth + 1)
+		}
+	};
+	let left = element(rng);
+	let right = element(rng);
+	format!("[{},{}]", left, right)
+}
+
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let nums: Vec<String> = (0..size.max(2))
+		.map(|_| generate_snailfish(rng, 1))
+		.collect();
+	nums.join("\n") + "\n"
+}
+
+pub struct Day18;
+
+impl Solution for Day18 {
+	const DAY: u32 = 18;
+
+	type Input = Vec<Vec<(i32, u8)>>;
+	type Output1 = i32;
+	type Output2 = i32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
 	}
-	return max_magnitude;
 }
 
 #[cfg(test)]
@@ -147,7 +210,8 @@ mod tests {
 [[9,3],[[9,9],[6,[4,9]]]]
 [[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
 [[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]",
-		);
+		)
+		.unwrap();
 		let result = part1(&nums);
 		assert_eq!(result, 4140);
 	}
@@ -165,15 +229,20 @@ mod tests {
 [[9,3],[[9,9],[6,[4,9]]]]
 [[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
 [[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]",
-		);
+		)
+		.unwrap();
 		let result = part2(&nums);
 		assert_eq!(result, 3993);
 	}
 
+	fn snailfish(line: &str) -> Vec<(i32, u8)> {
+		parse_snailfish(&Source::new(Day18::DAY, line), line).unwrap()
+	}
+
 	fn test_explosion(initial: &str, expected: &str) {
-		let mut num = parse_snailfish(initial);
+		let mut num = snailfish(initial);
 		try_exploding(&mut num);
-		assert_eq!(num, parse_snailfish(expected));
+		assert_eq!(num, snailfish(expected));
 	}
 
 	#[test]
@@ -206,9 +275,9 @@ mod tests {
 	fn test_sum(nums: Vec<&str>, expected: &str) {
 		let mut parsed_nums = Vec::new();
 		for num in nums {
-			parsed_nums.push(parse_snailfish(num));
+			parsed_nums.push(snailfish(num));
 		}
-		assert_eq!(sum(&parsed_nums), parse_snailfish(expected));
+		assert_eq!(sum(&parsed_nums), snailfish(expected));
 	}
 
 	#[test]
@@ -247,7 +316,7 @@ mod tests {
 	}
 
 	fn test_magnitude(initial: &str, expected: i32) {
-		let num = parse_snailfish(initial);
+		let num = snailfish(initial);
 		assert_eq!(get_magnitude(num), expected);
 	}
 
@@ -1,7 +1,18 @@
+//! Day 19: Beacon Scanner
+//!
+//! Lines up scanners that each see a part of the beacons, by trying every
+//! [`Rotation`] until enough beacons overlap.
+
+use crate::{
+	generate::Rng,
+	parallel,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
 use std::{ops::{Sub, Mul, Add, Neg}, collections::{HashMap, HashSet}, vec, fmt};
 
-// https://i.imgur.com/Ff1vGT9.png
-const ROTATIONS: [Rotation; 24] = [
+/// All 24 orientations, see <https://i.imgur.com/Ff1vGT9.png>
+pub const ROTATIONS: [Rotation; 24] = [
  	Rotation( 1, 0, 0, 0, 1, 0, 0, 0, 1), // (x ,y ,z)
  	Rotation( 1, 0, 0, 0, 0, 1, 0,-1, 0), // (x ,z ,-y)
  	Rotation( 1, 0, 0, 0,-1, 0, 0, 0,-1), // (x ,-y,-z)
@@ -28,15 +39,16 @@ const ROTATIONS: [Rotation; 24] = [
  	Rotation( 0, 0,-1, 0,-1, 0,-1, 0, 0), // (-z,-y,-x)
 ];
 
+/// A 3x3 rotation matrix, stored row by row
 #[derive(Clone, Copy, PartialEq, Eq)]
-struct Rotation(i32, i32, i32, i32, i32, i32, i32, i32, i32);
+pub struct Rotation(pub i32, pub i32, pub i32, pub i32, pub i32, pub i32, pub i32, pub i32, pub i32);
 
 impl Rotation {
-	fn identity() -> Rotation {
+	pub fn identity() -> Rotation {
 		Rotation(1, 0, 0, 0, 1, 0, 0, 0, 1)
 	}
 
-	fn transpose(self) -> Rotation {
+	pub fn transpose(self) -> Rotation {
 		Rotation(
 			self.0,
 			self.3,
@@ -110,8 +122,9 @@ impl Mul for Rotation {
 	}
 }
 
+/// Position of a beacon, relative to the scanner that sees it
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct Point(i32, i32, i32);
+pub struct Point(pub i32, pub i32, pub i32);
 
 impl Neg for Point {
     type Output = Point;
@@ -129,11 +142,11 @@ impl fmt::Debug for Point {
 }
 
 impl Point {
-	fn mag_sqrd(&self) -> i32 {
+	pub fn mag_sqrd(&self) -> i32 {
 		self.0*self.0 + self.1*self.1 + self.2*self.2
 	}
 
-	fn manhattan(&self, other: &Point) -> i32 {
+	pub fn manhattan(&self, other: &Point) -> i32 {
 		(self.0-other.0).abs() + (self.1-other.1).abs() + (self.2-other.2).abs()
 	}
 }
@@ -162,27 +175,45 @@ impl Add for Point {
     }
 }
 
-fn parse_point(line: &str) -> Point {
+fn parse_point(source: &Source, line: &str) -> Result<Point, ParseError> {
 	let nums: Vec<&str> = line.splitn(3, ',').collect();
-	let x = nums[0].parse().unwrap();
-	let y = nums[1].parse().unwrap();
-	let z = nums[2].parse().unwrap();
-	Point(x, y, z)
+	if nums.len() != 3 {
+		return Err(source.error(line, "expected 3 coordinates"));
+	}
+	let x = source.number(nums[0])?;
+	let y = source.number(nums[1])?;
+	let z = source.number(nums[2])?;
+	Ok(Point(x, y, z))
 }
 
-fn parse_scanner(input: &str) -> Vec<Point> {
+// A `--- scanner N ---` header followed by at least one beacon
+fn parse_scanner(source: &Source, input: &str) -> Result<Vec<Point>, ParseError> {
+	let mut lines = input.lines();
+	let header = lines.next().unwrap_or(input);
+	let number = source.strip_prefix(header, "--- scanner ")?;
+	let number = number.strip_suffix(" ---").ok_or_else(|| source.error(header, "expected '--- scanner N ---'"))?;
+	source.number::<u32>(number)?;
+
 	let mut beacons = Vec::new();
-	for line in input.lines().skip(1) {
-		beacons.push(parse_point(line));
+	for line in lines {
+		beacons.push(parse_point(source, line)?);
+	}
+	if beacons.is_empty() {
+		return Err(source.error(header, "scanner without any beacons"));
 	}
-	beacons
+	Ok(beacons)
 }
 
-pub fn parse_input(input: &str) -> Vec<Vec<Point>> {
-	let mut scanners = Vec::new(); for scanner_section in input.split("\n\n") {
-		scanners.push(parse_scanner(scanner_section));
+pub fn parse_input(input: &str) -> Result<Vec<Vec<Point>>, ParseError> {
+	let source = Source::new(Day19::DAY, input);
+	if input.trim().is_empty() {
+		return Err(source.error(source.end(), "expected at least one scanner"));
 	}
-	scanners
+	let mut scanners = Vec::new();
+	for scanner_section in input.trim_end().split("\n\n") {
+		scanners.push(parse_scanner(&source, scanner_section)?);
+	}
+	Ok(scanners)
 }
 
 fn calc_beacon_pairs(beacons: &[Point]) -> HashMap<(usize, usize), i32> {
@@ -226,11 +257,10 @@ fn find_correct_transform(
 	None
 }
 
+// The pairs of scanners are matched on all threads, but the transforms are
+// still collected in order, so the same path through the scanners is taken
 fn calc_transforms(scanners: &[Vec<Point>]) -> Vec<(Rotation, Point)> {
-	let mut diffs = vec![];
-	for scanner in scanners {
-		diffs.push(calc_beacon_pairs(scanner))
-	}
+	let diffs = parallel::map(scanners, |scanner| calc_beacon_pairs(scanner));
 
 	let mut relative_transforms = HashMap::new();
 	{
@@ -238,25 +268,34 @@ fn calc_transforms(scanners: &[Vec<Point>]) -> Vec<(Rotation, Point)> {
 			.map(|m| m.values().collect::<HashSet<_>>())
 			.collect::<Vec<_>>();
 
-		for (i, pairs1) in dist_diffs.iter().enumerate() {
-			for (j, pairs2) in dist_diffs.iter().enumerate().skip(i+1) {
-				if pairs1.intersection(pairs2).count() >= 66 {
-					let trans = find_correct_transform(
-						&scanners[i], &diffs[i],
-						&scanners[j], &diffs[j]
-					);
-					if let Some((rot, offset)) = trans {
-						relative_transforms.entry(i)
-							.or_insert(vec![])
-							.push((j, rot, offset));
-
-						let inv_rot = rot.transpose();
-
-						relative_transforms.entry(j)
-							.or_insert(vec![])
-							.push((i, inv_rot, inv_rot.mul(-offset)));
-					}
-				}
+		let mut scanner_pairs = vec![];
+		for i in 0..scanners.len() {
+			for j in i+1..scanners.len() {
+				scanner_pairs.push((i, j));
+			}
+		}
+		let pair_transforms = parallel::map(&scanner_pairs, |&(i, j)| {
+			if dist_diffs[i].intersection(&dist_diffs[j]).count() >= 66 {
+				find_correct_transform(
+					&scanners[i], &diffs[i],
+					&scanners[j], &diffs[j]
+				)
+			} else {
+				None
+			}
+		});
+
+		for ((i, j), trans) in scanner_pairs.into_iter().zip(pair_transforms) {
+			if let Some((rot, offset)) = trans {
+				relative_transforms.entry(i)
+					.or_insert(vec![])
+					.push((j, rot, offset));
+
+				let inv_rot = rot.transpose();
+
+				relative_transforms.entry(j)
+					.or_insert(vec![])
+					.push((i, inv_rot, inv_rot.mul(-offset)));
 			}
 		}
 	}
@@ -268,8 +307,7 @@ fn calc_transforms(scanners: &[Vec<Point>]) -> Vec<(Rotation, Point)> {
 
 	let mut stack = vec![(0, Rotation::identity(), Point(0, 0, 0))];
 	let mut visited = HashSet::new();
-	while !stack.is_empty() {
-		let (id, rot, offset) = stack.pop().unwrap();
+	while let Some((id, rot, offset)) = stack.pop() {
 
 		if visited.contains(&id) { continue; }
 		visited.insert(id);
@@ -313,6 +351,83 @@ pub fn part2(scanners: &[Vec<Point>]) -> i32 {
 	result
 }
 
+fn sees(scanner: &Point, beacon: &Point) -> bool {
+	(scanner.0 - beacon.0).abs() <= 1000 && (scanner.1 - beacon.1).abs() <= 1000 && (scanner.2 - beacon.2).abs() <= 1000
+}
+
+/// Scanners are placed one after another, each one sharing at least 12 beacons
+/// with the one before it, so that all of them can be lined up. Every scanner
+/// reports the beacons within 1000 units of it, turned by a random rotation.
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let mut scanners = vec![Point(0, 0, 0)];
+	let mut beacons: Vec<Point> = vec![];
+	for _ in 1..size.max(2) {
+		let previous = *scanners.last().unwrap();
+		let offset = loop {
+			let offset = Point(rng.range(-1200, 1200), rng.range(-1200, 1200), rng.range(-1200, 1200));
+			if offset.0.abs().max(offset.1.abs()).max(offset.2.abs()) >= 1000 { break offset; }
+		};
+		let scanner = previous + offset;
+
+		// Somewhere in the region seen by both of them, which might already have
+		// some beacons in it
+		let shared = |rng: &mut Rng, a: i32, b: i32| rng.range(a.max(b) - 1000, a.min(b) + 1000);
+		while beacons.iter().filter(|beacon| sees(&previous, beacon) && sees(&scanner, beacon)).count() < 12 {
+			let beacon = Point(shared(rng, previous.0, scanner.0), shared(rng, previous.1, scanner.1), shared(rng, previous.2, scanner.2));
+			if !beacons.contains(&beacon) { beacons.push(beacon); }
+		}
+		scanners.push(scanner);
+	}
+
+	// And some more that might be seen by just one of them
+	for scanner in &scanners {
+		for _ in 0..rng.range(0, 4) {
+			let beacon = *scanner + Point(rng.range(-1000, 1000), rng.range(-1000, 1000), rng.range(-1000, 1000));
+			if !beacons.contains(&beacon) { beacons.push(beacon); }
+		}
+	}
+
+	let mut sections = vec![];
+	for (i, scanner) in scanners.iter().enumerate() {
+		let rotation = *rng.choose(&ROTATIONS);
+		let mut lines = vec![format!("--- scanner {} ---", i)];
+		for beacon in beacons.iter().filter(|beacon| sees(scanner, beacon)) {
+			let Point(x, y, z) = rotation * (*beacon - *scanner);
+			lines.push(format!("{},{},{}", x, y, z));
+		}
+		rng.shuffle(&mut lines[1..]);
+		sections.push(lines.join("\n"));
+	}
+	sections.join("\n\n") + "\n"
+}
+
+pub struct Day19;
+
+impl Solution for Day19 {
+	const DAY: u32 = 19;
+
+	type Input = Vec<Vec<Point>>;
+	type Output1 = u32;
+	type Output2 = i32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -372,4 +487,14 @@ mod tests {
 		let c = a.mul(b);
 		assert_eq!(c, Rotation(41, 18, 7,  91, 15, 5, 79, -31, -15));
 	}
+
+	#[test]
+	fn parse_error() {
+		let error = parse_input("x").unwrap_err();
+		assert_eq!((error.line, error.column), (1, 1));
+		let error = parse_input("--- scanner 0 ---\n1,2,3\n\n--- scanner 1 ---").unwrap_err();
+		assert_eq!((error.line, error.message.as_str()), (4, "scanner without any beacons"));
+		assert!(parse_input("1\n\n2").is_err());
+		assert!(parse_input("").is_err());
+	}
 }
@@ -1,63 +1,361 @@
-use std::num::ParseIntError;
+//! Day 2: Dive!
+//!
+//! Steers the submarine with `forward`, `down` and `up` commands and multiplies
+//! the final horizontal position by the depth. The input can also be a longer
+//! script, with more commands and repeated blocks.
 
-#[derive(Debug)]
-pub enum ParseCommandError {
-	ParseEnumError,
-	ParseIntError(ParseIntError),
-}
+use std::{error::Error, fmt};
+
+use crate::{
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
 
+/// What a command does, the amount is in `Command`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CommandType {
 	Forward,
+	Back,
 	Down,
 	Up,
+	ResetAim,
+}
+
+/// A single command with its amount
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Command(pub CommandType, pub u32);
+
+/// The commands in the order they run, with repeats expanded, and the line of
+/// the input each of them came from
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Script {
+	pub commands: Vec<Command>,
+	pub lines: Vec<usize>,
+}
+
+// One command per line, like in the puzzle input
+impl From<Vec<Command>> for Script {
+	fn from(commands: Vec<Command>) -> Script {
+		let lines = (1..=commands.len()).collect();
+		Script { commands, lines }
+	}
 }
 
-pub struct Command(CommandType, u32);
+// Repeats are expanded while parsing, this keeps nested ones from using up all
+// of the memory
+const MAX_COMMANDS: usize = 10_000_000;
+
+// Every nested repeat is parsed by a call of its own, too many of them would
+// overflow the stack
+const MAX_DEPTH: usize = 100;
 
-fn parse_line(line: &str) -> Result<Command, ParseCommandError> {
-	let parts: Vec<&str> = line.split(' ').collect();
-	let command = match parts[0] {
-		"up" => Ok(CommandType::Up),
-		"down" => Ok(CommandType::Down),
-		"forward" => Ok(CommandType::Forward),
-		_ => Err(ParseCommandError::ParseEnumError),
-	}?;
-	let amount = parts[1].parse().map_err(ParseCommandError::ParseIntError)?;
+fn parse_command<'a>(
+	source: &Source<'a>,
+	word: &'a str,
+	rest: &'a str,
+) -> Result<Command, ParseError> {
+	let command = match word {
+		"forward" => CommandType::Forward,
+		"back" => CommandType::Back,
+		"down" => CommandType::Down,
+		"up" => CommandType::Up,
+		"reset-aim" => {
+			if !rest.is_empty() {
+				return Err(source.error(rest, "'reset-aim' doesn't take an amount"));
+			}
+			return Ok(Command(CommandType::ResetAim, 0));
+		}
+		_ => return Err(source.error(word, format!("unknown command '{}'", word))),
+	};
+	if rest.is_empty() {
+		return Err(source.error(rest, format!("expected an amount after '{}'", word)));
+	}
+	let amount = source.number(rest)?;
 	Ok(Command(command, amount))
 }
 
-pub fn parse_input(input: &str) -> Result<Vec<Command>, ParseCommandError> {
-	input.split_terminator('\n').map(parse_line).collect()
+// Parses lines up to the `}` that closes the block started on `opening`, or up
+// to the end of the input at the top level. `depth` is the number of repeats
+// the block is in.
+fn parse_block<'a>(
+	source: &Source<'a>,
+	lines: &mut impl Iterator<Item = (usize, &'a str)>,
+	opening: Option<&'a str>,
+	depth: usize,
+) -> Result<Script, ParseError> {
+	let mut script = Script::default();
+	while let Some((line_number, line)) = lines.next() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		if line == "}" {
+			if opening.is_none() {
+				return Err(source.error(line, "'}' without a 'repeat' to close"));
+			}
+			return Ok(script);
+		}
+		// Slicing keeps `rest` pointing into the input, even when it's empty
+		let (word, rest) = match line.find(char::is_whitespace) {
+			Some(i) => (&line[..i], line[i..].trim_start()),
+			None => (line, &line[line.len()..]),
+		};
+		if word != "repeat" {
+			script.commands.push(parse_command(source, word, rest)?);
+			script.lines.push(line_number);
+			continue;
+		}
+
+		let count_str = rest
+			.strip_suffix('{')
+			.ok_or_else(|| source.error(&line[line.len()..], "expected '{' after the repeat count"))?;
+		let count: usize = source.number(count_str)?;
+		if depth == MAX_DEPTH {
+			return Err(source.error(
+				line,
+				format!("repeats are nested more than {} deep", MAX_DEPTH),
+			));
+		}
+		let body = parse_block(source, lines, Some(line), depth + 1)?;
+		if body.commands.len().saturating_mul(count)
+			> MAX_COMMANDS.saturating_sub(script.commands.len())
+		{
+			return Err(source.error(
+				line,
+				format!("repeat expands to more than {} commands", MAX_COMMANDS),
+			));
+		}
+		for _ in 0..count {
+			script.commands.extend_from_slice(&body.commands);
+			script.lines.extend_from_slice(&body.lines);
+		}
+	}
+	match opening {
+		Some(opening) => Err(source.error(opening, "'repeat' is never closed with '}'")),
+		None => Ok(script),
+	}
 }
 
-pub fn part1(commands: &[Command]) -> u32 {
-	let mut depth = 0;
-	let mut horizontal = 0;
-	for command in commands {
-		match command.0 {
-			CommandType::Up => depth -= command.1,
-			CommandType::Down => depth += command.1,
-			CommandType::Forward => horizontal += command.1,
+/// Besides the puzzle's commands this reads `back <n>`, `reset-aim`, comments
+/// starting with `#` and blocks of commands that run more than once:
+///
+/// ```text
+/// repeat 3 {
+///     forward 2
+///     down 1
+/// }
+/// ```
+pub fn parse_input(input: &str) -> Result<Script, ParseError> {
+	let source = Source::new(Day2::DAY, input);
+	let mut lines = input
+		.split_terminator('\n')
+		.enumerate()
+		.map(|(i, line)| (i + 1, line));
+	parse_block(&source, &mut lines, None, 0)
+}
+
+/// How `down` and `up` are read: part 1 moves the submarine straight up and
+/// down, part 2 only turns it and `forward` moves along the aim
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+	Direct,
+	Aim,
+}
+
+impl Mode {
+	pub fn for_part(part: u32) -> Mode {
+		if part == 1 {
+			Mode::Direct
+		} else {
+			Mode::Aim
 		}
 	}
-	return depth * horizontal;
 }
 
-pub fn part2(commands: &[Command]) -> u32 {
+/// Depth grows downwards, so a negative one would be above the surface
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct State {
+	pub horizontal: i64,
+	pub depth: i64,
+	pub aim: i64,
+}
+
+/// Why the submarine couldn't follow the script. `line` is the line of the
+/// script with the failing command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NavigationError {
+	/// The depth would have become negative
+	Surfaced { line: usize, depth: i64 },
+	/// One of the coordinates doesn't fit in an `i64` anymore
+	Overflow { line: usize },
+}
+
+impl fmt::Display for NavigationError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			NavigationError::Surfaced { line, depth } => write!(
+				f,
+				"the command on line {} would take the submarine {} above the surface",
+				line, -depth
+			),
+			NavigationError::Overflow { line } => write!(
+				f,
+				"the command on line {} takes the submarine further than can be kept track of",
+				line
+			),
+		}
+	}
+}
+
+impl Error for NavigationError {}
+
+// Where the submarine ends up after `command`, `None` if a coordinate overflows
+fn apply(mode: Mode, state: State, command: &Command) -> Option<State> {
+	let amount = command.1 as i64;
+	let mut next = state;
+	match (command.0, mode) {
+		(CommandType::Forward, Mode::Direct) => {
+			next.horizontal = state.horizontal.checked_add(amount)?
+		}
+		(CommandType::Forward, Mode::Aim) => {
+			next.horizontal = state.horizontal.checked_add(amount)?;
+			next.depth = state.depth.checked_add(state.aim.checked_mul(amount)?)?;
+		}
+		// Reverses along the aim, the same way forward moves along it
+		(CommandType::Back, Mode::Direct) => next.horizontal = state.horizontal.checked_sub(amount)?,
+		(CommandType::Back, Mode::Aim) => {
+			next.horizontal = state.horizontal.checked_sub(amount)?;
+			next.depth = state.depth.checked_sub(state.aim.checked_mul(amount)?)?;
+		}
+		(CommandType::Down, Mode::Direct) => next.depth = state.depth.checked_add(amount)?,
+		(CommandType::Up, Mode::Direct) => next.depth = state.depth.checked_sub(amount)?,
+		(CommandType::Down, Mode::Aim) => next.aim = state.aim.checked_add(amount)?,
+		(CommandType::Up, Mode::Aim) => next.aim = state.aim.checked_sub(amount)?,
+		(CommandType::ResetAim, _) => next.aim = 0,
+	}
+	Some(next)
+}
+
+/// Follows the commands one by one and remembers every state it was in
+pub struct Submarine {
+	mode: Mode,
+	// Starts with the state before the first command
+	trajectory: Vec<State>,
+}
+
+impl Submarine {
+	pub fn new(mode: Mode) -> Submarine {
+		Submarine {
+			mode,
+			trajectory: vec![State::default()],
+		}
+	}
+
+	pub fn state(&self) -> State {
+		*self.trajectory.last().unwrap()
+	}
+
+	pub fn trajectory(&self) -> &[State] {
+		&self.trajectory
+	}
+
+	/// Leaves the submarine where it was if the command fails. `line` is where
+	/// the command came from, for the error.
+	pub fn execute(&mut self, command: &Command, line: usize) -> Result<State, NavigationError> {
+		let state =
+			apply(self.mode, self.state(), command).ok_or(NavigationError::Overflow { line })?;
+		if state.depth < 0 {
+			return Err(NavigationError::Surfaced {
+				line,
+				depth: state.depth,
+			});
+		}
+		self.trajectory.push(state);
+		Ok(state)
+	}
+
+	pub fn run(&mut self, script: &Script) -> Result<State, NavigationError> {
+		for (command, line) in script.commands.iter().zip(&script.lines) {
+			self.execute(command, *line)?;
+		}
+		Ok(self.state())
+	}
+
+	/// One row for every state, step 0 being the start
+	pub fn trajectory_csv(&self) -> String {
+		let mut csv = String::from("step,horizontal,depth,aim\n");
+		for (step, state) in self.trajectory.iter().enumerate() {
+			csv += &format!(
+				"{},{},{},{}\n",
+				step, state.horizontal, state.depth, state.aim
+			);
+		}
+		csv
+	}
+}
+
+/// Any two coordinates multiplied fit in an `i128`
+pub fn navigate(script: &Script, mode: Mode) -> Result<i128, NavigationError> {
+	let state = Submarine::new(mode).run(script)?;
+	return Ok(state.depth as i128 * state.horizontal as i128);
+}
+
+pub fn part1(script: &Script) -> Result<i128, NavigationError> {
+	navigate(script, Mode::Direct)
+}
+
+pub fn part2(script: &Script) -> Result<i128, NavigationError> {
+	navigate(script, Mode::Aim)
+}
+
+/// Never goes up above the surface, because the depth can't be negative
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
 	let mut depth = 0;
-	let mut horizontal = 0;
-	let mut aim = 0;
-	for command in commands {
-		match command.0 {
-			CommandType::Up => aim -= command.1,
-			CommandType::Down => aim += command.1,
-			CommandType::Forward => {
-				horizontal += command.1;
-				depth += aim * command.1;
+	let mut lines = Vec::new();
+	for _ in 0..size {
+		let amount = rng.range(1, 9);
+		let direction = match rng.below(3) {
+			0 => "forward",
+			1 if depth >= amount => {
+				depth -= amount;
+				"up"
 			}
-		}
+			_ => {
+				depth += amount;
+				"down"
+			}
+		};
+		lines.push(format!("{} {}", direction, amount));
+	}
+	lines.join("\n") + "\n"
+}
+
+pub struct Day2;
+
+impl Solution for Day2 {
+	const DAY: u32 = 2;
+
+	type Input = Script;
+	type Output1 = Result<i128, NavigationError>;
+	type Output2 = Result<i128, NavigationError>;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
 	}
-	return depth * horizontal;
 }
 
 #[cfg(test)]
@@ -74,8 +372,8 @@ mod tests {
 			Command(CommandType::Down, 8),
 			Command(CommandType::Forward, 2),
 		];
-		let result = part1(&commands);
-		assert_eq!(result, 150);
+		let result = part1(&Script::from(commands.to_vec()));
+		assert_eq!(result, Ok(150));
 	}
 
 	#[test]
@@ -88,7 +386,135 @@ mod tests {
 			Command(CommandType::Down, 8),
 			Command(CommandType::Forward, 2),
 		];
-		let result = part2(&commands);
-		assert_eq!(result, 900)
+		let result = part2(&Script::from(commands.to_vec()));
+		assert_eq!(result, Ok(900))
+	}
+
+	#[test]
+	fn parse_error() {
+		let error = parse_input("forward 5\nbackward 3").err().unwrap();
+		assert_eq!((error.line, error.column), (2, 1));
+		assert_eq!(error.message, "unknown command 'backward'");
+	}
+
+	#[test]
+	fn records_trajectory() {
+		let commands = parse_input("forward 5\ndown 5\nforward 8\n").unwrap();
+		let mut submarine = Submarine::new(Mode::Aim);
+		submarine.run(&commands).unwrap();
+		assert_eq!(
+			submarine.trajectory_csv(),
+			"step,horizontal,depth,aim\n0,0,0,0\n1,5,0,0\n2,5,0,5\n3,13,40,5\n"
+		);
+	}
+
+	#[test]
+	fn surfacing_is_an_error() {
+		let commands = parse_input("down 2\nup 3\nforward 1\n").unwrap();
+		let error = part1(&commands).unwrap_err();
+		assert_eq!(error, NavigationError::Surfaced { line: 2, depth: -1 });
+		assert_eq!(
+			error.to_string(),
+			"the command on line 2 would take the submarine 1 above the surface"
+		);
+
+		// Aiming up is fine until the submarine moves
+		let mut submarine = Submarine::new(Mode::Aim);
+		assert!(submarine.run(&commands).is_err());
+		assert_eq!(
+			submarine.state(),
+			State {
+				horizontal: 0,
+				depth: 0,
+				aim: -1
+			}
+		);
+		assert_eq!(submarine.trajectory().len(), 3);
+	}
+
+	// Big enough amounts are still valid commands
+	#[test]
+	fn overflow_is_an_error() {
+		let commands =
+			parse_input("down 4294967295\nforward 4294967295\nforward 4294967295\n").unwrap();
+		assert_eq!(part1(&commands), Ok(4294967295 * 8589934590));
+		let error = part2(&commands).unwrap_err();
+		assert_eq!(error, NavigationError::Overflow { line: 2 });
+		assert_eq!(
+			error.to_string(),
+			"the command on line 2 takes the submarine further than can be kept track of"
+		);
+	}
+
+	#[test]
+	fn scripts() {
+		let script = "# dive a bit\ndown 2\n\nrepeat 2 {\n\trepeat 3 {\n\t\tforward 1\n\t}\n\tback 1\n}\nreset-aim\n";
+		let commands = parse_input(script).unwrap();
+		let mut expected = vec![Command(CommandType::Down, 2)];
+		for _ in 0..2 {
+			expected.extend([Command(CommandType::Forward, 1); 3]);
+			expected.push(Command(CommandType::Back, 1));
+		}
+		expected.push(Command(CommandType::ResetAim, 0));
+		assert_eq!(commands.commands, expected);
+		assert_eq!(commands.lines, vec![2, 6, 6, 6, 8, 6, 6, 6, 8, 10]);
+
+		let mut submarine = Submarine::new(Mode::Aim);
+		let state = submarine.run(&commands).unwrap();
+		assert_eq!(
+			state,
+			State {
+				horizontal: 4,
+				depth: 8,
+				aim: 0
+			}
+		);
+		assert_eq!(part1(&commands), Ok(8));
+
+		// Errors point at the line in the script, not at the expanded command
+		let commands = parse_input("down 1\n# going up\nrepeat 3 {\n\tup 1\n}\n").unwrap();
+		assert_eq!(
+			part1(&commands),
+			Err(NavigationError::Surfaced { line: 4, depth: -1 })
+		);
+	}
+
+	#[test]
+	fn script_errors() {
+		let error = |input| {
+			let error = parse_input(input).unwrap_err();
+			(error.line, error.column, error.message)
+		};
+		assert_eq!(
+			error("repeat 2 {\n  forward\n}"),
+			(2, 10, "expected an amount after 'forward'".into())
+		);
+		assert_eq!(error("repeat x {\n}"), (1, 8, "invalid number 'x'".into()));
+		assert_eq!(
+			error("repeat 2\n"),
+			(1, 9, "expected '{' after the repeat count".into())
+		);
+		assert_eq!(
+			error("down 1\n  repeat 2 {\nup 1\n"),
+			(2, 3, "'repeat' is never closed with '}'".into())
+		);
+		assert_eq!(
+			error("down 1\n}"),
+			(2, 1, "'}' without a 'repeat' to close".into())
+		);
+		assert_eq!(
+			error("reset-aim 3"),
+			(1, 11, "'reset-aim' doesn't take an amount".into())
+		);
+		assert_eq!(
+			error("repeat 100000 {\nrepeat 100000 {\nup 1\n}\n}"),
+			(1, 1, "repeat expands to more than 10000000 commands".into())
+		);
+		assert_eq!(
+			error(&format!("forward 1\n{}", "repeat 1 {\n".repeat(200_000))),
+			(102, 1, "repeats are nested more than 100 deep".into())
+		);
+		let nested = "repeat 1 {\n".repeat(100) + "up 1\n" + &"}\n".repeat(100);
+		assert_eq!(parse_input(&nested).unwrap().lines, vec![101]);
 	}
 }
@@ -1,149 +1,84 @@
-use std::{fmt::Display, ops::Range};
-
-#[derive(Clone, Debug)]
-pub struct Image {
-	width: usize,
-	height: usize,
-	data: Vec<bool>,
-	default_value: bool,
-	offset_x: i32,
-	offset_y: i32,
-}
-
-impl Image {
-	fn new(width: usize, height: usize) -> Image {
-		let data = vec![false; width * height];
-		Image {
-			width,
-			height,
-			data,
-			default_value: false,
-			offset_x: 0,
-			offset_y: 0,
-		}
-	}
+//! Day 20: Trench Map
+//!
+//! Enhances an infinite image, where the pixels far away from the input can
+//! all flip at once.
 
-	fn get(&self, x: i32, y: i32) -> bool {
-		if self.in_bounds(x, y) {
-			let index = (y + self.offset_y) * self.width as i32 + (x + self.offset_x);
-			*self.data.get(index as usize).unwrap_or(&self.default_value)
-		} else {
-			self.default_value
-		}
-	}
+use crate::{
+	generate::Rng,
+	grid::{Grid, InfiniteGrid},
+	parallel,
+	parse::{ParseError, Source},
+	solution::{parse_param, unknown_param, PuzzleParams, Solution},
+	visualize::{Cell, Frame, FrameSink},
+};
 
-	fn set(&mut self, x: i32, y: i32, value: bool) {
-		// Make image larger if it is too small
-		let (left, top, right, bottom) = self.bounds();
-		if !(left <= x && x < right && top <= y && y < bottom) {
-			if x < left {
-				self.offset_x = -x;
-				self.width = self.width + (-x + left) as usize;
-			} else if x >= right {
-				self.width = self.width + 1 + (x - right) as usize;
-			}
-
-			if y < top {
-				self.offset_y = -y;
-				self.height = self.height + (-y + top) as usize;
-			} else if y >= bottom {
-				self.height = self.height + 1 + (y - bottom) as usize;
-			}
-
-			let mut new_data = Vec::new();
-			for y in self.y_range(0) {
-				for x in self.x_range(0) {
-					new_data.push(self.get(x, y));
-				}
-			}
-			self.data = new_data;
-		}
-
-		let index = ((y + self.offset_y) * self.width as i32 + (x + self.offset_x)) as usize;
-		self.data[index] = value;
-	}
-
-	fn in_bounds(&self, x: i32, y: i32) -> bool {
-		let (left, top, right, bottom) = self.bounds();
-		left <= x && x < right && top <= y && y < bottom
-	}
+/// Lit pixels are `true`
+pub type Image = InfiniteGrid<bool>;
 
-	fn count(&self, value: bool) -> usize {
-		if self.default_value == value {
-			usize::MAX
-		} else {
-			self.data.iter().filter(|x| **x == value).count()
-		}
-	}
+/// How many times the image gets enhanced
+pub struct Params {
+	pub enhancements: usize,
+}
 
-	fn x_range(&self, padding: u32) -> Range<i32> {
-		let padding = padding as i32;
-		Range {
-			start: -self.offset_x - padding,
-			end: self.width as i32 - self.offset_x + padding,
+impl PuzzleParams for Params {
+	fn puzzle(part: u32) -> Params {
+		Params {
+			enhancements: if part == 1 { 2 } else { 50 },
 		}
 	}
 
-	fn y_range(&self, padding: u32) -> Range<i32> {
-		let padding = padding as i32;
-		Range {
-			start: -self.offset_y - padding,
-			end: self.height as i32 - self.offset_y + padding,
+	fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+		match name {
+			"enhancements" => self.enhancements = parse_param(name, value)?,
+			_ => return Err(unknown_param(name, &["enhancements"])),
 		}
-	}
-
-	// (i32, i32, i32, i32) => (left, top, right, bottom)
-	fn bounds(&self) -> (i32, i32, i32, i32) {
-		return (
-			-self.offset_x,
-			-self.offset_y,
-			self.width as i32 - self.offset_x,
-			self.height as i32 - self.offset_y,
-		);
+		Ok(())
 	}
 }
 
-impl Display for Image {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		let mut grid = String::new();
-		for y in self.y_range(1) {
-			for x in self.x_range(1) {
-				let symbol = if self.get(x, y) { '#' } else { '.' };
-				grid.push(symbol);
-			}
-			grid.push('\n');
-		}
-		write!(f, "{}", grid)
+fn parse_pixel(source: &Source, token: &str) -> Result<bool, ParseError> {
+	match token.chars().next() {
+		Some('#') => Ok(true),
+		Some('.') => Ok(false),
+		Some(c) => Err(source.error(token, format!("expected '#' or '.', found '{}'", c))),
+		None => Err(source.error(token, "expected a pixel")),
 	}
 }
 
-fn parse_image(input: &str) -> Image {
-	let mut image = Image::new(3, 3);
-	let mut y = 0;
-	for line in input.lines() {
-		let mut x = 0;
-		for c in line.chars() {
-			image.set(x, y, c == '#');
-			x += 1;
-		}
-		y += 1
-	}
-	return image;
+fn parse_image(source: &Source, input: &str) -> Result<Image, ParseError> {
+	let pixels = Grid::parse(source, input, |c| match c {
+		'#' => Some(true),
+		'.' => Some(false),
+		_ => None,
+	})?;
+	return Ok(InfiniteGrid::from_grid(pixels, 0, 0, false));
 }
 
-fn parse_enchancer(input: &str) -> [bool; 512] {
+fn parse_enchancer(source: &Source, input: &str) -> Result<[bool; 512], ParseError> {
 	let mut enhancer = [false; 512];
 	let mut i = 0;
-	for c in input.chars() {
-		enhancer[i] = c == '#';
+	for (offset, _) in input.char_indices() {
+		if i == 512 {
+			return Err(source.error(&input[offset..], "expected an enhancer of 512 pixels"));
+		}
+		enhancer[i] = parse_pixel(source, &input[offset..])?;
 		i += 1;
 	}
-	return enhancer;
+	if i < 512 {
+		return Err(source.error(&input[input.len()..], "expected an enhancer of 512 pixels"));
+	}
+	return Ok(enhancer);
 }
 
-pub fn parse_input(input: &str) -> ([bool; 512], Image) {
-	let (section1, section2) = input.split_once("\n\n").unwrap();
-	return (parse_enchancer(section1), parse_image(section2));
+pub fn parse_input(input: &str) -> Result<([bool; 512], Image), ParseError> {
+	let source = Source::new(Day20::DAY, input);
+	let (section1, section2) = input
+		.split_once("\n\n")
+		.ok_or_else(|| source.error(source.end(), "expected a blank line before the image"))?;
+	return Ok((
+		parse_enchancer(&source, section1)?,
+		parse_image(&source, section2)?,
+	));
 }
 
 fn lookup_enhancer(x: i32, y: i32, image: &Image, enhancer: &[bool; 512]) -> bool {
@@ -162,7 +97,7 @@ fn lookup_enhancer(x: i32, y: i32, image: &Image, enhancer: &[bool; 512]) -> boo
 	let mut lookup_index = 0;
 	for i in 0..9 {
 		let (ox, oy) = pixel_offsets[i];
-		let pixel = image.get(x + ox, y + oy);
+		let pixel = *image.get(x + ox, y + oy);
 		if pixel {
 			lookup_index += 2usize.pow((8 - i) as u32)
 		}
@@ -171,37 +106,146 @@ fn lookup_enhancer(x: i32, y: i32, image: &Image, enhancer: &[bool; 512]) -> boo
 	enhancer[lookup_index]
 }
 
+// Every enhancement grows the image by one pixel on each side. The pixels
+// outside of it all have the same surroundings, so they flip together. The
+// rows are spread over the threads.
 fn enhance(image: &Image, enhancer: &[bool; 512]) -> Image {
-	let mut enhanced = image.clone();
-	for y in image.y_range(1) {
-		for x in image.x_range(1) {
-			enhanced.set(x, y, lookup_enhancer(x, y, image, enhancer));
-		}
-	}
+	let (x_range, y_range) = image.bounds();
+	let (width, height) = (x_range.len() + 2, y_range.len() + 2);
+	let rows: Vec<i32> = (y_range.start - 1..y_range.end + 1).collect();
+	let row_pixels = parallel::map(&rows, |y| {
+		(x_range.start - 1..x_range.end + 1)
+			.map(|x| lookup_enhancer(x, *y, image, enhancer))
+			.collect::<Vec<_>>()
+	});
+	let pixels = Grid::from_cells(width, height, row_pixels.concat());
 
-	if image.default_value {
-		enhanced.default_value = enhancer[511];
+	let default_value = if *image.default_value() {
+		enhancer[511]
 	} else {
-		enhanced.default_value = enhancer[0];
-	}
+		enhancer[0]
+	};
 
-	enhanced
+	InfiniteGrid::from_grid(pixels, x_range.start - 1, y_range.start - 1, default_value)
 }
 
-pub fn part1(data: &([bool; 512], Image)) -> usize {
+// An infinite amount of pixels is lit if the ones outside of the image are
+fn count_lit(image: &Image) -> usize {
+	if *image.default_value() {
+		usize::MAX
+	} else {
+		image.grid().iter().filter(|pixel| **pixel).count()
+	}
+}
+
+fn count_lit_after(data: &([bool; 512], Image), enhancements: usize) -> usize {
 	let (enhancer, image) = data;
-	let mut enhanced_image = enhance(&image, enhancer);
-	enhanced_image = enhance(&enhanced_image, enhancer);
-	enhanced_image.count(true)
+	let mut enhanced_image = image.clone();
+	for _ in 0..enhancements {
+		enhanced_image = enhance(&enhanced_image, enhancer);
+	}
+	count_lit(&enhanced_image)
+}
+
+pub fn part1(data: &([bool; 512], Image), params: &Params) -> usize {
+	count_lit_after(data, params.enhancements)
+}
+
+pub fn part2(data: &([bool; 512], Image), params: &Params) -> usize {
+	count_lit_after(data, params.enhancements)
 }
 
-pub fn part2(data: &([bool; 512], Image)) -> usize {
+fn image_frame(image: &Image, left: i32, top: i32, width: usize, height: usize) -> Frame {
+	let mut frame = Grid::new(width, height, Cell::new('.', [0, 0, 0]));
+	for (fx, fy) in frame.positions() {
+		if *image.get(left + fx as i32, top + fy as i32) {
+			frame[(fx, fy)] = Cell::new('#', [255, 255, 255]);
+		}
+	}
+	frame
+}
+
+/// Every frame covers the area that the image has grown into by the last
+/// enhancement, so that they all line up
+pub fn visualize(data: &([bool; 512], Image), params: &Params, frames: &mut dyn FrameSink) {
 	let (enhancer, image) = data;
-	let mut enhanced_image = enhance(&image, enhancer);
-	for _ in 0..49 {
+	let steps = params.enhancements;
+	let (x_range, y_range) = image.bounds();
+	let (left, top) = (x_range.start - steps as i32, y_range.start - steps as i32);
+	let (width, height) = (x_range.len() + 2 * steps, y_range.len() + 2 * steps);
+
+	let lit = |image: &Image| match count_lit(image) {
+		usize::MAX => "infinite".to_string(),
+		count => count.to_string(),
+	};
+	let mut enhanced_image = image.clone();
+	frames.push(
+		&image_frame(&enhanced_image, left, top, width, height),
+		&[("lit pixels", lit(&enhanced_image))],
+	);
+	for _ in 0..steps {
 		enhanced_image = enhance(&enhanced_image, enhancer);
+		frames.push(
+			&image_frame(&enhanced_image, left, top, width, height),
+			&[("lit pixels", lit(&enhanced_image))],
+		);
+	}
+}
+
+/// The algorithm never lights up the whole infinite image for good, otherwise
+/// there would be no count to give
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let mut algorithm: Vec<bool> = (0..512).map(|_| rng.chance(1, 2)).collect();
+	if algorithm[0] && algorithm[511] {
+		algorithm[511] = false;
+	}
+	let side = size.max(1);
+	let pixels = (0..side * side).map(|_| rng.chance(1, 2)).collect();
+	let image = Grid::from_cells(side, side, pixels);
+
+	let symbol = |lit: &bool| if *lit { '#' } else { '.' };
+	format!(
+		"{}\n\n{}",
+		algorithm.iter().map(symbol).collect::<String>(),
+		image.render(symbol)
+	)
+}
+
+pub struct Day20;
+
+impl Solution for Day20 {
+	const DAY: u32 = 20;
+
+	type Input = ([bool; 512], Image);
+	type Output1 = usize;
+	type Output2 = usize;
+	type Params = Params;
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, params: &Self::Params) -> Self::Output1 {
+		part1(input, params)
+	}
+
+	fn part2(input: &Self::Input, params: &Self::Params) -> Self::Output2 {
+		part2(input, params)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+
+	fn visualize(
+		input: &Self::Input,
+		_part: u32,
+		params: &Self::Params,
+		frames: &mut dyn FrameSink,
+	) -> bool {
+		visualize(input, params, frames);
+		true
 	}
-	enhanced_image.count(true)
 }
 
 #[cfg(test)]
@@ -216,8 +260,9 @@ mod tests {
 #....
 ##..#
 ..#..
-..###");
-		let result = part1(&input);
+..###")
+		.unwrap();
+		let result = part1(&input, &Params::puzzle(1));
 		assert_eq!(result, 35);
 	}
 
@@ -229,8 +274,32 @@ mod tests {
 #....
 ##..#
 ..#..
-..###");
-		let result = part2(&input);
+..###")
+		.unwrap();
+		let result = part2(&input, &Params::puzzle(2));
 		assert_eq!(result, 3351);
 	}
+
+	#[test]
+	fn frames_line_up() {
+		let input = parse_input("..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..###..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###.######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#..#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.##.#......#..#...##.#.##..#...##.#.##..###.#......#.#.......#.#.#.####.###.##...#.....####.#..#..#.##.#....##..#.####....##...##..#...#......#.#.......#.......##..####..#...#.#.#...##..#.#..###..#####........#..####......#..#
+
+#..#.
+#....
+##..#
+..#..
+..###")
+		.unwrap();
+		let mut frames = vec![];
+		visualize(&input, &Params::puzzle(1), &mut frames);
+		assert_eq!(frames.len(), 3);
+		assert!(frames
+			.iter()
+			.all(|frame| (frame.width(), frame.height()) == (9, 9)));
+		assert_eq!(frames[0][(2, 2)].symbol, '#');
+		assert_eq!(
+			frames[2].iter().filter(|cell| cell.symbol == '#').count(),
+			35
+		);
+	}
 }
@@ -1,14 +1,56 @@
+//! Day 21: Dirac Dice
+//!
+//! Plays a board game first with a deterministic die, then with a quantum die
+//! that splits the universe on every roll.
+
+use crate::{
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::{parse_param, unknown_param, PuzzleParams, Solution},
+};
 use std::collections::HashMap;
 
-pub fn parse_input(input: &str) -> (u8, u8) {
-	let players: Vec<u8> = input
-		.lines()
-		.map(|l| l.split_once(": ").unwrap().1.parse().unwrap())
-		.collect();
-	return (players[0], players[1]);
+pub fn parse_input(input: &str) -> Result<(u8, u8), ParseError> {
+	let source = Source::new(Day21::DAY, input);
+	let mut players = Vec::new();
+	for (i, line) in input.lines().enumerate() {
+		let prefix = format!("Player {} starting position: ", i + 1);
+		players.push(source.number(source.strip_prefix(line, &prefix)?)?);
+	}
+	if players.len() != 2 {
+		return Err(source.error(source.end(), "expected the starting positions of 2 players"));
+	}
+	return Ok((players[0], players[1]));
+}
+
+/// Score needed to win, and the number of spaces on the board
+pub struct Params {
+	pub winning_score: u32,
+	pub board_size: u32,
+}
+
+impl PuzzleParams for Params {
+	fn puzzle(part: u32) -> Params {
+		Params {
+			winning_score: if part == 1 { 1000 } else { 21 },
+			board_size: 10,
+		}
+	}
+
+	fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+		match name {
+			"winning_score" => self.winning_score = parse_param(name, value)?,
+			"board_size" => self.board_size = parse_param(name, value)?,
+			_ => return Err(unknown_param(name, &["winning_score", "board_size"])),
+		}
+		if self.board_size == 0 {
+			return Err("the board needs at least one space".into());
+		}
+		Ok(())
+	}
 }
 
-pub fn part1(starting_positions: &(u8, u8)) -> u32 {
+pub fn part1(starting_positions: &(u8, u8), params: &Params) -> u32 {
 	let mut player1_position = starting_positions.0 as u32;
 	let mut player2_position = starting_positions.1 as u32;
 	let mut player1_score: u32 = 0;
@@ -17,7 +59,7 @@ pub fn part1(starting_positions: &(u8, u8)) -> u32 {
 
 	let mut is_player1_turn = true;
 
-	while player1_score < 1000 && player2_score < 1000 {
+	while player1_score < params.winning_score && player2_score < params.winning_score {
 		let position;
 		let score;
 		if is_player1_turn {
@@ -32,14 +74,14 @@ pub fn part1(starting_positions: &(u8, u8)) -> u32 {
 		*position += (rolled_count + 2 - 1) % 100 + 1;
 		*position += (rolled_count + 3 - 1) % 100 + 1;
 
-		*position = (*position - 1) % 10 + 1;
-		*score += *position as u32;
+		*position = (*position - 1) % params.board_size + 1;
+		*score += *position;
 
 		rolled_count += 3;
 		is_player1_turn = !is_player1_turn;
 	}
 
-	player1_score.min(player2_score) * rolled_count as u32
+	player1_score.min(player2_score) * rolled_count
 }
 
 fn get_wins_amount(
@@ -47,6 +89,7 @@ fn get_wins_amount(
 	starting_pos2: u32,
 	starting_score1: u32,
 	starting_score2: u32,
+	params: &Params,
 	memo: &mut HashMap<(u32, u32, u32, u32), (u64, u64)>,
 ) -> (u64, u64) {
 	let memo_key = (
@@ -64,12 +107,13 @@ fn get_wins_amount(
 	for dice1 in 1..=3 {
 		for dice2 in 1..=3 {
 			for dice3 in 1..=3 {
-				let pos1 = (starting_pos1 + dice1 + dice2 + dice3 - 1) % 10 + 1;
+				let pos1 = (starting_pos1 + dice1 + dice2 + dice3 - 1) % params.board_size + 1;
 				let score1 = starting_score1 + pos1;
-				if score1 >= 21 {
+				if score1 >= params.winning_score {
 					total_wins1 += 1
 				} else {
-					let (wins2, wins1) = get_wins_amount(starting_pos2, pos1, starting_score2, score1, memo);
+					let (wins2, wins1) =
+						get_wins_amount(starting_pos2, pos1, starting_score2, score1, params, memo);
 					total_wins1 += wins1;
 					total_wins2 += wins2;
 				}
@@ -82,12 +126,54 @@ fn get_wins_amount(
 	(total_wins1, total_wins2)
 }
 
-pub fn part2(positions: &(u8, u8)) -> u64 {
+pub fn part2(positions: &(u8, u8), params: &Params) -> u64 {
 	let mut memo = HashMap::new();
-	let (wins1, wins2) = get_wins_amount(positions.0 as u32, positions.1 as u32, 0, 0, &mut memo);
+	let (wins1, wins2) = get_wins_amount(
+		positions.0 as u32,
+		positions.1 as u32,
+		0,
+		0,
+		params,
+		&mut memo,
+	);
 	wins1.max(wins2)
 }
 
+pub fn generate_input(rng: &mut Rng, _size: usize) -> String {
+	format!(
+		"Player 1 starting position: {}\nPlayer 2 starting position: {}\n",
+		rng.range(1, 10),
+		rng.range(1, 10)
+	)
+}
+
+pub struct Day21;
+
+impl Solution for Day21 {
+	const DAY: u32 = 21;
+
+	type Input = (u8, u8);
+	type Output1 = u32;
+	type Output2 = u64;
+	type Params = Params;
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, params: &Self::Params) -> Self::Output1 {
+		part1(input, params)
+	}
+
+	fn part2(input: &Self::Input, params: &Self::Params) -> Self::Output2 {
+		part2(input, params)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -95,14 +181,33 @@ mod tests {
 	#[test]
 	fn part1_example() {
 		let input = (4, 8);
-		let result = part1(&input);
+		let result = part1(&input, &Params::puzzle(1));
 		assert_eq!(result, 739785);
 	}
 
+	#[test]
+	fn shorter_games() {
+		let input = (4, 8);
+		let params = Params {
+			winning_score: 20,
+			board_size: 10,
+		};
+		assert_eq!(part1(&input, &params), 9 * 15);
+		// Player 1 always wins on the first turn, in every one of the 27 universes
+		let params = Params {
+			winning_score: 1,
+			board_size: 10,
+		};
+		assert_eq!(part2(&input, &params), 27);
+
+		let mut params = Params::puzzle(2);
+		assert!(params.set("board_size", "0").is_err());
+	}
+
 	#[test]
 	fn part2_example() {
 		let input = (4, 8);
-		let result = part2(&input);
+		let result = part2(&input, &Params::puzzle(2));
 		assert_eq!(result, 444356092776315);
 	}
 }
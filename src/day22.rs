@@ -1,49 +1,35 @@
-use std::{
-	collections::HashSet,
-	convert::{TryFrom, TryInto},
-	num::ParseIntError,
+//! Day 22: Reactor Reboot
+//!
+//! Turns cuboids of reactor cubes on and off and counts the cubes left on.
+
+use crate::{
+	cancel,
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::{parse_param, unknown_param, PuzzleParams, Solution},
 };
 
+/// Both ends of every range are inclusive
 #[derive(Debug, Clone)]
 pub struct Cuboid {
-	x: (i32, i32),
-	y: (i32, i32),
-	z: (i32, i32),
+	pub x: (i32, i32),
+	pub y: (i32, i32),
+	pub z: (i32, i32),
 }
 
-#[derive(Debug)]
+/// Whether a step turns the cubes on or off
+#[derive(Debug, Clone)]
 pub enum StepAction {
 	On,
 	Off,
 }
 
-#[derive(Debug)]
-pub struct RebootStep(StepAction, Cuboid);
-
-#[derive(Debug)]
-pub enum ParseRangeError {
-	Empty,
-	BadLen,
-	ParseInt(ParseIntError),
-}
-
-#[derive(Debug)]
-pub enum ParseCuboidError {
-	Empty,
-	BadLen,
-	ParseRange(ParseRangeError),
-}
-
-#[derive(Debug)]
-pub enum ParseRebootStepError {
-	Empty,
-	BadLen,
-	BadAction,
-	ParseCuboid(ParseCuboidError),
-}
+/// A single step of the reboot, applied in order
+#[derive(Debug, Clone)]
+pub struct RebootStep(pub StepAction, pub Cuboid);
 
 impl Cuboid {
-	fn contains(&self, point: &(i32, i32, i32)) -> bool {
+	pub fn contains(&self, point: &(i32, i32, i32)) -> bool {
 		self.x.0 <= point.0
 			&& point.0 <= self.x.1
 			&& self.y.0 <= point.1
@@ -52,7 +38,7 @@ impl Cuboid {
 			&& point.2 <= self.z.1
 	}
 
-	fn clamp(&self, other: &Cuboid) -> Cuboid {
+	pub fn clamp(&self, other: &Cuboid) -> Cuboid {
 		Cuboid {
 			x: (self.x.0.max(other.x.0), self.x.1.min(other.x.1)),
 			y: (self.y.0.max(other.y.0), self.y.1.min(other.y.1)),
@@ -60,7 +46,7 @@ impl Cuboid {
 		}
 	}
 
-	fn intersection(&self, other: &Cuboid) -> Option<Cuboid> {
+	pub fn intersection(&self, other: &Cuboid) -> Option<Cuboid> {
 		if self.z.0 > other.z.1 || other.z.0 > self.z.1 {
 			return None;
 		}
@@ -73,103 +59,53 @@ impl Cuboid {
 		Some(self.clamp(other))
 	}
 
-	fn volume(&self) -> u64 {
-		(self.x.1 - self.x.0 + 1) as u64
-			* (self.y.1 - self.y.0 + 1) as u64
-			* (self.z.1 - self.z.0 + 1) as u64
-	}
-}
-
-impl From<ParseIntError> for ParseRangeError {
-	fn from(e: ParseIntError) -> Self {
-		ParseRangeError::ParseInt(e)
+	pub fn volume(&self) -> u64 {
+		let length = |range: (i32, i32)| (range.1 as i64 - range.0 as i64 + 1) as u64;
+		length(self.x) * length(self.y) * length(self.z)
 	}
 }
 
-impl From<ParseRangeError> for ParseCuboidError {
-	fn from(e: ParseRangeError) -> Self {
-		ParseCuboidError::ParseRange(e)
-	}
-}
-
-fn parse_range(value: &str) -> Result<(i32, i32), ParseRangeError> {
-	if value.is_empty() {
-		return Err(ParseRangeError::Empty);
-	}
-	let (start, end) = value[2..].split_once("..").ok_or(ParseRangeError::BadLen)?;
-	Ok((start.parse()?, end.parse()?))
-}
-
-impl TryFrom<&str> for Cuboid {
-	type Error = ParseCuboidError;
-
-	fn try_from(value: &str) -> Result<Self, Self::Error> {
-		if value.is_empty() {
-			return Err(Self::Error::Empty);
-		}
-		let ranges: Vec<_> = value
-			.split(',')
-			.map(parse_range)
-			.collect::<Result<_, _>>()?;
-		if ranges.len() != 3 {
-			return Err(Self::Error::BadLen);
-		}
-		Ok(Cuboid {
-			x: ranges[0],
-			y: ranges[1],
-			z: ranges[2],
-		})
-	}
+fn parse_range(source: &Source, value: &str, axis: &str) -> Result<(i32, i32), ParseError> {
+	let range = source.strip_prefix(value, &format!("{}=", axis))?;
+	let (start, end) = source.split_once(range, "..")?;
+	Ok((source.number(start)?, source.number(end)?))
 }
 
-impl From<ParseCuboidError> for ParseRebootStepError {
-	fn from(e: ParseCuboidError) -> Self {
-		ParseRebootStepError::ParseCuboid(e)
+fn parse_cuboid(source: &Source, value: &str) -> Result<Cuboid, ParseError> {
+	let ranges: Vec<&str> = value.split(',').collect();
+	if ranges.len() != 3 {
+		return Err(source.error(value, "expected ranges for x, y and z"));
 	}
+	Ok(Cuboid {
+		x: parse_range(source, ranges[0], "x")?,
+		y: parse_range(source, ranges[1], "y")?,
+		z: parse_range(source, ranges[2], "z")?,
+	})
 }
 
-impl TryFrom<&str> for RebootStep {
-	type Error = ParseRebootStepError;
-
-	fn try_from(value: &str) -> Result<Self, Self::Error> {
-		if value.is_empty() {
-			return Err(Self::Error::Empty);
+fn parse_reboot_step(source: &Source, value: &str) -> Result<RebootStep, ParseError> {
+	let (action_str, cuboid) = source.split_once(value, " ")?;
+
+	let action = match action_str {
+		"on" => StepAction::On,
+		"off" => StepAction::Off,
+		_ => {
+			return Err(source.error(
+				action_str,
+				format!("expected 'on' or 'off', found '{}'", action_str),
+			))
 		}
-		let (action_str, cuboid) = value.split_once(" ").ok_or(Self::Error::BadLen)?;
-
-		let action = match action_str {
-			"on" => StepAction::On,
-			"off" => StepAction::Off,
-			_ => return Err(Self::Error::BadAction),
-		};
+	};
 
-		Ok(RebootStep(action, cuboid.try_into()?))
-	}
+	Ok(RebootStep(action, parse_cuboid(source, cuboid)?))
 }
 
-pub fn parse_input(input: &str) -> Vec<RebootStep> {
-	input.lines().map(|l| l.try_into().unwrap()).collect()
-}
-
-fn count_cubes_in_cuboid(steps: &[RebootStep], region: &Cuboid) -> u32 {
-	let mut cubes = HashSet::new();
-
-	for step in steps {
-		let clamped = step.1.clamp(region);
-		for x in clamped.x.0..=clamped.x.1 {
-			for y in clamped.y.0..=clamped.y.1 {
-				for z in clamped.z.0..=clamped.z.1 {
-					let cube = (x, y, z);
-					match step.0 {
-						StepAction::On => cubes.insert(cube),
-						StepAction::Off => cubes.remove(&cube),
-					};
-				}
-			}
-		}
-	}
-
-	cubes.len() as u32
+pub fn parse_input(input: &str) -> Result<Vec<RebootStep>, ParseError> {
+	let source = Source::new(Day22::DAY, input);
+	input
+		.lines()
+		.map(|line| parse_reboot_step(&source, line))
+		.collect()
 }
 
 // From: http://twocentstudios.com/2016/08/16/calculating-the-area-of-multiple-intersecting-rectangles-with-swift
@@ -239,10 +175,11 @@ fn total_volume(cuboids: &Vec<Cuboid>) -> u64 {
  * From: https://github.com/Jellycious/aoc-2021/blob/main/src/days/day22.rs
  * Based on inclusion-exclusion principle. https://en.wikipedia.org/wiki/Inclusion%E2%80%93exclusion_principle
  */
-fn count_cubes(steps: Vec<RebootStep>) -> u64 {
+fn count_cubes(steps: &[RebootStep]) -> u64 {
 	let mut cuboids: Vec<(Cuboid, bool)> = Vec::new();
 
 	for step in steps {
+		cancel::checkpoint();
 		let mut extra_cuboids = Vec::new();
 		for c in cuboids.iter() {
 			if let Some(intersect) = step.1.intersection(&c.0) {
@@ -251,7 +188,7 @@ fn count_cubes(steps: Vec<RebootStep>) -> u64 {
 		}
 
 		if let StepAction::On = step.0 {
-			cuboids.push((step.1, true));
+			cuboids.push((step.1.clone(), true));
 		}
 		cuboids.append(&mut extra_cuboids);
 	}
@@ -264,22 +201,141 @@ fn count_cubes(steps: Vec<RebootStep>) -> u64 {
 	total_volume as u64
 }
 
-pub fn part1(steps: &[RebootStep]) -> u32 {
+/// Part 1 only counts the cubes within -region..=region on every axis, part 2
+/// counts all of them
+pub struct Params {
+	pub region: i32,
+}
+
+impl PuzzleParams for Params {
+	fn puzzle(_part: u32) -> Params {
+		Params { region: 50 }
+	}
+
+	fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+		match name {
+			"region" => self.region = parse_param(name, value)?,
+			_ => return Err(unknown_param(name, &["region"])),
+		}
+		if self.region < 0 {
+			return Err("the region can't have a negative size".into());
+		}
+		Ok(())
+	}
+}
+
+pub fn part1(steps: &[RebootStep], params: &Params) -> u64 {
+	let size = params.region;
 	let region = Cuboid {
-		x: (-50, 50),
-		y: (-50, 50),
-		z: (-50, 50),
+		x: (-size, size),
+		y: (-size, size),
+		z: (-size, size),
 	};
-	count_cubes_in_cuboid(steps, &region)
+	let clamped: Vec<RebootStep> = steps
+		.iter()
+		.filter_map(|step| Some(RebootStep(step.0.clone(), step.1.intersection(&region)?)))
+		.collect();
+	count_cubes(&clamped)
 }
 
-pub fn part2(steps: Vec<RebootStep>) -> u64 {
+pub fn part2(steps: &[RebootStep]) -> u64 {
 	count_cubes(steps)
 }
 
+/// Like in the puzzle, the first tenth of the steps are within the
+/// initialization region. The rest are large and stay out of it, at least
+/// along one of the axes.
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let initialization_steps = size.div_ceil(10);
+	let mut lines = Vec::new();
+	for i in 0..size {
+		let action = if i == 0 || rng.chance(2, 3) {
+			"on"
+		} else {
+			"off"
+		};
+		let outside = rng.below(3);
+		let ranges: Vec<(i32, i32)> = (0..3)
+			.map(|axis| {
+				if i < initialization_steps {
+					let start = rng.range(-50, 50);
+					return (start, (start + rng.range(0, 50)).min(50));
+				}
+				let length = rng.range(0, 40_000);
+				if axis != outside {
+					let start = rng.range(-100_000, 100_000);
+					(start, start + length)
+				} else if rng.chance(1, 2) {
+					let start = rng.range(51, 100_000);
+					(start, start + length)
+				} else {
+					let end = rng.range(-100_000, -51);
+					(end - length, end)
+				}
+			})
+			.collect();
+		lines.push(format!(
+			"{} x={}..{},y={}..{},z={}..{}",
+			action, ranges[0].0, ranges[0].1, ranges[1].0, ranges[1].1, ranges[2].0, ranges[2].1
+		));
+	}
+	lines.join("\n") + "\n"
+}
+
+pub struct Day22;
+
+impl Solution for Day22 {
+	const DAY: u32 = 22;
+
+	type Input = Vec<RebootStep>;
+	type Output1 = u64;
+	type Output2 = u64;
+	type Params = Params;
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, params: &Self::Params) -> Self::Output1 {
+		part1(input, params)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::differential::{check, compare, shrink_towards, shrink_vec};
+	use std::collections::HashSet;
+
+	// Turns every single cube on and off, only feasible for small regions
+	fn count_cubes_in_cuboid(steps: &[RebootStep], region: &Cuboid) -> u64 {
+		let mut cubes = HashSet::new();
+
+		for step in steps {
+			let clamped = step.1.clamp(region);
+			for x in clamped.x.0..=clamped.x.1 {
+				for y in clamped.y.0..=clamped.y.1 {
+					for z in clamped.z.0..=clamped.z.1 {
+						let cube = (x, y, z);
+						match step.0 {
+							StepAction::On => cubes.insert(cube),
+							StepAction::Off => cubes.remove(&cube),
+						};
+					}
+				}
+			}
+		}
+
+		cubes.len() as u64
+	}
 
 	#[test]
 	fn part1_example() {
@@ -306,8 +362,9 @@ off x=18..30,y=-20..-8,z=-3..13
 on x=-41..9,y=-7..43,z=-33..15
 on x=-54112..-39298,y=-85059..-49293,z=-27449..7877
 on x=967..23432,y=45373..81175,z=27513..53682",
-		);
-		let result = part1(&steps);
+		)
+		.unwrap();
+		let result = part1(&steps, &Params::puzzle(1));
 		assert_eq!(result, 590784);
 	}
 
@@ -374,8 +431,94 @@ off x=-27365..46395,y=31009..98017,z=15428..76570
 off x=-70369..-16548,y=22648..78696,z=-1892..86821
 on x=-53470..21291,y=-120233..-33476,z=-44150..38147
 off x=-93533..-4276,y=-16170..68771,z=-104985..-24507",
-		);
-		let result = part2(steps);
+		)
+		.unwrap();
+		let result = part2(&steps);
 		assert_eq!(result, 2758514936282235);
 	}
+
+	// Smaller cuboids, by moving one of the ends of a range towards the other
+	fn shrink_step(step: &RebootStep) -> Vec<RebootStep> {
+		let ranges = [step.1.x, step.1.y, step.1.z];
+		let mut candidates = vec![];
+		for axis in 0..3 {
+			let (low, high) = ranges[axis];
+			let smaller_ranges = shrink_towards(low, high)
+				.into_iter()
+				.map(|low| (low, high))
+				.chain(
+					shrink_towards(high, low)
+						.into_iter()
+						.map(|high| (low, high)),
+				);
+			for range in smaller_ranges {
+				let mut smaller = ranges;
+				smaller[axis] = range;
+				let cuboid = Cuboid {
+					x: smaller[0],
+					y: smaller[1],
+					z: smaller[2],
+				};
+				candidates.push(RebootStep(step.0.clone(), cuboid));
+			}
+		}
+		candidates
+	}
+
+	#[test]
+	fn region_param() {
+		let mut params = Params::puzzle(1);
+		params.set("region", "0").unwrap();
+		assert_eq!(params.region, 0);
+		assert!(params.set("region", "-1").is_err());
+	}
+
+	#[test]
+	fn large_region() {
+		let steps = parse_input("on x=-200000..10,y=0..0,z=0..0\noff x=5..5,y=0..0,z=0..0").unwrap();
+		assert_eq!(part1(&steps, &Params { region: 100_000 }), 100_010);
+		assert_eq!(part1(&steps, &Params { region: i32::MAX }), 200_010);
+	}
+
+	// The region cuts through some of the cuboids, so clamping gets tested too
+	#[test]
+	fn inclusion_exclusion_matches_counting_cubes() {
+		let region = Cuboid {
+			x: (-4, 4),
+			y: (-4, 4),
+			z: (-4, 4),
+		};
+		check(
+			22,
+			300,
+			|rng| {
+				(0..rng.range(1, 6))
+					.map(|_| {
+						let action = if rng.chance(1, 2) {
+							StepAction::On
+						} else {
+							StepAction::Off
+						};
+						let mut range = || {
+							let start = rng.range(-6, 6);
+							(start, start + rng.range(0, 6))
+						};
+						let cuboid = Cuboid {
+							x: range(),
+							y: range(),
+							z: range(),
+						};
+						RebootStep(action, cuboid)
+					})
+					.collect::<Vec<_>>()
+			},
+			|steps| shrink_vec(steps, shrink_step),
+			|steps| {
+				compare(
+					count_cubes_in_cuboid(steps, &region),
+					part1(steps, &Params { region: 4 }),
+				)
+			},
+		);
+	}
 }
@@ -1,17 +1,28 @@
+//! Day 23: Amphipod
+//!
+//! Finds the cheapest way to sort the amphipods into their rooms.
+
+use crate::{
+	generate::Rng,
+	grid::Grid,
+	parse::{ParseError, Source},
+	search::{astar, dijkstra, SearchResult},
+	solution::Solution,
+	visualize::{Cell, Frame, FrameSink},
+};
 use core::fmt;
-use std::cmp::Reverse;
-
-use priority_queue::PriorityQueue;
 
 const HALLWAY_POINTS: &[Point] = &[Point(1, 1), Point(2, 1), Point(4, 1), Point(6, 1), Point(8, 1), Point(10, 1), Point(11, 1)];
 
+/// The kind of an amphipod, each one has its own room
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Amphipod {
 	A, B, C, D
 }
 
+/// Column and row in the burrow, the hallway being row 1
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct Point(u32, u32);
+pub struct Point(pub u32, pub u32);
 
 impl fmt::Debug for Point {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -20,24 +31,27 @@ impl fmt::Debug for Point {
     }
 }
 
+/// The positions of all amphipods, `amphipod_types` is in the same order
 #[derive(Debug, Clone)]
 pub struct Map {
-	room_size: u32,
-	amphipod_positions: Vec<Point>,
-	amphipod_types: Vec<Amphipod>
+	pub room_size: u32,
+	pub amphipod_positions: Vec<Point>,
+	pub amphipod_types: Vec<Amphipod>
 }
 
-pub fn parse_input(input: &str) -> Map {
+pub fn parse_input(input: &str) -> Result<Map, ParseError> {
+	let source = Source::new(Day23::DAY, input);
 	let mut positions = vec![];
 	let mut types = vec![];
 	for (y, line) in input.lines().enumerate() {
-		for (x, c) in line.chars().enumerate() {
+		for (x, (i, c)) in line.char_indices().enumerate() {
 			let amphipod = match c {
 				'A' => Amphipod::A,
 				'B' => Amphipod::B,
 				'C' => Amphipod::C,
 				'D' => Amphipod::D,
-				_ => continue
+				'#' | '.' | ' ' => continue,
+				_ => return Err(source.error(&line[i..], format!("unexpected character '{}'", c)))
 			};
 
 			positions.push(Point(x as u32, y as u32));
@@ -45,13 +59,18 @@ pub fn parse_input(input: &str) -> Map {
 		}
 	}
 
-	let room_size = (input.lines().count()-3) as u32;
+	// Hallway with its walls, at least one row of rooms and the bottom wall
+	let line_count = input.lines().count();
+	if line_count < 4 {
+		return Err(source.error(source.end(), "expected a burrow with at least one row of rooms"));
+	}
+	let room_size = (line_count-3) as u32;
 
-	Map {
+	Ok(Map {
 		room_size,
 		amphipod_types: types,
 		amphipod_positions: positions
-	}
+	})
 }
 
 fn is_in_hallway(point: &Point) -> bool {
@@ -76,54 +95,29 @@ fn get_amphipod_cost(amphipod: &Amphipod) -> u32 {
 	}
 }
 
-#[allow(dead_code)]
-fn dbg_map(map: &Map, positions: &[Point]) {
-	fn print_amphipod(map: &Map, positions: &[Point], x: u32, y: u32) {
-		for (i, Point(ix, iy)) in positions.iter().enumerate() {
-			if *ix == x && *iy == y {
-				match map.amphipod_types[i] {
-					Amphipod::A => print!("A"),
-					Amphipod::B => print!("B"),
-					Amphipod::C => print!("C"),
-					Amphipod::D => print!("D"),
-				}
-				return;
-			}
+// Same layout as the input, walls and all
+fn burrow_frame(map: &Map, positions: &[Point]) -> Frame {
+	let height = map.room_size as usize + 3;
+	let mut frame = Grid::new(13, height, Cell::new('#', [90, 90, 90]));
+	for (x, y) in frame.positions() {
+		let in_hallway = y == 1 && (1..=11).contains(&x);
+		let in_room = y >= 2 && y < height-1 && [3, 5, 7, 9].contains(&x);
+		if in_hallway || in_room {
+			frame[(x, y)] = Cell::new('.', [20, 20, 20]);
+		} else if y >= 3 && !(2..=10).contains(&x) {
+			frame[(x, y)] = Cell::new(' ', [0, 0, 0]);
 		}
-
-		print!(".");
 	}
 
-	println!("#############");
-	print!("#");
-	for x in 1..=11 {
-		print_amphipod(map, positions, x, 1);
-	}
-	println!("#");
-
-	print!("###");
-	print_amphipod(map, positions, 3, 2);
-	print!("#");
-	print_amphipod(map, positions, 5, 2);
-	print!("#");
-	print_amphipod(map, positions, 7, 2);
-	print!("#");
-	print_amphipod(map, positions, 9, 2);
-	println!("###");
-
-	for y in 3..2+map.room_size {
-		print!("  #");
-		print_amphipod(map, positions, 3, y);
-		print!("#");
-		print_amphipod(map, positions, 5, y);
-		print!("#");
-		print_amphipod(map, positions, 7, y);
-		print!("#");
-		print_amphipod(map, positions, 9, y);
-		println!("#  ");
+	for (point, amphipod) in positions.iter().zip(&map.amphipod_types) {
+		frame[(point.0 as usize, point.1 as usize)] = match amphipod {
+			Amphipod::A => Cell::new('A', [220, 60, 60]),
+			Amphipod::B => Cell::new('B', [230, 200, 50]),
+			Amphipod::C => Cell::new('C', [60, 180, 80]),
+			Amphipod::D => Cell::new('D', [70, 110, 230]),
+		};
 	}
-
-	println!("  #########  ");
+	frame
 }
 
 fn is_solved(map: &Map, positions: &[Point]) -> bool {
@@ -136,124 +130,135 @@ fn is_solved(map: &Map, positions: &[Point]) -> bool {
 	true
 }
 
-fn solve(map: &Map) -> u32 {
-	if is_solved(map, &map.amphipod_positions) { return 0; }
-
-	fn push_next_state(states: &mut PriorityQueue<Vec<Point>, Reverse<u32>>, next_state: Vec<Point>, cost: u32) {
-		if let Some((_, Reverse(v))) = states.get(&next_state) {
-			if cost < *v {
-				states.push(next_state, Reverse(cost));
-			}
-		} else {
-			states.push(next_state, Reverse(cost));
-		}
-	}
-
-	fn has_point(points: &[Point], x: u32, y: u32) -> bool {
-		for p in points {
-			if p.0 == x && p.1 == y {
-				return true;
-			}
+fn has_point(points: &[Point], x: u32, y: u32) -> bool {
+	for p in points {
+		if p.0 == x && p.1 == y {
+			return true;
 		}
-		false
 	}
+	false
+}
 
-	fn get_top_free_point(map: &Map, state: &[Point], column: u32) -> Point {
-		for y in (2..(2+map.room_size)).rev() {
-			if !has_point(state, column, y) {
-				return Point(column, y);
-			}
+fn get_top_free_point(map: &Map, state: &[Point], column: u32) -> Point {
+	for y in (2..(2+map.room_size)).rev() {
+		if !has_point(state, column, y) {
+			return Point(column, y);
 		}
-		panic!("this should never happen")
 	}
+	panic!("this should never happen")
+}
 
-	let mut states = PriorityQueue::new();
-	states.push(map.amphipod_positions.clone(), Reverse(0));
-	while !states.is_empty() {
-		let (state, Reverse(state_cost)) = states.pop().unwrap();
-
-		if is_solved(map, &state) {
-			return state_cost;
-		}
+// Every state reachable by moving a single amphipod, with the energy it takes
+fn next_states(map: &Map, state: &[Point]) -> Vec<(Vec<Point>, u32)> {
+	let mut next_states = vec![];
 
-		'outer: for (i, point) in state.iter().enumerate() {
-			let home_column = get_home_column(&map.amphipod_types[i]);
-			let step_cost = get_amphipod_cost(&map.amphipod_types[i]);
+	'outer: for (i, point) in state.iter().enumerate() {
+		let home_column = get_home_column(&map.amphipod_types[i]);
+		let step_cost = get_amphipod_cost(&map.amphipod_types[i]);
 
-			if is_in_hallway(point) {
-				for y in (2..(2+map.room_size)).rev() {
-					let index = state.iter().position(|p| p.0 == home_column && p.1 == y);
-					if let Some(index) = index {
-						if map.amphipod_types[index] != map.amphipod_types[i] { continue 'outer }
-					}
+		if is_in_hallway(point) {
+			for y in (2..(2+map.room_size)).rev() {
+				let index = state.iter().position(|p| p.0 == home_column && p.1 == y);
+				if let Some(index) = index {
+					if map.amphipod_types[index] != map.amphipod_types[i] { continue 'outer }
 				}
+			}
 
-				let x_range = {
-					if point.0 > home_column {
-						home_column..=(point.0-1)
-					} else {
-						(point.0+1)..=home_column
-					}
-				};
-				for x in x_range {
-					if has_point(&state, x, 1) { continue 'outer }
+			let x_range = {
+				if point.0 > home_column {
+					home_column..=(point.0-1)
+				} else {
+					(point.0+1)..=home_column
 				}
+			};
+			for x in x_range {
+				if has_point(state, x, 1) { continue 'outer }
+			}
 
-				let top_free_point = get_top_free_point(map, &state, home_column);
-				let mut next_state = state.clone();
-				next_state[i] = top_free_point;
-				let cost = state_cost + ((top_free_point.1 - 1) + (home_column as i32 - point.0 as i32).abs() as u32) * step_cost;
-				push_next_state(&mut states, next_state, cost);
-			} else {
-				// Check if there are not amphipod above
-				for y in 2..point.1 {
-					if has_point(&state, point.0, y) { continue 'outer }
-				}
+			let top_free_point = get_top_free_point(map, state, home_column);
+			let mut next_state = state.to_vec();
+			next_state[i] = top_free_point;
+			let cost = ((top_free_point.1 - 1) + home_column.abs_diff(point.0)) * step_cost;
+			next_states.push((next_state, cost));
+		} else {
+			// Check if there are not amphipod above
+			for y in 2..point.1 {
+				if has_point(state, point.0, y) { continue 'outer }
+			}
 
-				// Check if amphipod is block anything below it
-				if home_column == point.0 {
-					let mut needs_moving = false;
-					for y in (point.1+1)..=(2+map.room_size) {
-						let index = state.iter().position(|p| p.0 == point.0 && p.1 == y);
-						if let Some(index) = index {
-							if map.amphipod_types[index] != map.amphipod_types[i] {
-								needs_moving = true;
-								break;
-							}
+			// Check if amphipod is block anything below it
+			if home_column == point.0 {
+				let mut needs_moving = false;
+				for y in (point.1+1)..=(2+map.room_size) {
+					let index = state.iter().position(|p| p.0 == point.0 && p.1 == y);
+					if let Some(index) = index {
+						if map.amphipod_types[index] != map.amphipod_types[i] {
+							needs_moving = true;
+							break;
 						}
 					}
-
-					if !needs_moving {
-						continue 'outer;
-					}
 				}
 
-				'inner: for hallway_point in HALLWAY_POINTS {
-					if !state.contains(hallway_point) {
-						let from_x = point.0.min(hallway_point.0);
-						let to_x = point.0.max(hallway_point.0);
-						for x in from_x..to_x {
-							if has_point(&state, x, 1) { continue 'inner }
-						}
+				if !needs_moving {
+					continue 'outer;
+				}
+			}
 
-						let mut next_state = state.clone();
-						next_state[i] = *hallway_point;
-						let cost = state_cost + ((point.1 - 1) + (to_x - from_x)) * step_cost;
-						push_next_state(&mut states, next_state, cost);
+			'inner: for hallway_point in HALLWAY_POINTS {
+				if !state.contains(hallway_point) {
+					let from_x = point.0.min(hallway_point.0);
+					let to_x = point.0.max(hallway_point.0);
+					for x in from_x..to_x {
+						if has_point(state, x, 1) { continue 'inner }
 					}
+
+					let mut next_state = state.to_vec();
+					next_state[i] = *hallway_point;
+					let cost = ((point.1 - 1) + (to_x - from_x)) * step_cost;
+					next_states.push((next_state, cost));
 				}
 			}
 		}
 	}
 
-	panic!("how did we get here?");
+	next_states
+}
+
+/// The cheapest way of organizing the amphipods, every step of the path has the
+/// positions of all amphipods
+pub fn organize(map: &Map) -> SearchResult<Vec<Point>> {
+	dijkstra(
+		map.amphipod_positions.clone(),
+		|state| next_states(map, state),
+		|state| is_solved(map, state)
+	).expect("how did we get here?")
 }
 
-pub fn part1(map: Map) -> u32 {
-	solve(&map)
+// Only whether there is a way at all matters here, so instead of the cheapest
+// one the search goes for whatever gets the most amphipods home
+fn can_organize(map: &Map) -> bool {
+	let not_home = |state: &Vec<Point>| {
+		state.iter().zip(&map.amphipod_types).filter(|(point, amphipod)| point.0 != get_home_column(amphipod)).count() as u32
+	};
+	astar(
+		map.amphipod_positions.clone(),
+		|state| next_states(map, state),
+		|state| not_home(state) * 100_000,
+		|state| is_solved(map, state)
+	).is_some()
 }
 
-pub fn part2(mut map: Map) -> u32 {
+fn solve(map: &Map) -> u32 {
+	organize(map).cost
+}
+
+pub fn part1(map: &Map) -> u32 {
+	solve(map)
+}
+
+// Adds the two rows folded into the middle of the rooms
+fn unfold(map: &Map) -> Map {
+	let mut map = map.clone();
 	map.room_size = 4;
 	for position in [Point(3, 3), Point(5,3), Point(7,3), Point(9,3)] {
 		let index = map.amphipod_positions.iter().position(|p| *p == position);
@@ -276,13 +281,78 @@ pub fn part2(mut map: Map) -> u32 {
 		map.amphipod_positions.push(position);
 		map.amphipod_types.push(r#type);
 	}
+	map
+}
+
+pub fn part2(map: &Map) -> u32 {
+	solve(&unfold(map))
+}
+
+/// Every move of the cheapest way to organize them, part 2 in the unfolded burrow
+pub fn visualize(map: &Map, part: u32, frames: &mut dyn FrameSink) {
+	let map = if part == 1 { map.clone() } else { unfold(map) };
+	let path = organize(&map).path;
+	let mut energy = 0;
+	for (i, positions) in path.iter().enumerate() {
+		if i > 0 {
+			let (_, cost) = next_states(&map, &path[i-1]).into_iter().find(|(state, _)| state == positions).unwrap();
+			energy += cost;
+		}
+		frames.push(&burrow_frame(&map, positions), &[("energy", energy.to_string())]);
+	}
+}
+
+/// Two of every amphipod type shuffled into the rooms. Some orders get stuck
+/// once the rooms are unfolded, those are shuffled again.
+pub fn generate_input(rng: &mut Rng, _size: usize) -> String {
+	let mut amphipods: Vec<char> = "AABBCCDD".chars().collect();
+	let row = |a: &[char]| format!("#{}#{}#{}#{}#", a[0], a[1], a[2], a[3]);
+	loop {
+		rng.shuffle(&mut amphipods);
+		let input = format!("#############\n#...........#\n##{}##\n  {}\n  #########\n", row(&amphipods[..4]), row(&amphipods[4..]));
+		let map = parse_input(&input).unwrap();
+		if can_organize(&map) && can_organize(&unfold(&map)) {
+			return input;
+		}
+	}
+}
+
+pub struct Day23;
+
+impl Solution for Day23 {
+	const DAY: u32 = 23;
+
+	type Input = Map;
+	type Output1 = u32;
+	type Output2 = u32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
 
-	solve(&map)
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+
+	fn visualize(input: &Self::Input, part: u32, _params: &Self::Params, frames: &mut dyn FrameSink) -> bool {
+		visualize(input, part, frames);
+		true
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::visualize::render_text;
 
 	#[test]
 	fn part1_example() {
@@ -292,8 +362,8 @@ mod tests {
 			"###B#C#B#D###",
 			"  #A#D#C#A#  ",
 			"  #########  "
-		].join("\n"));
-		let result = part1(map);
+		].join("\n")).unwrap();
+		let result = part1(&map);
 		assert_eq!(result, 12521);
 	}
 
@@ -305,8 +375,8 @@ mod tests {
 			"###C#B#D#A###",
 			"  #B#D#A#C#  ",
 			"  #########  "
-		].join("\n"));
-		let result = part1(map);
+		].join("\n")).unwrap();
+		let result = part1(&map);
 		assert_eq!(result, 13520);
 	}
 
@@ -318,8 +388,32 @@ mod tests {
 			"###B#C#B#D###",
 			"  #A#D#C#A#  ",
 			"  #########  "
-		].join("\n"));
-		let result = part2(map);
+		].join("\n")).unwrap();
+		let result = part2(&map);
 		assert_eq!(result, 44169);
 	}
+
+	#[test]
+	fn frame_for_every_move() {
+		let map = parse_input(&[
+			"#############",
+			"#...........#",
+			"###B#A#C#D###",
+			"  #A#B#C#D#  ",
+			"  #########  "
+		].join("\n")).unwrap();
+		let mut frames = vec![];
+		visualize(&map, 1, &mut frames);
+		let rendered: Vec<String> = frames.iter().map(render_text).collect();
+		assert_eq!(rendered.first().unwrap(), &[
+			"#############",
+			"#...........#",
+			"###B#A#C#D###",
+			"  #A#B#C#D#  ",
+			"  #########  ",
+			"",
+		].join("\n"));
+		assert!(rendered.last().unwrap().contains("###A#B#C#D###"));
+		assert_eq!(frames.len(), organize(&map).path.len());
+	}
 }
@@ -1,16 +1,31 @@
+//! Day 24: Arithmetic Logic Unit
+//!
+//! Finds the largest and smallest model numbers accepted by the MONAD program.
+//! The program can be run on a [`CPU`], but the answers come from analysing its
+//! structure instead.
+
 // Beautiful explanation: https://github.com/dphilipson/advent-of-code-2021/blob/master/src/days/day24.rs
 
+use crate::{
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
+
+/// One of the four registers of the ALU
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Register {
 	X, Y, Z, W
 }
 
+/// Second operand of an instruction
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Operand {
 	Register(Register),
 	Number(i32)
 }
 
+/// A single instruction of the MONAD program
 #[derive(Debug, PartialEq, Eq)]
 pub enum Instruction {
 	Input(Register),
@@ -21,17 +36,20 @@ pub enum Instruction {
 	Equal(Register, Operand)
 }
 
+/// The ALU, with its registers and the input that is still left
 #[allow(clippy::upper_case_acronyms)]
+#[derive(Default)]
 pub struct CPU {
-	rx: i32,
-	ry: i32,
-	rz: i32,
-	rw: i32,
-	input: Vec<i32>
+	pub rx: i32,
+	pub ry: i32,
+	pub rz: i32,
+	pub rw: i32,
+	/// Consumed from the back, so it has to be pushed in reverse
+	pub input: Vec<i32>
 }
 
 impl CPU {
-	fn new() -> CPU {
+	pub fn new() -> CPU {
 		CPU { rx: 0, ry: 0, rz: 0, rw: 0, input: vec![] }
 	}
 
@@ -77,7 +95,7 @@ impl CPU {
 		*a = if *a == b { 1 } else { 0 };
 	}
 
-	fn run(&mut self, inst: &Instruction) {
+	pub fn run(&mut self, inst: &Instruction) {
 		use Instruction::*;
 		match *inst {
 			Input(a) => {
@@ -93,65 +111,49 @@ impl CPU {
 	}
 }
 
-fn parse_register(input: &str) -> Register {
+fn parse_register(source: &Source, input: &str) -> Result<Register, ParseError> {
 	match input {
-		"x" => Register::X,
-		"y" => Register::Y,
-		"z" => Register::Z,
-		"w" => Register::W,
-		_ => panic!("Unknown variable '{}'", input)
+		"x" => Ok(Register::X),
+		"y" => Ok(Register::Y),
+		"z" => Ok(Register::Z),
+		"w" => Ok(Register::W),
+		_ => Err(source.error(input, format!("unknown variable '{}'", input)))
 	}
 }
 
-fn parse_operand(input: &str) -> Operand {
+fn parse_operand(source: &Source, input: &str) -> Result<Operand, ParseError> {
 	if let Ok(number) = input.parse::<i32>() {
-		Operand::Number(number)
+		Ok(Operand::Number(number))
 	} else {
-		Operand::Register(parse_register(input))
+		Ok(Operand::Register(parse_register(source, input)?))
 	}
 }
 
-pub fn parse_input(input: &str) -> Vec<Instruction> {
+pub fn parse_input(input: &str) -> Result<Vec<Instruction>, ParseError> {
+	let source = Source::new(Day24::DAY, input);
 	let mut instructions = vec![];
 	for line in input.lines() {
 		if line.is_empty() { continue; }
 		let parts = line.split(' ').collect::<Vec<_>>();
-		let opcode = *parts.get(0).expect("Missing opcode");
+		let opcode = parts[0];
+		// Missing arguments are pointed out at the end of the line
+		let arg = |i: usize, what: &str| {
+			parts.get(i).copied().ok_or_else(|| source.error(&line[line.len()..], format!("missing {}", what)))
+		};
+		let variable = || parse_register(&source, arg(1, "variable")?);
+		let operand = || parse_operand(&source, arg(2, "operand")?);
 		let instruction = match opcode {
-			"inp" => {
-				let op1 = parts.get(1).expect("Missing variable");
-				Instruction::Input(parse_register(op1))
-			},
-			"add" => {
-				let op1 = parts.get(1).expect("Missing variable");
-				let op2 = parts.get(2).expect("Missing operand");
-				Instruction::Add(parse_register(op1), parse_operand(op2))
-			},
-			"mul" => {
-				let op1 = parts.get(1).expect("Missing variable");
-				let op2 = parts.get(2).expect("Missing operand");
-				Instruction::Multiply(parse_register(op1), parse_operand(op2))
-			}
-			"div" => {
-				let op1 = parts.get(1).expect("Missing variable");
-				let op2 = parts.get(2).expect("Missing operand");
-				Instruction::Divide(parse_register(op1), parse_operand(op2))
-			},
-			"mod" => {
-				let op1 = parts.get(1).expect("Missing variable");
-				let op2 = parts.get(2).expect("Missing operand");
-				Instruction::Modulo(parse_register(op1), parse_operand(op2))
-			}
-			"eql" => {
-				let op1 = parts.get(1).expect("Missing variable");
-				let op2 = parts.get(2).expect("Missing operand");
-				Instruction::Equal(parse_register(op1), parse_operand(op2))
-			},
-			_ => panic!("Unexpected opcode '{}'", opcode)
+			"inp" => Instruction::Input(variable()?),
+			"add" => Instruction::Add(variable()?, operand()?),
+			"mul" => Instruction::Multiply(variable()?, operand()?),
+			"div" => Instruction::Divide(variable()?, operand()?),
+			"mod" => Instruction::Modulo(variable()?, operand()?),
+			"eql" => Instruction::Equal(variable()?, operand()?),
+			_ => return Err(source.error(opcode, format!("unknown opcode '{}'", opcode)))
 		};
 		instructions.push(instruction);
 	}
-	instructions
+	Ok(instructions)
 }
 
 fn check(instructions: &[Instruction], monad: &[u8]) -> bool {
@@ -215,9 +217,9 @@ fn concat_nums(nums: &[u8]) -> u64 {
 	answer
 }
 
-pub fn part1(instructions: Vec<Instruction>) -> u64 {
+pub fn part1(instructions: &[Instruction]) -> u64 {
 	let mut monad = [9u8; 14];
-	for (i, j, offset) in analyze_requirements(&instructions) {
+	for (i, j, offset) in analyze_requirements(instructions) {
 		if offset > 0 {
 			monad[j as usize] = (9 - offset) as u8;
 		} else {
@@ -225,14 +227,14 @@ pub fn part1(instructions: Vec<Instruction>) -> u64 {
 		}
 	}
 
-	assert!(check(&instructions, &monad));
+	assert!(check(instructions, &monad));
 
 	concat_nums(&monad)
 }
 
-pub fn part2(instructions: Vec<Instruction>) -> u64 {
+pub fn part2(instructions: &[Instruction]) -> u64 {
 	let mut monad = [1u8; 14];
-	for (i, j, offset) in analyze_requirements(&instructions) {
+	for (i, j, offset) in analyze_requirements(instructions) {
 		if offset > 0 {
 			monad[i as usize] = (1 + offset) as u8;
 		} else {
@@ -240,11 +242,69 @@ pub fn part2(instructions: Vec<Instruction>) -> u64 {
 		}
 	}
 
-	assert!(check(&instructions, &monad));
+	assert!(check(instructions, &monad));
 
 	concat_nums(&monad)
 }
 
+/// Makes up a program with the same structure as MONAD: 14 blocks, half of which
+/// push a digit onto the stack in z, while the other half pop one and compare it
+/// to their own digit. The offsets are picked so that every comparison can be
+/// satisfied, which means that some model numbers are accepted.
+pub fn generate_input(rng: &mut Rng, _size: usize) -> String {
+	let mut blocks = vec![];
+	let mut stack = vec![];
+	for i in 0..14 {
+		let remaining = 14 - i;
+		let push = stack.is_empty() || (stack.len() + 1 < remaining && rng.chance(1, 2));
+		if push {
+			let offset = rng.range(1, 16);
+			stack.push(offset);
+			blocks.push((1, rng.range(10, 16), offset));
+		} else {
+			let pushed_offset = stack.pop().unwrap();
+			let difference = rng.range(-8, pushed_offset.min(8));
+			blocks.push((26, difference - pushed_offset, rng.range(1, 16)));
+		}
+	}
+
+	let mut lines = vec![];
+	for (a, b, c) in blocks {
+		lines.push("inp w\nmul x 0\nadd x z\nmod x 26".to_string());
+		lines.push(format!("div z {}\nadd x {}", a, b));
+		lines.push("eql x w\neql x 0\nmul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w".to_string());
+		lines.push(format!("add y {}\nmul y x\nadd z y", c));
+	}
+	lines.join("\n") + "\n"
+}
+
+pub struct Day24;
+
+impl Solution for Day24 {
+	const DAY: u32 = 24;
+
+	type Input = Vec<Instruction>;
+	type Output1 = u64;
+	type Output2 = u64;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -258,7 +318,7 @@ mod tests {
 				"inp x",
 				"mul z 3",
 				"eql z x",
-			].join("\n"));
+			].join("\n")).unwrap();
 		assert_eq!(instructions, vec![
 			Input(Z),
 			Input(X),
@@ -266,4 +326,12 @@ mod tests {
 			Equal(Z, Operand::Register(X))
 		])
 	}
+
+	#[test]
+	fn parse_error() {
+		let error = parse_input("inp x\nadd y").unwrap_err();
+		assert_eq!((error.line, error.column), (2, 6));
+		let error = parse_input("inp x\nsub y 2").unwrap_err();
+		assert_eq!(error.message, "unknown opcode 'sub'");
+	}
 }
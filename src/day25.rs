@@ -1,111 +1,155 @@
-#[derive(Debug, PartialEq, Eq)]
+//! Day 25: Sea Cucumber
+//!
+//! Moves herds of sea cucumbers until none of them can move anymore. Part 2 has
+//! no puzzle to solve, so it is left unimplemented.
+
+use crate::{
+	cancel,
+	generate::Rng,
+	grid::Grid,
+	parse::{ParseError, Source},
+	solution::Solution,
+	visualize::{render_text, Cell, Frame, FrameSink},
+};
+
+/// A sea cucumber facing east or south, or nothing
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Tile {
 	East,
 	South,
 	Empty
 }
 
-type SeaFloor = Vec<Vec<Tile>>;
-
-pub fn parse_input(input: &str) -> SeaFloor {
-	let mut floor = vec![];
-
-	for line in input.lines() {
-		let mut row = vec![];
-		for char in line.chars() {
-			match char {
-				'>' => row.push(Tile::East),
-				'v' => row.push(Tile::South),
-				'.' => row.push(Tile::Empty),
-				_ => panic!()
-			};
-		}
-		floor.push(row);
-	}
-	floor
+/// Wraps around on both axes
+pub type SeaFloor = Grid<Tile>;
+
+pub fn parse_input(input: &str) -> Result<SeaFloor, ParseError> {
+	let source = Source::new(Day25::DAY, input);
+	Grid::parse(&source, input, |c| match c {
+		'>' => Some(Tile::East),
+		'v' => Some(Tile::South),
+		'.' => Some(Tile::Empty),
+		_ => None
+	})
 }
 
-fn step(floor: &mut SeaFloor) -> bool {
-	let mut moved = false;
-	let width = floor.first().unwrap().len();
-	let height = floor.len();
-
-	{
-		for row in floor.iter_mut() {
-			let mut moved_tiles = vec![];
-			for (x, tile) in row.iter().enumerate() {
-				if *tile == Tile::East && row[(x+1) % width] == Tile::Empty {
-					moved_tiles.push(x);
-				}
-			}
-
-			if !moved_tiles.is_empty() {
-				moved = true;
-			}
-
-			for x in moved_tiles {
-				row[x] = Tile::Empty;
-				row[(x+1) % width] = Tile::East;
-			}
+// Moves every sea cucumber of the herd that has free space in front of it.
+// The sea floor wraps around, so the ones at the edge can move to the other side.
+// Returns how many of them moved.
+fn move_herd(floor: &mut SeaFloor, herd: Tile, dx: i32, dy: i32) -> usize {
+	let mut moved_tiles = vec![];
+	for (x, y) in floor.positions() {
+		let (x, y) = (x as i32, y as i32);
+		if *floor.get_wrapped(x, y) == herd && *floor.get_wrapped(x+dx, y+dy) == Tile::Empty {
+			moved_tiles.push((x, y));
 		}
 	}
 
-	{
-		for x in 0..width {
-			let mut moved_tiles = vec![];
-			for y in 0..height {
-				if floor[y][x] == Tile::South && floor[(y+1) % height][x] == Tile::Empty {
-					moved_tiles.push(y);
-				}
-			}
-
-			if !moved_tiles.is_empty() {
-				moved = true;
-			}
-
-			for y in moved_tiles {
-				floor[y][x] = Tile::Empty;
-				floor[(y+1) % height][x] = Tile::South;
-			}
-		}
+	for (x, y) in &moved_tiles {
+		*floor.get_wrapped_mut(*x, *y) = Tile::Empty;
+		*floor.get_wrapped_mut(x+dx, y+dy) = herd.clone();
 	}
 
-	moved
+	moved_tiles.len()
 }
 
-fn show_seafloor(floor: &SeaFloor) {
-	for row in floor {
-		for tile in row {
-			match tile {
-				Tile::East => print!(">"),
-				Tile::South => print!("v"),
-				Tile::Empty => print!("."),
-			}
-		}
-		println!();
-	}
-	println!();
+fn step(floor: &mut SeaFloor) -> usize {
+	let moved_east = move_herd(floor, Tile::East, 1, 0);
+	let moved_south = move_herd(floor, Tile::South, 0, 1);
+	moved_east + moved_south
+}
+
+fn seafloor_frame(floor: &SeaFloor) -> Frame {
+	floor.map(|tile| match tile {
+		Tile::East => Cell::new('>', [230, 120, 40]),
+		Tile::South => Cell::new('v', [60, 200, 120]),
+		Tile::Empty => Cell::new('.', [10, 20, 60]),
+	})
 }
 
-pub fn part1(mut floor: SeaFloor) -> u32 {
+pub fn part1(floor: &SeaFloor) -> u32 {
+	let mut floor = floor.clone();
 	let mut count = 1;
-	while step(&mut floor) {
+	while step(&mut floor) > 0 {
+		cancel::checkpoint();
 		count += 1;
 	}
 	count
 }
 
-pub fn part2(floor: SeaFloor) -> u32 {
+pub fn part2(_floor: &SeaFloor) -> u32 {
 	todo!();
 }
 
+/// Every step until the sea cucumbers stop, so there is a frame for each step
+/// counted by part 1
+pub fn visualize(floor: &SeaFloor, frames: &mut dyn FrameSink) {
+	let mut floor = floor.clone();
+	frames.push(&seafloor_frame(&floor), &[("moved", "0".into())]);
+	loop {
+		cancel::checkpoint();
+		let moved = step(&mut floor);
+		if moved == 0 {
+			break;
+		}
+		frames.push(&seafloor_frame(&floor), &[("moved", moved.to_string())]);
+	}
+}
+
+/// A third of the tiles are empty. Some layouts never stop moving, those are
+/// thrown away.
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let side = size.max(1);
+	loop {
+		let tiles = (0..side * side).map(|_| rng.choose(&[Tile::East, Tile::South, Tile::Empty]).clone()).collect();
+		let floor = Grid::from_cells(side, side, tiles);
+		let mut moving_floor = floor.clone();
+		if (0..10 * side * side).any(|_| step(&mut moving_floor) == 0) {
+			return render_text(&seafloor_frame(&floor));
+		}
+	}
+}
+
+pub struct Day25;
+
+impl Solution for Day25 {
+	const DAY: u32 = 25;
+
+	type Input = SeaFloor;
+	type Output1 = u32;
+	type Output2 = u32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+
+	fn visualize(input: &Self::Input, part: u32, _params: &Self::Params, frames: &mut dyn FrameSink) -> bool {
+		if part == 1 {
+			visualize(input, frames);
+		}
+		part == 1
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
-	#[test]
-	fn part1_example() {
- 		let map = parse_input(&[
+	fn example() -> SeaFloor {
+		parse_input(&[
 			"v...>>.vv>",
 			".vv>>.vv..",
 			">>.>v>...v",
@@ -115,8 +159,32 @@ mod tests {
 			".vv..>.>v.",
 			"v.v..>>v.v",
 			"....v..v.>",
-		].join("\n"));
-		let result = part1(map);
+		].join("\n")).unwrap()
+	}
+
+	#[test]
+	fn part1_example() {
+		let result = part1(&example());
 		assert_eq!(result, 58);
 	}
+
+	#[test]
+	fn frame_for_every_step() {
+		let mut frames = vec![];
+		visualize(&example(), &mut frames);
+		assert_eq!(frames.len(), 58);
+		assert_eq!(render_text(frames.last().unwrap()), [
+			"..>>v>vv..",
+			"..v.>>vv..",
+			"..>>v>>vv.",
+			"..>>>>>vv.",
+			"v......>vv",
+			"v>v....>>v",
+			"vvv.....>>",
+			">vv......>",
+			".>v.vv.v..",
+			"",
+		].join("\n"));
+		assert_eq!(frames[0][(0, 0)], Cell::new('v', [60, 200, 120]));
+	}
 }
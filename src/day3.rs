@@ -1,123 +1,357 @@
-use std::{num::ParseIntError, slice::Iter};
+//! Day 3: Binary Diagnostic
+//!
+//! Derives power consumption and life support ratings from the most and least
+//! common bits of the diagnostic report. Readings can be up to 128 bits wide.
 
-pub fn parse_input(input: &str) -> Result<Vec<i32>, ParseIntError> {
-	input
-		.split_whitespace()
-		.map(|s| i32::from_str_radix(s, 2))
-		.collect()
+use crate::{
+	generate::Rng,
+	json::Json,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
+use std::collections::HashSet;
+
+/// The width comes from the text of the readings rather than their values, so
+/// that leading zeros count too. Every reading has the same width.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+	pub width: u32,
+	pub readings: Vec<u128>,
 }
 
-fn calculate_max_bits(nums: &[i32]) -> u32 {
-	let mut max_bits = 0;
-	for num in nums {
-		max_bits = max_bits.max((*num as f32).log2().ceil() as u32);
+impl Diagnostics {
+	// Columns are counted from the left, starting at 0
+	fn bit(&self, reading: u128, column: u32) -> bool {
+		(reading >> (self.width - 1 - column)) & 1 == 1
+	}
+
+	fn count_column(&self, readings: &[u128], column: u32) -> ColumnCount {
+		let ones = readings
+			.iter()
+			.filter(|reading| self.bit(**reading, column))
+			.count();
+		ColumnCount {
+			ones,
+			zeros: readings.len() - ones,
+		}
 	}
-	return max_bits;
 }
 
-fn count_bits(nums: Iter<i32>, power: &i32) -> u32 {
-	let mut bits = 0;
-	for num in nums {
-		if num & power > 0 {
-			bits += 1;
+pub fn parse_input(input: &str) -> Result<Diagnostics, ParseError> {
+	let source = Source::new(Day3::DAY, input);
+	let mut width = None;
+	let mut readings = Vec::new();
+	for s in input.split_whitespace() {
+		if !s.bytes().all(|b| b == b'0' || b == b'1') {
+			return Err(source.error(s, format!("invalid binary number '{}'", s)));
 		}
+		if s.len() > 128 {
+			return Err(source.error(
+				s,
+				format!("readings can't be wider than 128 bits, found {}", s.len()),
+			));
+		}
+		let expected = *width.get_or_insert(s.len());
+		if s.len() != expected {
+			return Err(source.error(
+				s,
+				format!(
+					"expected {} bits like the first reading, found {}",
+					expected,
+					s.len()
+				),
+			));
+		}
+		readings.push(u128::from_str_radix(s, 2).unwrap());
 	}
-	return bits;
+	Ok(Diagnostics {
+		width: width.unwrap_or(0) as u32,
+		readings,
+	})
+}
+
+/// How many readings have a 1 and a 0 in a column
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnCount {
+	pub ones: usize,
+	pub zeros: usize,
+}
+
+impl ColumnCount {
+	pub fn is_tie(&self) -> bool {
+		self.ones == self.zeros
+	}
+
+	/// Ties count as 1 being the most common
+	pub fn most_common(&self) -> bool {
+		self.ones >= self.zeros
+	}
+}
+
+pub fn column_counts(diagnostics: &Diagnostics) -> Vec<ColumnCount> {
+	(0..diagnostics.width)
+		.map(|column| diagnostics.count_column(&diagnostics.readings, column))
+		.collect()
 }
 
-pub fn part1(diagnostics: &[i32]) -> i32 {
-	let n = diagnostics.len() as u32;
+fn product(a: u128, b: u128, what: &str) -> Result<u128, String> {
+	a.checked_mul(b)
+		.ok_or_else(|| format!("{} {} times {} doesn't fit in 128 bits", what, a, b))
+}
+
+pub fn part1(diagnostics: &Diagnostics) -> Result<u128, String> {
 	let mut gamma = 0;
 	let mut epsilon = 0;
-
-	let max_bits = calculate_max_bits(&diagnostics);
-	let mut power = 1;
-	for _ in 1..=max_bits {
-		let bits = count_bits(diagnostics.iter(), &power);
-		if 2 * bits >= n {
-			gamma += power;
+	for count in column_counts(diagnostics) {
+		gamma <<= 1;
+		epsilon <<= 1;
+		if count.most_common() {
+			gamma |= 1;
 		} else {
-			epsilon += power;
-		}
-		power *= 2;
-	}
-
-	return gamma * epsilon;
-}
-
-pub fn part2(diagnostics: &[i32]) -> i32 {
-	let mut carbon_diagnostics = Vec::new();
-	let mut oxygen_diagnostics = Vec::new();
-
-	carbon_diagnostics.extend_from_slice(diagnostics);
-	oxygen_diagnostics.extend_from_slice(diagnostics);
-
-	let max_bits = calculate_max_bits(&diagnostics);
-	let mut power = 2i32.pow(max_bits - 1);
-	for _ in 1..=max_bits {
-		let oxygen_len = oxygen_diagnostics.len() as u32;
-		if oxygen_len > 1 {
-			let bit_count = count_bits(oxygen_diagnostics.iter(), &power);
-			if 2 * bit_count >= oxygen_len {
-				oxygen_diagnostics = oxygen_diagnostics
-					.into_iter()
-					.filter(|n| n & power > 0)
-					.collect();
-			} else {
-				oxygen_diagnostics = oxygen_diagnostics
-					.into_iter()
-					.filter(|n| n & power == 0)
-					.collect();
-			}
+			epsilon |= 1;
 		}
+	}
+	return product(gamma, epsilon, "power consumption");
+}
+
+/// How many readings had which bit in the column, and which of them were kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterStep {
+	pub column: u32,
+	pub count: ColumnCount,
+	/// Whether the readings with a 1 in the column were the ones kept
+	pub kept: bool,
+	pub remaining: usize,
+}
+
+/// A rating, and how the readings got filtered down to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rating {
+	pub value: Option<u128>,
+	pub steps: Vec<FilterStep>,
+}
+
+/// Keeps the numbers with the most (or least) common bit in each column, from
+/// the left, until only one is left. Ends up with nothing when all of the
+/// remaining numbers have the same bit and the least common one is wanted.
+pub fn find_rating(diagnostics: &Diagnostics, most_common: bool) -> Rating {
+	let mut remaining = diagnostics.readings.clone();
+	let mut steps = Vec::new();
+	let mut column = 0;
+	while remaining.len() > 1 && column < diagnostics.width {
+		let count = diagnostics.count_column(&remaining, column);
+		let kept = count.most_common() == most_common;
+		remaining.retain(|n| diagnostics.bit(*n, column) == kept);
+		steps.push(FilterStep {
+			column,
+			count,
+			kept,
+			remaining: remaining.len(),
+		});
+		column += 1;
+	}
+	return Rating {
+		value: remaining.first().copied(),
+		steps,
+	};
+}
+
+pub fn part2(diagnostics: &Diagnostics) -> Result<u128, String> {
+	let oxygen = find_rating(diagnostics, true)
+		.value
+		.ok_or("no oxygen generator rating left")?;
+	let carbon = find_rating(diagnostics, false)
+		.value
+		.ok_or("no CO2 scrubber rating left")?;
+	return product(carbon, oxygen, "life support rating");
+}
 
-		let carbon_len = carbon_diagnostics.len() as u32;
-		if carbon_len > 1 {
-			let bit_count = count_bits(carbon_diagnostics.iter(), &power);
-			if 2 * bit_count < carbon_len {
-				carbon_diagnostics = carbon_diagnostics
-					.into_iter()
-					.filter(|n| n & power > 0)
-					.collect();
-			} else {
-				carbon_diagnostics = carbon_diagnostics
-					.into_iter()
-					.filter(|n| n & power == 0)
-					.collect();
+fn count_to_json(column: u32, count: &ColumnCount) -> Vec<(&'static str, Json)> {
+	vec![
+		("column", Json::number(column)),
+		("ones", Json::number(count.ones)),
+		("zeros", Json::number(count.zeros)),
+		("tie", Json::Bool(count.is_tie())),
+	]
+}
+
+fn rating_to_json(rating: &Rating) -> Json {
+	let steps = rating
+		.steps
+		.iter()
+		.map(|step| {
+			let mut fields = count_to_json(step.column, &step.count);
+			fields.push(("kept", Json::Bool(step.kept)));
+			fields.push(("remaining", Json::number(step.remaining)));
+			Json::Object(fields)
+		})
+		.collect();
+	Json::Object(vec![
+		("rating", rating.value.map_or(Json::Null, Json::number)),
+		("steps", Json::Array(steps)),
+	])
+}
+
+pub fn report(diagnostics: &Diagnostics) -> Json {
+	let columns = column_counts(diagnostics)
+		.iter()
+		.zip(0..)
+		.map(|(count, column)| Json::Object(count_to_json(column, count)))
+		.collect();
+	Json::Object(vec![
+		("width", Json::number(diagnostics.width)),
+		("columns", Json::Array(columns)),
+		("oxygen", rating_to_json(&find_rating(diagnostics, true))),
+		("co2", rating_to_json(&find_rating(diagnostics, false))),
+	])
+}
+
+/// The numbers are all different and reports where one of the rating filters
+/// ends up with nothing are thrown away, so there are always both ratings
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let size = size.max(1);
+	let mut width = 5;
+	while (1 << width) < 4 * size {
+		width += 1;
+	}
+
+	loop {
+		let mut numbers = HashSet::new();
+		let mut readings = Vec::new();
+		while readings.len() < size {
+			let number = rng.below(1 << width) as u128;
+			if numbers.insert(number) {
+				readings.push(number);
 			}
 		}
 
-		if oxygen_len == 1 && carbon_len == 1 {
-			break;
+		let diagnostics = Diagnostics { width, readings };
+		if find_rating(&diagnostics, true).value.is_some()
+			&& find_rating(&diagnostics, false).value.is_some()
+		{
+			let lines: Vec<String> = diagnostics
+				.readings
+				.iter()
+				.map(|n| format!("{:0width$b}", n, width = width as usize))
+				.collect();
+			return lines.join("\n") + "\n";
 		}
+	}
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+	const DAY: u32 = 3;
+
+	type Input = Diagnostics;
+	type Output1 = Result<u128, String>;
+	type Output2 = Result<u128, String>;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
 
-		power /= 2;
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
 	}
 
-	return carbon_diagnostics[0] * oxygen_diagnostics[0];
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+
+	fn report(input: &Self::Input, _part: u32, _params: &Self::Params) -> Option<Json> {
+		Some(report(input))
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	const EXAMPLE: &str =
+		"00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010\n";
+
 	#[test]
 	fn part1_example() {
-		let diagnostics = [
-			0b00100, 0b11110, 0b10110, 0b10111, 0b10101, 0b01111, 0b00111, 0b11100, 0b10000, 0b11001,
-			0b00010, 0b01010,
-		];
+		let diagnostics = parse_input(EXAMPLE).unwrap();
 		let result = part1(&diagnostics);
-		assert_eq!(result, 198);
+		assert_eq!(result, Ok(198));
 	}
 
 	#[test]
 	fn part2_example() {
-		let diagnostics = [
-			0b00100, 0b11110, 0b10110, 0b10111, 0b10101, 0b01111, 0b00111, 0b11100, 0b10000, 0b11001,
-			0b00010, 0b01010,
-		];
+		let diagnostics = parse_input(EXAMPLE).unwrap();
 		let result = part2(&diagnostics);
-		assert_eq!(result, 230);
+		assert_eq!(result, Ok(230));
+	}
+
+	// The width used to come from the largest value, which got it wrong for
+	// leading zeros and for a largest value that is a power of two
+	#[test]
+	fn width_from_text() {
+		let diagnostics = parse_input("0100\n0010\n0011\n").unwrap();
+		assert_eq!(diagnostics.width, 4);
+		assert_eq!(part1(&diagnostics), Ok(0b0010 * 0b1101));
+
+		let wide = format!("{}\n{}\n", "1".repeat(100), "0".repeat(100));
+		let diagnostics = parse_input(&wide).unwrap();
+		assert_eq!(diagnostics.width, 100);
+		assert_eq!(diagnostics.readings, vec![(1 << 100) - 1, 0]);
+		assert_eq!(part1(&diagnostics), Ok(0));
+		assert_eq!(part2(&diagnostics), Ok(0));
+
+		// Answers can need all 128 bits
+		let halves = format!("1{}\n01{}\n", "0".repeat(64), "0".repeat(63));
+		assert_eq!(part2(&parse_input(&halves).unwrap()), Ok(1 << 127));
+
+		let full = format!("{}\n0{}\n", "1".repeat(128), "1".repeat(127));
+		let error = part2(&parse_input(&full).unwrap()).unwrap_err();
+		assert!(error.starts_with("life support rating"));
+	}
+
+	#[test]
+	fn parse_errors() {
+		let error = parse_input("0101\n011\n").unwrap_err();
+		assert_eq!((error.line, error.column), (2, 1));
+		assert_eq!(
+			error.message,
+			"expected 4 bits like the first reading, found 3"
+		);
+		assert_eq!(
+			parse_input("01\n+1\n").unwrap_err().message,
+			"invalid binary number '+1'"
+		);
+		assert!(parse_input(&"1".repeat(129)).is_err());
+		assert_eq!(parse_input("").unwrap().width, 0);
+	}
+
+	#[test]
+	fn report_json() {
+		let diagnostics = parse_input("10\n11\n01\n").unwrap();
+		assert_eq!(
+			column_counts(&diagnostics),
+			vec![
+				ColumnCount { ones: 2, zeros: 1 },
+				ColumnCount { ones: 2, zeros: 1 }
+			]
+		);
+		let rating = find_rating(&diagnostics, false);
+		assert_eq!(rating.value, Some(0b01));
+		assert_eq!(rating.steps.len(), 1);
+		assert_eq!(
+			report(&diagnostics).to_string(),
+			concat!(
+				r#"{"width":2,"columns":[{"column":0,"ones":2,"zeros":1,"tie":false},{"column":1,"ones":2,"zeros":1,"tie":false}],"#,
+				r#""oxygen":{"rating":3,"steps":[{"column":0,"ones":2,"zeros":1,"tie":false,"kept":true,"remaining":2},"#,
+				r#"{"column":1,"ones":1,"zeros":1,"tie":true,"kept":true,"remaining":1}]},"#,
+				r#""co2":{"rating":1,"steps":[{"column":0,"ones":2,"zeros":1,"tie":false,"kept":false,"remaining":1}]}}"#
+			)
+		);
 	}
 }
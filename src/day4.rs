@@ -1,43 +1,51 @@
-use std::num::ParseIntError;
+//! Day 4: Giant Squid
+//!
+//! Plays bingo against the squid, scoring the first and the last board to win.
 
-#[derive(Debug)]
-pub struct BingoGame {
-	numbers: Vec<i32>,
-	boards: Vec<[i32; 25]>,
-}
+use crate::{
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
 
+/// The numbers in the order they are drawn, and all of the boards
 #[derive(Debug)]
-pub enum ParseBingoGameError {
-	NoNumbersError,
-	ParseNumberError(ParseIntError),
+pub struct BingoGame {
+	pub numbers: Vec<i32>,
+	/// Every board is stored row by row
+	pub boards: Vec<[i32; 25]>,
 }
 
-pub fn parse_input(input: &str) -> Result<BingoGame, ParseBingoGameError> {
+pub fn parse_input(input: &str) -> Result<BingoGame, ParseError> {
+	let source = Source::new(Day4::DAY, input);
 	let mut sections = input.split_terminator("\n\n");
 
-	let numbers_section = sections.next().ok_or(ParseBingoGameError::NoNumbersError)?;
+	let numbers_section = sections
+		.next()
+		.filter(|s| !s.trim().is_empty())
+		.ok_or_else(|| source.error(input, "expected the drawn numbers"))?;
 
 	let numbers = numbers_section
+		.trim_end()
 		.split_terminator(",")
-		.map(|s| s.parse::<i32>())
-		.collect::<Result<Vec<i32>, ParseIntError>>()
-		.map_err(ParseBingoGameError::ParseNumberError)?;
+		.map(|s| source.number(s))
+		.collect::<Result<Vec<i32>, ParseError>>()?;
 
 	let mut boards = Vec::new();
 	for section in sections {
 		let mut board: [i32; 25] = [0; 25];
-		let mut y = 0;
-		for row in section.split_terminator("\n") {
-			let mut x = 0;
-			for s in row.split_whitespace() {
-				let n = s
-					.trim()
-					.parse::<i32>()
-					.map_err(ParseBingoGameError::ParseNumberError)?;
-				board[5 * y + x] = n;
-				x += 1;
+		let rows: Vec<&str> = section.split_terminator("\n").collect();
+		if rows.len() != 5 {
+			return Err(source.error(section, "expected a board with 5 rows"));
+		}
+		for (y, row) in rows.iter().enumerate() {
+			let cells: Vec<&str> = row.split_whitespace().collect();
+			if cells.len() != 5 {
+				return Err(source.error(row, "expected a row with 5 numbers"));
+			}
+			for (x, s) in cells.iter().enumerate() {
+				board[5 * y + x] = source.number(s)?;
 			}
-			y += 1;
 		}
 		boards.push(board);
 	}
@@ -46,19 +54,13 @@ pub fn parse_input(input: &str) -> Result<BingoGame, ParseBingoGameError> {
 }
 
 fn find_number(board: &[i32], value: i32) -> Option<usize> {
-	for i in 0..board.len() {
-		if board[i] == value {
-			return Some(i);
-		}
-	}
-	return None;
+	board.iter().position(|n| *n == value)
 }
 
 fn mark_number(board: &[i32], markings: &mut i32, value: i32) -> bool {
-	let pos: usize;
-	match find_number(board, value) {
+	let pos = match find_number(board, value) {
 		None => return false,
-		Some(n) => pos = n,
+		Some(n) => n,
 	};
 	*markings |= 1 << pos;
 	return true;
@@ -133,6 +135,57 @@ pub fn part2(game: &BingoGame) -> i32 {
 		);
 }
 
+/// Every number on the boards gets drawn at some point, so every board wins
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let mut numbers: Vec<i32> = (0..size.max(100) as i32).collect();
+	rng.shuffle(&mut numbers);
+	let drawn: Vec<String> = numbers.iter().map(|n| n.to_string()).collect();
+
+	let mut sections = vec![drawn.join(",")];
+	for _ in 0..size.max(1) {
+		rng.shuffle(&mut numbers);
+		let rows: Vec<String> = numbers[..25]
+			.chunks(5)
+			.map(|row| {
+				row
+					.iter()
+					.map(|n| format!("{:>2}", n))
+					.collect::<Vec<_>>()
+					.join(" ")
+			})
+			.collect();
+		sections.push(rows.join("\n"));
+	}
+	sections.join("\n\n") + "\n"
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+	const DAY: u32 = 4;
+
+	type Input = BingoGame;
+	type Output1 = i32;
+	type Output2 = i32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
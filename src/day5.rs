@@ -1,50 +1,36 @@
-use std::{
-	cmp::{max, min},
-	num::ParseIntError,
-};
+//! Day 5: Hydrothermal Venture
+//!
+//! Counts the points where at least two lines of hydrothermal vents overlap.
 
-type Grid = Vec<Vec<i32>>;
+use crate::{
+	generate::Rng,
+	grid::Grid,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
+use std::cmp::{max, min};
 
+/// A line of vents between two points, both included
 #[derive(Debug)]
 pub struct Line {
-	x1: i32,
-	y1: i32,
-	x2: i32,
-	y2: i32,
+	pub x1: i32,
+	pub y1: i32,
+	pub x2: i32,
+	pub y2: i32,
 }
 
-#[derive(Debug)]
-pub enum ParseLineError {
-	ParseNumberError(ParseIntError),
-	ParsePointsError,
-	ParsePointError,
+pub fn parse_point(source: &Source, input: &str) -> Result<(i32, i32), ParseError> {
+	let (x, y) = source.split_once(input, ",")?;
+	return Ok((source.number(x)?, source.number(y)?));
 }
 
-pub fn parse_point(input: &str) -> Result<(i32, i32), ParseLineError> {
-	let mut parts = input.split(',');
-	let x = parts
-		.next()
-		.ok_or(ParseLineError::ParsePointError)?
-		.parse()
-		.map_err(ParseLineError::ParseNumberError)?;
-	let y = parts
-		.next()
-		.ok_or(ParseLineError::ParsePointError)?
-		.parse()
-		.map_err(ParseLineError::ParseNumberError)?;
-	return Ok((x, y));
-}
-
-pub fn parse_input(input: &str) -> Result<Vec<Line>, ParseLineError> {
+pub fn parse_input(input: &str) -> Result<Vec<Line>, ParseError> {
+	let source = Source::new(Day5::DAY, input);
 	let mut lines = Vec::new();
 	for input_line in input.lines() {
-		let mut parts = input_line.split(" -> ");
-
-		let point1 = parts.next().ok_or(ParseLineError::ParsePointsError)?;
-		let (x1, y1) = parse_point(point1)?;
-
-		let point2 = parts.next().ok_or(ParseLineError::ParsePointsError)?;
-		let (x2, y2) = parse_point(point2)?;
+		let (point1, point2) = source.split_once(input_line, " -> ")?;
+		let (x1, y1) = parse_point(&source, point1)?;
+		let (x2, y2) = parse_point(&source, point2)?;
 
 		lines.push(Line { x1, y1, x2, y2 })
 	}
@@ -65,14 +51,6 @@ fn determine_bounds(lines: &[Line]) -> (i32, i32, i32, i32) {
 	return (x1, y1, x2, y2);
 }
 
-fn new_grid(width: usize, height: usize) -> Grid {
-	let mut grid = Vec::new();
-	for _ in 0..height {
-		grid.push(vec![0; width]);
-	}
-	grid
-}
-
 fn sign(x: i32) -> i32 {
 	if x > 0 {
 		1
@@ -83,13 +61,13 @@ fn sign(x: i32) -> i32 {
 	}
 }
 
-fn mark_line(grid: &mut Grid, line: &Line, ox: i32, oy: i32) {
+fn mark_line(grid: &mut Grid<i32>, line: &Line, ox: i32, oy: i32) {
 	let dx = sign(line.x2 - line.x1);
 	let dy = sign(line.y2 - line.y1);
 	let mut x = line.x1;
 	let mut y = line.y1;
 	while x != line.x2 || y != line.y2 {
-		grid[(y - oy) as usize][(x - ox) as usize] += 1;
+		grid[((x - ox) as usize, (y - oy) as usize)] += 1;
 		if x != line.x2 {
 			x += dx;
 		}
@@ -97,16 +75,14 @@ fn mark_line(grid: &mut Grid, line: &Line, ox: i32, oy: i32) {
 			y += dy;
 		}
 	}
-	grid[(y - oy) as usize][(x - ox) as usize] += 1;
+	grid[((x - ox) as usize, (y - oy) as usize)] += 1;
 }
 
-fn count_dangerous_areas(grid: &Grid) -> u32 {
+fn count_dangerous_areas(grid: &Grid<i32>) -> u32 {
 	let mut count = 0;
-	for row in grid {
-		for point in row {
-			if *point > 1 {
-				count += 1;
-			}
+	for point in grid.iter() {
+		if *point > 1 {
+			count += 1;
 		}
 	}
 	count
@@ -116,7 +92,7 @@ pub fn part1(lines: &[Line]) -> u32 {
 	let bounds = determine_bounds(lines);
 	let width = (bounds.2 - bounds.0 + 1) as usize;
 	let height = (bounds.3 - bounds.1 + 1) as usize;
-	let mut grid = new_grid(width, height);
+	let mut grid = Grid::new(width, height, 0);
 
 	for line in lines {
 		if (line.x1 == line.x2) || (line.y1 == line.y2) {
@@ -131,7 +107,7 @@ pub fn part2(lines: &[Line]) -> u32 {
 	let bounds = determine_bounds(lines);
 	let width = (bounds.2 - bounds.0 + 1) as usize;
 	let height = (bounds.3 - bounds.1 + 1) as usize;
-	let mut grid = new_grid(width, height);
+	let mut grid = Grid::new(width, height, 0);
 
 	for line in lines {
 		mark_line(&mut grid, line, bounds.0, bounds.1);
@@ -140,6 +116,72 @@ pub fn part2(lines: &[Line]) -> u32 {
 	count_dangerous_areas(&grid)
 }
 
+/// Lines go in all 8 directions and always stay within 0..=2*size
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let max = (size as i32 * 2).max(10);
+	let directions = [
+		(1, 0),
+		(-1, 0),
+		(0, 1),
+		(0, -1),
+		(1, 1),
+		(1, -1),
+		(-1, 1),
+		(-1, -1),
+	];
+	let room = |position: i32, step: i32| match step {
+		1 => max - position,
+		-1 => position,
+		_ => max,
+	};
+
+	let mut lines = Vec::new();
+	while lines.len() < size {
+		let (x1, y1) = (rng.range(0, max), rng.range(0, max));
+		let (dx, dy) = *rng.choose(&directions);
+		let longest = room(x1, dx).min(room(y1, dy));
+		if longest == 0 {
+			continue;
+		}
+		let length = rng.range(1, longest);
+		lines.push(format!(
+			"{},{} -> {},{}",
+			x1,
+			y1,
+			x1 + dx * length,
+			y1 + dy * length
+		));
+	}
+	lines.join("\n") + "\n"
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+	const DAY: u32 = 5;
+
+	type Input = Vec<Line>;
+	type Output1 = u32;
+	type Output2 = u32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -1,82 +1,223 @@
-use std::num::ParseIntError;
+//! Day 6: Lanternfish
+//!
+//! Simulates a population of lanternfish that grows exponentially.
 
-pub fn parse_input(input: &str) -> Result<Vec<i32>, ParseIntError> {
+use crate::{
+	cancel,
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::{parse_param, unknown_param, PuzzleParams, Solution},
+};
+
+pub fn parse_input(input: &str) -> Result<Vec<i32>, ParseError> {
+	let source = Source::new(Day6::DAY, input);
 	input
 		.trim_end()
 		.split_terminator(',')
-		.map(|s| s.parse())
+		.map(|s| source.number(s))
 		.collect()
 }
 
-fn simulate_step(fishes: &mut Vec<i32>) {
-	for i in 0..fishes.len() {
-		if fishes[i] == 0 {
-			fishes[i] = 7;
-			fishes.push(9);
+/// How many days to simulate
+pub struct Params {
+	pub days: u32,
+}
+
+impl PuzzleParams for Params {
+	fn puzzle(part: u32) -> Params {
+		Params {
+			days: if part == 1 { 80 } else { 256 },
 		}
 	}
 
-	for i in 0..fishes.len() {
-		if fishes[i] > 0 {
-			fishes[i] -= 1;
+	fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+		match name {
+			"days" => self.days = parse_param(name, value)?,
+			_ => return Err(unknown_param(name, &["days"])),
 		}
+		Ok(())
 	}
 }
 
-pub fn part1(input: &[i32]) -> u32 {
-	let mut fishes = input.to_vec();
-
-	for _ in 0..80 {
-		simulate_step(&mut fishes)
-	}
-
-	fishes.len() as u32
-}
-
 // Instead of storing each fishes cycle as individual values group them up
 // by there cycles. Because it dosen't matter where the fish is in the list.
 // So just make an array of size 9 for the 9 possible fish cycle timers.
 // And one extra group, for accounting for the delay that when the timer is 0,
 // they produce a new fish only on the next turn.
-pub fn part2(input: &[i32]) -> u64 {
+// `None` once there are more fish than fit in a `u64`.
+fn count_grouped(input: &[i32], days: u32) -> Option<u64> {
 	let mut groups: [u64; 10] = [0; 10];
 
 	for fish in input.iter() {
 		groups[*fish as usize + 1] += 1;
 	}
 
-	for _ in 0..256 {
+	for _ in 0..days {
+		cancel::checkpoint();
 		for i in 1..10 {
 			groups[i - 1] += groups[i];
 			groups[i] = 0;
 		}
-		groups[7] += groups[0];
+		groups[7] = groups[7].checked_add(groups[0])?;
 		groups[9] += groups[0];
 		groups[0] = 0;
 	}
 
 	let mut count: u64 = 0;
 	for amount in groups.iter() {
-		count += amount;
+		count = count.checked_add(*amount)?;
+	}
+	Some(count)
+}
+
+fn count(input: &[i32], days: u32) -> Result<u64, String> {
+	count_grouped(input, days)
+		.ok_or_else(|| format!("too many lanternfish to count after {} days", days))
+}
+
+pub fn part1(input: &[i32], params: &Params) -> Result<u64, String> {
+	count(input, params.days)
+}
+
+pub fn part2(input: &[i32], params: &Params) -> Result<u64, String> {
+	count(input, params.days)
+}
+
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let fishes: Vec<String> = (0..size.max(1))
+		.map(|_| rng.range(1, 5).to_string())
+		.collect();
+	fishes.join(",") + "\n"
+}
+
+pub struct Day6;
+
+impl Solution for Day6 {
+	const DAY: u32 = 6;
+
+	type Input = Vec<i32>;
+	type Output1 = Result<u64, String>;
+	type Output2 = Result<u64, String>;
+	type Params = Params;
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, params: &Self::Params) -> Self::Output1 {
+		part1(input, params)
+	}
+
+	fn part2(input: &Self::Input, params: &Self::Params) -> Self::Output2 {
+		part2(input, params)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
 	}
-	count
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::differential::{check, compare, shrink_towards, shrink_vec};
+
+	fn simulate_step(fishes: &mut Vec<i32>) {
+		for i in 0..fishes.len() {
+			if fishes[i] == 0 {
+				fishes[i] = 7;
+				fishes.push(9);
+			}
+		}
+
+		for i in 0..fishes.len() {
+			if fishes[i] > 0 {
+				fishes[i] -= 1;
+			}
+		}
+	}
+
+	// Follows every fish on its own, which is only feasible for a few days
+	fn count_simulated(input: &[i32], days: u32) -> usize {
+		let mut fishes = input.to_vec();
+
+		for _ in 0..days {
+			simulate_step(&mut fishes)
+		}
+
+		fishes.len()
+	}
 
 	#[test]
 	fn part1_example() {
 		let input = [3, 4, 3, 1, 2];
-		let result = part1(&input);
-		assert_eq!(result, 5934);
+		let result = part1(&input, &Params::puzzle(1));
+		assert_eq!(result, Ok(5934));
+	}
+
+	#[test]
+	fn fewer_days() {
+		let input = [3, 4, 3, 1, 2];
+		assert_eq!(part1(&input, &Params { days: 18 }), Ok(26));
+		assert_eq!(part2(&input, &Params { days: 18 }), Ok(26));
+
+		let mut params = Params::puzzle(2);
+		params.set("days", "18").unwrap();
+		assert_eq!(params.days, 18);
+		assert_eq!(
+			params.set("days", "soon").unwrap_err(),
+			"invalid value 'soon' for parameter 'days'"
+		);
+		assert_eq!(
+			params.set("steps", "1").unwrap_err(),
+			"unknown parameter 'steps', expected one of: days"
+		);
 	}
 
 	#[test]
 	fn part2_example() {
 		let input = [3, 4, 3, 1, 2];
-		let result = part2(&input);
-		assert_eq!(result, 26984457539 as u64);
+		let result = part2(&input, &Params::puzzle(2));
+		assert_eq!(result, Ok(26984457539));
+	}
+
+	#[test]
+	fn grouped_count_matches_simulation() {
+		check(
+			6,
+			200,
+			|rng| {
+				let fishes: Vec<i32> = (0..rng.range(1, 10)).map(|_| rng.range(0, 8)).collect();
+				(fishes, rng.range(0, 80))
+			},
+			|(fishes, days)| {
+				let mut candidates: Vec<_> = shrink_vec(fishes, |fish| shrink_towards(*fish, 0))
+					.into_iter()
+					.map(|fishes| (fishes, *days))
+					.collect();
+				candidates.extend(
+					shrink_towards(*days, 0)
+						.into_iter()
+						.map(|days| (fishes.clone(), days)),
+				);
+				candidates
+			},
+			|(fishes, days)| {
+				compare(
+					Some(count_simulated(fishes, *days as u32) as u64),
+					count_grouped(fishes, *days as u32),
+				)
+			},
+		);
+	}
+
+	#[test]
+	fn too_many_days() {
+		let input = [3, 4, 3, 1, 2];
+		assert_eq!(
+			part1(&input, &Params { days: 1_000_000 }).unwrap_err(),
+			"too many lanternfish to count after 1000000 days"
+		);
+		assert_eq!(part2(&[], &Params { days: 1_000_000 }), Ok(0));
 	}
 }
@@ -1,10 +1,19 @@
-use std::num::ParseIntError;
+//! Day 7: The Treachery of Whales
+//!
+//! Finds the position that costs the crab submarines the least fuel to align on.
 
-pub fn parse_input(input: &str) -> Result<Vec<i32>, ParseIntError> {
+use crate::{
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
+
+pub fn parse_input(input: &str) -> Result<Vec<i32>, ParseError> {
+	let source = Source::new(Day7::DAY, input);
 	input
 		.trim_end()
 		.split_terminator(',')
-		.map(|s| s.parse())
+		.map(|s| source.number(s))
 		.collect()
 }
 
@@ -70,6 +79,41 @@ pub fn part2(crabs: &[i32]) -> u32 {
 	best_cost
 }
 
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let max = size as i32 * 2;
+	let crabs: Vec<String> = (0..size.max(1))
+		.map(|_| rng.range(0, max).to_string())
+		.collect();
+	crabs.join(",") + "\n"
+}
+
+pub struct Day7;
+
+impl Solution for Day7 {
+	const DAY: u32 = 7;
+
+	type Input = Vec<i32>;
+	type Output1 = u32;
+	type Output2 = u32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
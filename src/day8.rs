@@ -1,26 +1,40 @@
+//! Day 8: Seven Segment Search
+//!
+//! Untangles the scrambled wires of four-digit seven segment displays.
+
+use crate::{
+	generate::Rng,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
 use std::{collections::HashMap, convert::TryInto};
 
-pub struct Entry([String; 10], [String; 4]);
+/// The 10 unique signal patterns and the 4 digits of the output
+pub struct Entry(pub [String; 10], pub [String; 4]);
 
-fn parse_line(line: &str) -> Entry {
-	let parts: Vec<&str> = line.split(" | ").collect();
-	let unique_patterns = parts[0]
+fn parse_line(source: &Source, line: &str) -> Result<Entry, ParseError> {
+	let (patterns, digits) = source.split_once(line, " | ")?;
+	let unique_patterns = patterns
 		.split_whitespace()
-		.map(|s| String::from(s))
+		.map(String::from)
 		.collect::<Vec<String>>()
 		.try_into()
-		.unwrap();
-	let output_digits = parts[1]
+		.map_err(|_| source.error(patterns, "expected 10 unique patterns"))?;
+	let output_digits = digits
 		.split_whitespace()
-		.map(|s| String::from(s))
+		.map(String::from)
 		.collect::<Vec<String>>()
 		.try_into()
-		.unwrap();
-	return Entry(unique_patterns, output_digits);
+		.map_err(|_| source.error(digits, "expected 4 output digits"))?;
+	return Ok(Entry(unique_patterns, output_digits));
 }
 
-pub fn parse_input(input: &str) -> Vec<Entry> {
-	input.lines().map(parse_line).collect()
+pub fn parse_input(input: &str) -> Result<Vec<Entry>, ParseError> {
+	let source = Source::new(Day8::DAY, input);
+	input
+		.lines()
+		.map(|line| parse_line(&source, line))
+		.collect()
 }
 
 pub fn part1(entries: &[Entry]) -> u32 {
@@ -56,7 +70,7 @@ fn signal_to_bitmask(signal: &str) -> u32 {
 }
 
 fn decode_signal(signal: &str, wire_loopup: &HashMap<u32, u32>) -> Option<u32> {
-	wire_loopup.get(&signal_to_bitmask(signal)).map(|n| *n)
+	wire_loopup.get(&signal_to_bitmask(signal)).copied()
 }
 
 fn decode_signals(signals: &[String], wire_loopup: &HashMap<u32, u32>) -> u32 {
@@ -134,6 +148,63 @@ pub fn part2(entries: &[Entry]) -> u32 {
 	sum
 }
 
+// Segments lit for each digit, before the wires get mixed up
+const DIGIT_SEGMENTS: [&str; 10] = [
+	"abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+/// Every display gets its own wire mixup
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let mut lines = Vec::new();
+	for _ in 0..size {
+		let mut wires: Vec<char> = "abcdefg".chars().collect();
+		rng.shuffle(&mut wires);
+		let mut patterns: Vec<usize> = (0..10).collect();
+		rng.shuffle(&mut patterns);
+		let output: Vec<usize> = (0..4).map(|_| rng.below(10)).collect();
+
+		let mut scramble = |digit: &usize| {
+			let mut signal: Vec<char> = DIGIT_SEGMENTS[*digit]
+				.bytes()
+				.map(|segment| wires[(segment - b'a') as usize])
+				.collect();
+			rng.shuffle(&mut signal);
+			signal.into_iter().collect::<String>()
+		};
+		let patterns: Vec<String> = patterns.iter().map(&mut scramble).collect();
+		let output: Vec<String> = output.iter().map(&mut scramble).collect();
+		lines.push(format!("{} | {}", patterns.join(" "), output.join(" ")));
+	}
+	lines.join("\n") + "\n"
+}
+
+pub struct Day8;
+
+impl Solution for Day8 {
+	const DAY: u32 = 8;
+
+	type Input = Vec<Entry>;
+	type Output1 = u32;
+	type Output2 = u32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
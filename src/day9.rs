@@ -1,108 +1,198 @@
-use std::collections::HashSet;
+//! Day 9: Smoke Basin
+//!
+//! Finds the low points of a height map and the basins that drain into them.
 
-pub fn parse_input(input: &str) -> Vec<Vec<u32>> {
-	input
-		.lines()
-		.map(|s| s.chars().map(|c| c.to_digit(10).unwrap()).collect())
-		.collect()
+use crate::{
+	generate::Rng,
+	grid::Grid,
+	parse::{ParseError, Source},
+	solution::Solution,
+};
+use std::collections::{HashSet, VecDeque};
+
+pub fn parse_input(input: &str) -> Result<Grid<u32>, ParseError> {
+	let source = Source::new(Day9::DAY, input);
+	Grid::parse(&source, input, |c| c.to_digit(10))
 }
 
-fn find_low_points(grid: &Vec<Vec<u32>>) -> Vec<(usize, usize)> {
+fn find_low_points(grid: &Grid<u32>) -> Vec<(usize, usize)> {
 	let mut low_points = Vec::new();
-	let height = grid.len();
-	for i in 0..height {
-		let width = grid[i].len();
-		for j in 0..width {
-			if (i == 0 || grid[i - 1][j] > grid[i][j])
-				&& (i == height - 1 || grid[i + 1][j] > grid[i][j])
-				&& (j == 0 || grid[i][j - 1] > grid[i][j])
-				&& (j == width - 1 || grid[i][j + 1] > grid[i][j])
-			{
-				low_points.push((i, j));
-			}
+	for (x, y) in grid.positions() {
+		if grid
+			.neighbours4(x, y)
+			.all(|neighbour| grid[neighbour] > grid[(x, y)])
+		{
+			low_points.push((x, y));
 		}
 	}
 	return low_points;
 }
 
-pub fn part1(grid: Vec<Vec<u32>>) -> u32 {
+pub fn part1(grid: &Grid<u32>) -> u32 {
 	let mut sum = 0;
-	for low_point in find_low_points(&grid) {
-		let depth = grid[low_point.0][low_point.1];
+	for low_point in find_low_points(grid) {
+		let depth = grid[low_point];
 		sum += depth + 1;
 	}
 	return sum;
 }
 
-fn find_basin_size(grid: &Vec<Vec<u32>>, location: (usize, usize)) -> u32 {
+fn find_basin_size(grid: &Grid<u32>, location: (usize, usize)) -> u32 {
 	let mut explored_spots = HashSet::new();
 	let mut leaf_nodes = vec![location];
-	let height = grid.len();
-	let width = grid[0].len();
 
-	while leaf_nodes.len() > 0 {
-		let leaf_node = leaf_nodes.pop().unwrap();
+	while let Some(leaf_node) = leaf_nodes.pop() {
 		explored_spots.insert(leaf_node);
 
-		let (i, j) = leaf_node;
-		if i > 0 && !explored_spots.contains(&(i - 1, j)) && grid[i - 1][j] != 9 {
-			leaf_nodes.push((i - 1, j));
-		}
-		if i < height - 1 && !explored_spots.contains(&(i + 1, j)) && grid[i + 1][j] != 9 {
-			leaf_nodes.push((i + 1, j));
-		}
-		if j > 0 && !explored_spots.contains(&(i, j - 1)) && grid[i][j - 1] != 9 {
-			leaf_nodes.push((i, j - 1));
-		}
-		if j < width - 1 && !explored_spots.contains(&(i, j + 1)) && grid[i][j + 1] != 9 {
-			leaf_nodes.push((i, j + 1));
+		for neighbour in grid.neighbours4(leaf_node.0, leaf_node.1) {
+			if !explored_spots.contains(&neighbour) && grid[neighbour] != 9 {
+				leaf_nodes.push(neighbour);
+			}
 		}
 	}
 
 	return explored_spots.len() as u32;
 }
 
-fn find_basin_sizes(grid: &Vec<Vec<u32>>) -> Vec<u32> {
+fn find_basin_sizes(grid: &Grid<u32>) -> Vec<u32> {
 	let mut sizes = Vec::new();
-	for low_point in find_low_points(&grid) {
+	for low_point in find_low_points(grid) {
 		sizes.push(find_basin_size(grid, low_point))
 	}
 	return sizes;
 }
 
-pub fn part2(grid: Vec<Vec<u32>>) -> u32 {
-	let mut basin_sizes = find_basin_sizes(&grid);
+pub fn part2(grid: &Grid<u32>) -> u32 {
+	let mut basin_sizes = find_basin_sizes(grid);
 	basin_sizes.sort_by(|a, b| b.cmp(a));
 	return basin_sizes[0] * basin_sizes[1] * basin_sizes[2];
 }
 
+// Positions in the same basin as `start`, with how many steps away they are
+fn walk_basin(is_basin: &Grid<bool>, start: (usize, usize)) -> Vec<((usize, usize), u32)> {
+	let mut distances = Grid::new(is_basin.width(), is_basin.height(), None);
+	distances[start] = Some(0);
+	let mut visited = vec![(start, 0)];
+	let mut i = 0;
+	while i < visited.len() {
+		let ((x, y), distance) = visited[i];
+		for neighbour in is_basin.neighbours4(x, y) {
+			if is_basin[neighbour] && distances[neighbour].is_none() {
+				distances[neighbour] = Some(distance + 1);
+				visited.push((neighbour, distance + 1));
+			}
+		}
+		i += 1;
+	}
+	return visited;
+}
+
+/// Like in the puzzle, the basins are walled off by 9s and each one of them has
+/// a single low point. The walls go along the borders of the regions closest to
+/// some random points, and the heights grow with the distance from the low point.
+pub fn generate_input(rng: &mut Rng, size: usize) -> String {
+	let side = size.max(5);
+	loop {
+		let mut regions = Grid::new(side, side, None);
+		let mut queue = VecDeque::new();
+		for region in 0..(side * side / 50).max(3) {
+			let position = (rng.below(side), rng.below(side));
+			if regions[position].is_none() {
+				regions[position] = Some(region);
+				queue.push_back(position);
+			}
+		}
+		while let Some((x, y)) = queue.pop_front() {
+			for neighbour in regions.neighbours4(x, y).collect::<Vec<_>>() {
+				if regions[neighbour].is_none() {
+					regions[neighbour] = regions[(x, y)];
+					queue.push_back(neighbour);
+				}
+			}
+		}
+
+		// Of two neighbouring regions, only one side of the border becomes a wall
+		let mut is_basin = Grid::new(side, side, false);
+		for (x, y) in regions.positions() {
+			is_basin[(x, y)] = regions
+				.neighbours4(x, y)
+				.all(|neighbour| regions[neighbour] >= regions[(x, y)]);
+		}
+
+		let mut grid = Grid::new(side, side, 9);
+		for position in is_basin.positions() {
+			if !is_basin[position] || grid[position] != 9 {
+				continue;
+			}
+			let basin: Vec<_> = walk_basin(&is_basin, position)
+				.into_iter()
+				.map(|(p, _)| p)
+				.collect();
+			for (p, distance) in walk_basin(&is_basin, *rng.choose(&basin)) {
+				grid[p] = distance.min(8);
+			}
+		}
+
+		if find_low_points(&grid).len() >= 3 {
+			return grid.render(|height| std::char::from_digit(*height, 10).unwrap());
+		}
+	}
+}
+
+pub struct Day9;
+
+impl Solution for Day9 {
+	const DAY: u32 = 9;
+
+	type Input = Grid<u32>;
+	type Output1 = u32;
+	type Output2 = u32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {
+		parse_input(input)
+	}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {
+		part1(input)
+	}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {
+		part2(input)
+	}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {
+		generate_input(rng, size)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
 	#[test]
 	fn part1_example() {
-		let input = vec![
+		let input = [
 			vec![2, 1, 9, 9, 9, 4, 3, 2, 1, 0],
 			vec![3, 9, 8, 7, 8, 9, 4, 9, 2, 1],
 			vec![9, 8, 5, 6, 7, 8, 9, 8, 9, 2],
 			vec![8, 7, 6, 7, 8, 9, 6, 7, 8, 9],
 			vec![9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
 		];
-		let result = part1(input);
+		let result = part1(&Grid::from_cells(10, 5, input.concat()));
 		assert_eq!(result, 15);
 	}
 
 	#[test]
 	fn part2_example() {
-		let input = vec![
+		let input = [
 			vec![2, 1, 9, 9, 9, 4, 3, 2, 1, 0],
 			vec![3, 9, 8, 7, 8, 9, 4, 9, 2, 1],
 			vec![9, 8, 5, 6, 7, 8, 9, 8, 9, 2],
 			vec![8, 7, 6, 7, 8, 9, 6, 7, 8, 9],
 			vec![9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
 		];
-		let result = part2(input);
+		let result = part2(&Grid::from_cells(10, 5, input.concat()));
 		assert_eq!(result, 1134);
 	}
 }
@@ -0,0 +1,147 @@
+// Helpers for testing two algorithms that compute the same thing against each
+// other, usually a naive one that is easy to trust and an optimized one. Both
+// are run on random inputs, and the first input they disagree on is shrunk
+// down to a minimal counterexample before the test fails.
+
+use crate::generate::Rng;
+use std::fmt::Debug;
+
+/// Describes the disagreement, if the two answers differ
+pub fn compare<T: PartialEq + Debug>(naive: T, optimized: T) -> Option<String> {
+	if naive == optimized {
+		None
+	} else {
+		Some(format!(
+			"naive gave {:?}, optimized gave {:?}",
+			naive, optimized
+		))
+	}
+}
+
+// Keeps taking the first smaller input that still fails, until none of them do
+fn shrink_counterexample<T>(
+	mut input: T,
+	mut difference: String,
+	shrink: &impl Fn(&T) -> Vec<T>,
+	differs: &impl Fn(&T) -> Option<String>,
+) -> (T, String) {
+	'shrinking: loop {
+		for candidate in shrink(&input) {
+			if let Some(candidate_difference) = differs(&candidate) {
+				input = candidate;
+				difference = candidate_difference;
+				continue 'shrinking;
+			}
+		}
+		return (input, difference);
+	}
+}
+
+/// Runs `cases` inputs made by `generate` through `differs`. Returns the
+/// shrunk input and what went wrong with it for the first failing case.
+pub fn find_counterexample<T>(
+	seed: u64,
+	cases: usize,
+	mut generate: impl FnMut(&mut Rng) -> T,
+	shrink: impl Fn(&T) -> Vec<T>,
+	differs: impl Fn(&T) -> Option<String>,
+) -> Option<(T, String)> {
+	let mut rng = Rng::new(seed);
+	for _ in 0..cases {
+		let input = generate(&mut rng);
+		if let Some(difference) = differs(&input) {
+			return Some(shrink_counterexample(input, difference, &shrink, &differs));
+		}
+	}
+	None
+}
+
+pub fn check<T: Debug>(
+	seed: u64,
+	cases: usize,
+	generate: impl FnMut(&mut Rng) -> T,
+	shrink: impl Fn(&T) -> Vec<T>,
+	differs: impl Fn(&T) -> Option<String>,
+) {
+	if let Some((input, difference)) = find_counterexample(seed, cases, generate, shrink, differs) {
+		panic!("Algorithms disagree ({}) on {:?}", difference, input);
+	}
+}
+
+/// Values between `value` and `target`, closest to the target first
+pub fn shrink_towards(value: i32, target: i32) -> Vec<i32> {
+	let mut candidates = vec![];
+	let mut distance = value - target;
+	while distance != 0 {
+		candidates.push(value - distance);
+		distance /= 2;
+	}
+	candidates
+}
+
+/// Shorter lists first, dropping big chunks before single items, then the
+/// same list with one of the items shrunk
+pub fn shrink_vec<T: Clone>(items: &[T], shrink_item: impl Fn(&T) -> Vec<T>) -> Vec<Vec<T>> {
+	let mut candidates = vec![];
+	let mut chunk = items.len();
+	while chunk > 0 {
+		for start in (0..items.len()).step_by(chunk) {
+			let mut smaller = items[..start].to_vec();
+			smaller.extend_from_slice(&items[(start + chunk).min(items.len())..]);
+			candidates.push(smaller);
+		}
+		chunk /= 2;
+	}
+	for (i, item) in items.iter().enumerate() {
+		for smaller_item in shrink_item(item) {
+			let mut smaller = items.to_vec();
+			smaller[i] = smaller_item;
+			candidates.push(smaller);
+		}
+	}
+	candidates
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn shrinking_numbers() {
+		assert_eq!(shrink_towards(10, 0), vec![0, 5, 8, 9]);
+		assert_eq!(shrink_towards(-3, 1), vec![1, -1, -2]);
+		assert!(shrink_towards(4, 4).is_empty());
+	}
+
+	#[test]
+	fn shrinking_lists() {
+		let candidates = shrink_vec(&[1, 2, 3], |_| vec![]);
+		assert_eq!(candidates[0], Vec::<i32>::new());
+		assert!(candidates.contains(&vec![2, 3]));
+		assert!(candidates.contains(&vec![1, 3]));
+		let candidates = shrink_vec(&[4], |n| shrink_towards(*n, 0));
+		assert_eq!(candidates, vec![vec![], vec![0], vec![2], vec![3]]);
+	}
+
+	#[test]
+	fn finds_minimal_counterexample() {
+		// "Optimized" sum that forgets about numbers above 6
+		let found = find_counterexample(
+			0,
+			100,
+			|rng| (0..10).map(|_| rng.range(0, 9)).collect::<Vec<_>>(),
+			|numbers| shrink_vec(numbers, |n| shrink_towards(*n, 0)),
+			|numbers| {
+				let sum: i32 = numbers.iter().sum();
+				compare(sum, numbers.iter().filter(|n| **n <= 6).sum())
+			},
+		);
+		let (input, difference) = found.unwrap();
+		assert_eq!(input, vec![7]);
+		assert_eq!(difference, "naive gave 7, optimized gave 0");
+
+		let agreeing =
+			find_counterexample(0, 100, |rng| rng.below(10), |_| vec![], |n| compare(*n, *n));
+		assert!(agreeing.is_none());
+	}
+}
@@ -0,0 +1,119 @@
+/// Small pseudo random number generator for making up puzzle inputs. It is
+/// seeded explicitly, so that the same seed always gives the same input, no
+/// matter the platform or the version of some external crate.
+///
+/// The numbers come from SplitMix64: <https://prng.di.unimi.it/splitmix64.c>
+pub struct Rng {
+	state: u64,
+}
+
+impl Rng {
+	pub fn new(seed: u64) -> Rng {
+		Rng { state: seed }
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+		z ^ (z >> 31)
+	}
+
+	/// Number in 0..n
+	pub fn below(&mut self, n: usize) -> usize {
+		assert!(n > 0, "Can't pick a number below 0");
+		(self.next_u64() % n as u64) as usize
+	}
+
+	/// Number in low..=high
+	pub fn range(&mut self, low: i32, high: i32) -> i32 {
+		assert!(low <= high, "Empty range {}..={}", low, high);
+		low + (self.next_u64() % (high as i64 - low as i64 + 1) as u64) as i32
+	}
+
+	/// True with a chance of `numerator` out of `denominator`
+	pub fn chance(&mut self, numerator: usize, denominator: usize) -> bool {
+		self.below(denominator) < numerator
+	}
+
+	pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+		&items[self.below(items.len())]
+	}
+
+	pub fn shuffle<T>(&mut self, items: &mut [T]) {
+		for i in (1..items.len()).rev() {
+			items.swap(i, self.below(i + 1));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{runner, solution::Runner, SOLUTIONS};
+
+	#[test]
+	fn same_seed_same_numbers() {
+		let numbers = |seed| {
+			let mut rng = Rng::new(seed);
+			(0..10).map(|_| rng.next_u64()).collect::<Vec<_>>()
+		};
+		assert_eq!(numbers(7), numbers(7));
+		assert_ne!(numbers(7), numbers(8));
+	}
+
+	#[test]
+	fn ranges() {
+		let mut rng = Rng::new(0);
+		for _ in 0..1000 {
+			assert!((-3..=3).contains(&rng.range(-3, 3)));
+			assert!(rng.below(5) < 5);
+		}
+		assert_eq!(rng.range(4, 4), 4);
+
+		let mut items: Vec<_> = (0..20).collect();
+		rng.shuffle(&mut items);
+		assert_ne!(items, (0..20).collect::<Vec<_>>());
+		items.sort();
+		assert_eq!(items, (0..20).collect::<Vec<_>>());
+	}
+
+	fn check_generated(solution: &dyn Runner, seed: u64, size: usize, solve: bool) {
+		let input = solution.generate(&mut Rng::new(seed), size);
+		assert_eq!(input, solution.generate(&mut Rng::new(seed), size));
+		let parts: &[u32] = if solve { &[1, 2] } else { &[] };
+		let report = runner::run_parts(solution, &input, parts);
+		if let Some(error) = report.parse_error {
+			panic!(
+				"Day {} generated an invalid input:\n{}",
+				solution.day(),
+				error.render("<generated>", &input)
+			);
+		}
+		for part in &report.parts {
+			if let runner::Outcome::Skipped(reason) | runner::Outcome::Failed(reason) = &part.outcome {
+				// Day 25 only has one part
+				if solution.day() != 25 || part.part != 2 {
+					panic!(
+						"Day {} part {} failed on a generated input: {}",
+						solution.day(),
+						part.part,
+						reason
+					);
+				}
+			}
+		}
+	}
+
+	// Solving is slow for some of the days, so only one of the inputs is solved
+	#[test]
+	fn generated_inputs_are_valid() {
+		for solution in SOLUTIONS {
+			check_generated(solution, 0, 5, true);
+			for seed in 1..4 {
+				check_generated(solution, seed, 5, false);
+			}
+		}
+	}
+}
@@ -0,0 +1,378 @@
+use std::{
+	ops::{Index, IndexMut, Range},
+	slice,
+};
+
+use crate::parse::{ParseError, Source};
+
+const OFFSETS4: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const OFFSETS8: [(i32, i32); 8] = [
+	(-1, -1),
+	(0, -1),
+	(1, -1),
+	(-1, 0),
+	(1, 0),
+	(-1, 1),
+	(0, 1),
+	(1, 1),
+];
+
+/// Rectangular grid stored row by row. Positions are given as (x, y), where x
+/// is the column and y is the row, starting from the top left corner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+	width: usize,
+	height: usize,
+	cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+	pub fn new(width: usize, height: usize, value: T) -> Grid<T> {
+		Grid {
+			width,
+			height,
+			cells: vec![value; width * height],
+		}
+	}
+}
+
+impl<T> Grid<T> {
+	pub fn from_cells(width: usize, height: usize, cells: Vec<T>) -> Grid<T> {
+		assert_eq!(
+			cells.len(),
+			width * height,
+			"Cell count doesn't match the size"
+		);
+		Grid {
+			width,
+			height,
+			cells,
+		}
+	}
+
+	/// Reads a character map, where every character is turned into a cell. All
+	/// rows have to be of the same length.
+	pub fn parse(
+		source: &Source,
+		input: &str,
+		mut cell: impl FnMut(char) -> Option<T>,
+	) -> Result<Grid<T>, ParseError> {
+		let mut cells = Vec::new();
+		let mut width = None;
+		let mut height = 0;
+		for line in input.lines() {
+			let mut row_width = 0;
+			for (i, c) in line.char_indices() {
+				let value = cell(c)
+					.ok_or_else(|| source.error(&line[i..], format!("unexpected character '{}'", c)))?;
+				cells.push(value);
+				row_width += 1;
+			}
+			match width {
+				None => width = Some(row_width),
+				Some(width) if width != row_width => {
+					return Err(source.error(line, format!("expected a row of {} cells", width)))
+				}
+				_ => {}
+			}
+			height += 1;
+		}
+
+		match width {
+			Some(width) => Ok(Grid::from_cells(width, height, cells)),
+			None => Err(source.error(input, "expected at least one row")),
+		}
+	}
+
+	pub fn width(&self) -> usize {
+		self.width
+	}
+
+	pub fn height(&self) -> usize {
+		self.height
+	}
+
+	pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+		x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+	}
+
+	pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+		if self.in_bounds(x, y) {
+			Some(&self.cells[y as usize * self.width + x as usize])
+		} else {
+			None
+		}
+	}
+
+	pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut T> {
+		if self.in_bounds(x, y) {
+			Some(&mut self.cells[y as usize * self.width + x as usize])
+		} else {
+			None
+		}
+	}
+
+	/// Treats the grid as a torus, going off one edge comes back on the other
+	pub fn get_wrapped(&self, x: i32, y: i32) -> &T {
+		let (x, y) = self.wrap(x, y);
+		&self[(x, y)]
+	}
+
+	pub fn get_wrapped_mut(&mut self, x: i32, y: i32) -> &mut T {
+		let (x, y) = self.wrap(x, y);
+		&mut self[(x, y)]
+	}
+
+	fn wrap(&self, x: i32, y: i32) -> (usize, usize) {
+		(
+			x.rem_euclid(self.width as i32) as usize,
+			y.rem_euclid(self.height as i32) as usize,
+		)
+	}
+
+	fn neighbours<'a>(
+		&self,
+		x: usize,
+		y: usize,
+		offsets: &'a [(i32, i32)],
+	) -> impl Iterator<Item = (usize, usize)> + 'a {
+		let (width, height) = (self.width as i32, self.height as i32);
+		offsets.iter().filter_map(move |(dx, dy)| {
+			let nx = x as i32 + dx;
+			let ny = y as i32 + dy;
+			if nx >= 0 && ny >= 0 && nx < width && ny < height {
+				Some((nx as usize, ny as usize))
+			} else {
+				None
+			}
+		})
+	}
+
+	/// Positions directly above, below, left and right that are within bounds
+	pub fn neighbours4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+		self.neighbours(x, y, &OFFSETS4)
+	}
+
+	/// Same as `neighbours4`, but with diagonals
+	pub fn neighbours8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+		self.neighbours(x, y, &OFFSETS8)
+	}
+
+	/// Every position, row by row
+	pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+		let width = self.width;
+		(0..self.width * self.height).map(move |i| (i % width, i / width))
+	}
+
+	pub fn iter(&self) -> slice::Iter<'_, T> {
+		self.cells.iter()
+	}
+
+	pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+		self.cells.iter_mut()
+	}
+
+	pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+		Grid::from_cells(self.width, self.height, self.cells.iter().map(f).collect())
+	}
+
+	pub fn render(&self, mut symbol: impl FnMut(&T) -> char) -> String {
+		let mut rendering = String::new();
+		for row in self.cells.chunks(self.width.max(1)) {
+			rendering.extend(row.iter().map(&mut symbol));
+			rendering.push('\n');
+		}
+		rendering
+	}
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+	type Output = T;
+
+	fn index(&self, (x, y): (usize, usize)) -> &T {
+		assert!(
+			x < self.width && y < self.height,
+			"({}, {}) is out of bounds",
+			x,
+			y
+		);
+		&self.cells[y * self.width + x]
+	}
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+	fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+		assert!(
+			x < self.width && y < self.height,
+			"({}, {}) is out of bounds",
+			x,
+			y
+		);
+		&mut self.cells[y * self.width + x]
+	}
+}
+
+/// Grid without edges. Only a rectangular region is stored, which grows when
+/// something is set outside of it, and everything else has the default value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfiniteGrid<T> {
+	grid: Grid<T>,
+	// Position of the top left cell of the stored region
+	left: i32,
+	top: i32,
+	default: T,
+}
+
+impl<T: Clone> InfiniteGrid<T> {
+	pub fn new(default: T) -> InfiniteGrid<T> {
+		InfiniteGrid::from_grid(Grid::from_cells(0, 0, vec![]), 0, 0, default)
+	}
+
+	pub fn from_grid(grid: Grid<T>, left: i32, top: i32, default: T) -> InfiniteGrid<T> {
+		InfiniteGrid {
+			grid,
+			left,
+			top,
+			default,
+		}
+	}
+
+	pub fn get(&self, x: i32, y: i32) -> &T {
+		self
+			.grid
+			.get(x - self.left, y - self.top)
+			.unwrap_or(&self.default)
+	}
+
+	pub fn set(&mut self, x: i32, y: i32, value: T) {
+		if !self.grid.in_bounds(x - self.left, y - self.top) {
+			self.grow_to(x, y);
+		}
+		let (left, top) = (self.left, self.top);
+		*self.grid.get_mut(x - left, y - top).unwrap() = value;
+	}
+
+	fn grow_to(&mut self, x: i32, y: i32) {
+		let (x_range, y_range) = if self.grid.width() == 0 || self.grid.height() == 0 {
+			(x..x + 1, y..y + 1)
+		} else {
+			let (x_range, y_range) = self.bounds();
+			(
+				x_range.start.min(x)..x_range.end.max(x + 1),
+				y_range.start.min(y)..y_range.end.max(y + 1),
+			)
+		};
+
+		let mut grown = InfiniteGrid::from_grid(
+			Grid::new(x_range.len(), y_range.len(), self.default.clone()),
+			x_range.start,
+			y_range.start,
+			self.default.clone(),
+		);
+		for (gx, gy) in self.grid.positions() {
+			let value = self.grid[(gx, gy)].clone();
+			*grown
+				.grid
+				.get_mut(
+					gx as i32 + self.left - grown.left,
+					gy as i32 + self.top - grown.top,
+				)
+				.unwrap() = value;
+		}
+		*self = grown;
+	}
+}
+
+impl<T> InfiniteGrid<T> {
+	pub fn default_value(&self) -> &T {
+		&self.default
+	}
+
+	pub fn set_default_value(&mut self, value: T) {
+		self.default = value;
+	}
+
+	/// Columns and rows of the stored region
+	pub fn bounds(&self) -> (Range<i32>, Range<i32>) {
+		(
+			self.left..self.left + self.grid.width() as i32,
+			self.top..self.top + self.grid.height() as i32,
+		)
+	}
+
+	pub fn grid(&self) -> &Grid<T> {
+		&self.grid
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse_digits(input: &str) -> Result<Grid<u32>, ParseError> {
+		Grid::parse(&Source::new(0, input), input, |c| c.to_digit(10))
+	}
+
+	#[test]
+	fn parse_and_render() {
+		let grid = parse_digits("123\n456").unwrap();
+		assert_eq!((grid.width(), grid.height()), (3, 2));
+		assert_eq!(grid[(2, 1)], 6);
+		assert_eq!(grid.get(3, 0), None);
+		assert_eq!(grid.get(-1, 0), None);
+		assert_eq!(
+			grid.render(|d| std::char::from_digit(*d, 10).unwrap()),
+			"123\n456\n"
+		);
+
+		let error = parse_digits("123\n4x6").unwrap_err();
+		assert_eq!((error.line, error.column), (2, 2));
+		let error = parse_digits("123\n45").unwrap_err();
+		assert_eq!(error.message, "expected a row of 3 cells");
+		assert!(parse_digits("").is_err());
+	}
+
+	#[test]
+	fn neighbours() {
+		let grid = Grid::new(3, 3, 0);
+		let mut corner: Vec<_> = grid.neighbours4(0, 0).collect();
+		corner.sort();
+		assert_eq!(corner, vec![(0, 1), (1, 0)]);
+		assert_eq!(grid.neighbours4(1, 1).count(), 4);
+		assert_eq!(grid.neighbours8(0, 0).count(), 3);
+		assert_eq!(grid.neighbours8(1, 1).count(), 8);
+		assert_eq!(grid.neighbours8(2, 1).count(), 5);
+	}
+
+	#[test]
+	fn wrapped_access() {
+		let mut grid = parse_digits("12\n34").unwrap();
+		assert_eq!(*grid.get_wrapped(2, 0), 1);
+		assert_eq!(*grid.get_wrapped(-1, -1), 4);
+		*grid.get_wrapped_mut(3, 2) = 9;
+		assert_eq!(grid[(1, 0)], 9);
+	}
+
+	#[test]
+	fn positions_are_row_by_row() {
+		let grid = Grid::new(2, 2, 0);
+		let positions: Vec<_> = grid.positions().collect();
+		assert_eq!(positions, vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+	}
+
+	#[test]
+	fn infinite_grid_grows() {
+		let mut grid = InfiniteGrid::new(false);
+		assert!(!grid.get(100, -100));
+		grid.set(1, 1, true);
+		grid.set(-2, 3, true);
+		assert_eq!(grid.bounds(), (-2..2, 1..4));
+		assert!(*grid.get(1, 1));
+		assert!(*grid.get(-2, 3));
+		assert!(!grid.get(0, 2));
+
+		grid.set_default_value(true);
+		assert!(*grid.get(100, 100));
+		assert!(!grid.get(0, 2));
+	}
+}
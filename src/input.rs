@@ -0,0 +1,170 @@
+use std::{
+	fs,
+	io::{self, Read},
+	path::{Path, PathBuf},
+};
+
+/// Where an input gets read from
+pub enum InputSource {
+	Stdin,
+	File(PathBuf),
+}
+
+/// Where the puzzle input of a day is looked for when none is given, the days
+/// of other years than the crate's own have a directory for each year
+pub fn default_path(year: u32, day: u32) -> String {
+	if year == crate::YEAR {
+		format!("input/{}.txt", day)
+	} else {
+		format!("input/{}/{}.txt", year, day)
+	}
+}
+
+impl InputSource {
+	/// Used in diagnostics and to tell answers apart when running several inputs
+	pub fn name(&self) -> String {
+		match self {
+			InputSource::Stdin => "<stdin>".into(),
+			InputSource::File(path) => path.display().to_string(),
+		}
+	}
+
+	pub fn read(&self) -> Result<String, String> {
+		let mut contents = String::new();
+		match self {
+			InputSource::Stdin => io::stdin()
+				.read_to_string(&mut contents)
+				.map_err(|e| format!("Failed to read stdin: {}", e))?,
+			InputSource::File(path) => fs::File::open(path)
+				.and_then(|mut file| file.read_to_string(&mut contents))
+				.map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?,
+		};
+		Ok(contents)
+	}
+}
+
+// Supports `*` (any number of characters) and `?` (exactly one character)
+fn matches_glob(pattern: &[char], name: &[char]) -> bool {
+	match (pattern.first(), name.first()) {
+		(None, None) => true,
+		(Some('*'), _) => {
+			matches_glob(&pattern[1..], name) || (!name.is_empty() && matches_glob(pattern, &name[1..]))
+		}
+		(Some('?'), Some(_)) => matches_glob(&pattern[1..], &name[1..]),
+		(Some(p), Some(n)) if p == n => matches_glob(&pattern[1..], &name[1..]),
+		_ => false,
+	}
+}
+
+fn list_files(dir: &Path, pattern: Option<&str>) -> Result<Vec<PathBuf>, String> {
+	let entries = fs::read_dir(dir)
+		.map_err(|e| format!("Failed to read directory '{}': {}", dir.display(), e))?;
+	let pattern: Option<Vec<char>> = pattern.map(|p| p.chars().collect());
+
+	let mut files = Vec::new();
+	for entry in entries.flatten() {
+		let path = entry.path();
+		if !path.is_file() {
+			continue;
+		}
+		if let Some(pattern) = &pattern {
+			let name: Vec<char> = entry.file_name().to_string_lossy().chars().collect();
+			if !matches_glob(pattern, &name) {
+				continue;
+			}
+		}
+		files.push(path);
+	}
+	// Directory order is up to the filesystem, sorting keeps the output stable
+	files.sort();
+	Ok(files)
+}
+
+/// Turns an input argument into the inputs it refers to:
+///
+/// ```text
+/// -                    stdin
+/// inputs/22/           every file in the directory
+/// inputs/22/*.txt      every file matching the pattern, only the last
+///                      component of the path may contain wildcards
+/// input/22.txt         just that file
+/// ```
+pub fn resolve(arg: &str) -> Result<Vec<InputSource>, String> {
+	if arg == "-" {
+		return Ok(vec![InputSource::Stdin]);
+	}
+
+	let path = Path::new(arg);
+	let files = if path.is_dir() {
+		list_files(path, None)?
+	} else if arg.contains(['*', '?']) {
+		let dir = match path.parent() {
+			Some(dir) if !dir.as_os_str().is_empty() => dir,
+			_ => Path::new("."),
+		};
+		let pattern = path
+			.file_name()
+			.map(|name| name.to_string_lossy().to_string());
+		if dir.to_string_lossy().contains(['*', '?']) {
+			return Err(format!(
+				"Wildcards are only supported in file names: '{}'",
+				arg
+			));
+		}
+		list_files(dir, pattern.as_deref())?
+	} else {
+		vec![path.to_path_buf()]
+	};
+
+	if files.is_empty() {
+		return Err(format!("No input files found for '{}'", arg));
+	}
+	Ok(files.into_iter().map(InputSource::File).collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn glob(pattern: &str, name: &str) -> bool {
+		let pattern: Vec<char> = pattern.chars().collect();
+		let name: Vec<char> = name.chars().collect();
+		matches_glob(&pattern, &name)
+	}
+
+	#[test]
+	fn glob_matching() {
+		assert!(glob("*.txt", "22.txt"));
+		assert!(glob("*.txt", ".txt"));
+		assert!(glob("variant-?.txt", "variant-1.txt"));
+		assert!(glob("*", "anything"));
+		assert!(!glob("*.txt", "22.txt.bak"));
+		assert!(!glob("variant-?.txt", "variant-12.txt"));
+		assert!(!glob("22.txt", "21.txt"));
+	}
+
+	#[test]
+	fn resolve_inputs() {
+		let dir = std::env::temp_dir().join(format!("aoc-input-test-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		for name in ["b.txt", "a.txt", "notes.md"] {
+			fs::write(dir.join(name), name).unwrap();
+		}
+		let names = |arg: &str| -> Vec<String> {
+			resolve(arg)
+				.unwrap()
+				.iter()
+				.map(|input| input.read().unwrap())
+				.collect()
+		};
+
+		let dir_arg = dir.to_string_lossy().to_string();
+		assert_eq!(names(&dir_arg), vec!["a.txt", "b.txt", "notes.md"]);
+		assert_eq!(names(&format!("{}/*.txt", dir_arg)), vec!["a.txt", "b.txt"]);
+		assert_eq!(names(&format!("{}/notes.md", dir_arg)), vec!["notes.md"]);
+		assert!(resolve(&format!("{}/*.rs", dir_arg)).is_err());
+		assert!(matches!(resolve("-").unwrap()[0], InputSource::Stdin));
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,88 @@
+use std::fmt;
+
+/// Just enough JSON to print reports, without pulling in a serializer
+pub enum Json {
+	Null,
+	Bool(bool),
+	Number(String),
+	String(String),
+	Array(Vec<Json>),
+	Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+	pub fn number(value: impl fmt::Display) -> Json {
+		Json::Number(value.to_string())
+	}
+
+	pub fn string(value: impl Into<String>) -> Json {
+		Json::String(value.into())
+	}
+}
+
+fn write_escaped(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+	write!(f, "\"")?;
+	for c in value.chars() {
+		match c {
+			'"' => write!(f, "\\\"")?,
+			'\\' => write!(f, "\\\\")?,
+			'\n' => write!(f, "\\n")?,
+			'\r' => write!(f, "\\r")?,
+			'\t' => write!(f, "\\t")?,
+			c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+			c => write!(f, "{}", c)?,
+		}
+	}
+	write!(f, "\"")
+}
+
+impl fmt::Display for Json {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Json::Null => write!(f, "null"),
+			Json::Bool(value) => write!(f, "{}", value),
+			Json::Number(value) => write!(f, "{}", value),
+			Json::String(value) => write_escaped(f, value),
+			Json::Array(items) => {
+				write!(f, "[")?;
+				for (i, item) in items.iter().enumerate() {
+					if i > 0 {
+						write!(f, ",")?;
+					}
+					write!(f, "{}", item)?;
+				}
+				write!(f, "]")
+			}
+			Json::Object(fields) => {
+				write!(f, "{{")?;
+				for (i, (key, value)) in fields.iter().enumerate() {
+					if i > 0 {
+						write!(f, ",")?;
+					}
+					write_escaped(f, key)?;
+					write!(f, ":{}", value)?;
+				}
+				write!(f, "}}")
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn display_json() {
+		let json = Json::Object(vec![
+			("day", Json::number(13)),
+			("answer", Json::string("#.\n\"#\"")),
+			("error", Json::Null),
+			("parts", Json::Array(vec![Json::number(1), Json::number(2)])),
+		]);
+		assert_eq!(
+			json.to_string(),
+			r##"{"day":13,"answer":"#.\n\"#\"","error":null,"parts":[1,2]}"##
+		);
+	}
+}
@@ -0,0 +1,104 @@
+//! Solutions to [Advent of Code 2021](https://adventofcode.com/2021).
+//!
+//! Every day lives in its own module with a `parse_input` function, `part1`
+//! and `part2` solvers and the types they work with. Each day also has a unit
+//! struct implementing [`Solution`](solution::Solution), which is what the
+//! runner uses to treat all days the same way.
+//!
+//! ```
+//! use advent_of_code_2021::{day1, solution::Solution};
+//!
+//! let depths = day1::parse_input("199\n200\n208\n210\n200").unwrap();
+//! assert_eq!(day1::part1(&depths), 3);
+//! assert_eq!(day1::Day1::DAY, 1);
+//! ```
+
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+
+pub mod bench;
+pub mod cancel;
+#[cfg(test)]
+mod differential;
+pub mod generate;
+pub mod grid;
+pub mod input;
+pub mod json;
+pub mod memory;
+pub mod parallel;
+pub mod parse;
+pub mod runner;
+pub mod scaffold;
+pub mod search;
+pub mod server;
+pub mod solution;
+pub mod terminal;
+pub mod verify;
+pub mod visualize;
+
+use solution::Runner;
+
+/// The year of the days in this module, other years have a `yearNNNN` module
+pub const YEAR: u32 = 2021;
+
+/// Every day, in order
+pub static SOLUTIONS: [&dyn Runner; 25] = [
+	&day1::Day1,
+	&day2::Day2,
+	&day3::Day3,
+	&day4::Day4,
+	&day5::Day5,
+	&day6::Day6,
+	&day7::Day7,
+	&day8::Day8,
+	&day9::Day9,
+	&day10::Day10,
+	&day11::Day11,
+	&day12::Day12,
+	&day13::Day13,
+	&day14::Day14,
+	&day15::Day15,
+	&day16::Day16,
+	&day17::Day17,
+	&day18::Day18,
+	&day19::Day19,
+	&day20::Day20,
+	&day21::Day21,
+	&day22::Day22,
+	&day23::Day23,
+	&day24::Day24,
+	&day25::Day25,
+];
+
+/// Every year and its days, `new-day --year` adds to it
+pub static YEARS: [(u32, &[&dyn Runner]); 1] = [(YEAR, &SOLUTIONS)];
+
+pub fn solutions_for_year(year: u32) -> Option<&'static [&'static dyn Runner]> {
+	YEARS
+		.iter()
+		.find(|(y, _)| *y == year)
+		.map(|(_, solutions)| *solutions)
+}
@@ -1,157 +1,719 @@
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
-
+use advent_of_code_2021::{
+	bench,
+	cancel::{self, CancelToken},
+	day1, day2,
+	generate::Rng,
+	input::{self, InputSource},
+	json::Json,
+	parallel,
+	runner::{self, Outcome, OutputFormat},
+	scaffold, server,
+	solution::{self, Runner},
+	terminal, verify,
+	visualize::{FrameFormat, FrameSink, FrameWriter, Recording},
+	YEAR,
+};
+use std::any::Any;
 use std::fs::File;
-use std::io::prelude::*;
+use std::io::{self, prelude::*, BufReader};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
 use std::{env, process};
 
-fn run(day: i32, part: i32, input_filename: &str) {
+fn print_usage(program: &str) {
+	println!(
+		"Usage: {} <day> <part> [input] [--format text|json] [--param <name>=<value>]...",
+		program
+	);
+	println!(
+		"       {} <day> <part> [input] --visualize <directory> [--frame-format ppm|pgm|text]",
+		program
+	);
+	println!("       {} <day> <part> [input] --interactive", program);
+	println!(
+		"       {} <day> <part> [input] --report [--param <name>=<value>]...",
+		program
+	);
+	println!("       {} run-all [--format text|json]", program);
+	println!(
+		"       {} bench <day> [--iterations <n>] [--format text|tsv] [input]",
+		program
+	);
+	println!("       {} verify [answers-file]", program);
+	println!(
+		"       {} generate <day> [--seed <n>] [--size <n>]",
+		program
+	);
+	println!("       {} serve [--port <n>]", program);
+	println!(
+		"       {} sweep [input] [--window <n>] [--format text|json]",
+		program
+	);
+	println!("       {} trajectory [input] [--part 1|2]", program);
+	println!("       {} new-day <day> [--year <year>]", program);
+	println!();
+	println!("Any of them can be given `--jobs <n>` to run on up to n threads, and");
+	println!("`--timeout <seconds>` to give up on parts that take longer than that.");
+	println!(
+		"`--year <year>` picks the year of the days, {} by default.",
+		YEAR
+	);
+	println!("An input is a file, `-` for stdin, a directory or a pattern like `inputs/*.txt`.");
+}
+
+// The year picked with `--year`, and its days
+static SELECTED_YEAR: OnceLock<(u32, &'static [&'static dyn Runner])> = OnceLock::new();
+
+fn selected_year() -> u32 {
+	SELECTED_YEAR.get().map_or(YEAR, |(year, _)| *year)
+}
+
+fn solutions() -> &'static [&'static dyn Runner] {
+	match SELECTED_YEAR.get() {
+		Some((_, solutions)) => solutions,
+		None => advent_of_code_2021::solutions_for_year(YEAR).unwrap(),
+	}
+}
+
+fn find_solution(day: u32) -> &'static dyn Runner {
+	match solution::find_solution(solutions(), day) {
+		Some(solution) => solution,
+		None => {
+			eprintln!("Day {} not found", day);
+			process::exit(1);
+		}
+	}
+}
+
+fn read_input(input_filename: &str) -> String {
 	let mut input_file = File::open(input_filename)
-		.expect(format!("Input file '{}' not found", input_filename).as_str());
+		.unwrap_or_else(|_| panic!("Input file '{}' not found", input_filename));
 
 	let mut contents = String::new();
 	input_file
 		.read_to_string(&mut contents)
 		.expect("Failed to read input file");
+	contents
+}
 
-	// Janky solution so that I could specify what I want to run
-	let parse_error_msg = "Failed to parse input";
-	match format!("{}.{}", day, part).as_str() {
-		"1.1" => println!(
-			"{}",
-			day1::part1(&day1::parse_input(&contents).expect(parse_error_msg))
-		),
-		"1.2" => println!(
-			"{}",
-			day1::part2(&day1::parse_input(&contents).expect(parse_error_msg))
-		),
-		"2.1" => println!(
-			"{}",
-			day2::part1(&day2::parse_input(&contents).expect(parse_error_msg))
-		),
-		"2.2" => println!(
-			"{}",
-			day2::part2(&day2::parse_input(&contents).expect(parse_error_msg))
-		),
-		"3.1" => println!(
-			"{}",
-			day3::part1(&day3::parse_input(&contents).expect(parse_error_msg))
-		),
-		"3.2" => println!(
-			"{}",
-			day3::part2(&day3::parse_input(&contents).expect(parse_error_msg))
-		),
-		"4.1" => println!(
-			"{}",
-			day4::part1(&day4::parse_input(&contents).expect(parse_error_msg))
-		),
-		"4.2" => println!(
-			"{}",
-			day4::part2(&day4::parse_input(&contents).expect(parse_error_msg))
-		),
-		"5.1" => println!(
-			"{}",
-			day5::part1(&day5::parse_input(&contents).expect(parse_error_msg))
-		),
-		"5.2" => println!(
-			"{}",
-			day5::part2(&day5::parse_input(&contents).expect(parse_error_msg))
-		),
-		"6.1" => println!(
-			"{}",
-			day6::part1(&day6::parse_input(&contents).expect(parse_error_msg))
-		),
-		"6.2" => println!(
-			"{}",
-			day6::part2(&day6::parse_input(&contents).expect(parse_error_msg))
-		),
-		"7.1" => println!(
-			"{}",
-			day7::part1(&day7::parse_input(&contents).expect(parse_error_msg))
-		),
-		"7.2" => println!(
-			"{}",
-			day7::part2(&day7::parse_input(&contents).expect(parse_error_msg))
-		),
-		"8.1" => println!("{}", day8::part1(&day8::parse_input(&contents))),
-		"8.2" => println!("{}", day8::part2(&day8::parse_input(&contents))),
-		"9.1" => println!("{}", day9::part1(day9::parse_input(&contents))),
-		"9.2" => println!("{}", day9::part2(day9::parse_input(&contents))),
-		"10.1" => println!("{}", day10::part1(&day10::parse_input(&contents))),
-		"10.2" => println!("{}", day10::part2(&day10::parse_input(&contents))),
-		"11.1" => println!("{}", day11::part1(&day11::parse_input(&contents))),
-		"11.2" => println!("{}", day11::part2(&day11::parse_input(&contents))),
-		"12.1" => println!("{}", day12::part1(&day12::parse_input(&contents))),
-		"12.2" => println!("{}", day12::part2(&day12::parse_input(&contents))),
-		"13.1" => println!("{}", day13::part1(&day13::parse_input(&contents))),
-		"13.2" => day13::part2(&day13::parse_input(&contents)),
-		"14.1" => println!("{}", day14::part1(&day14::parse_input(&contents))),
-		"14.2" => println!("{}", day14::part2(&day14::parse_input(&contents))),
-		"15.1" => println!("{}", day15::part1(&day15::parse_input(&contents))),
-		"15.2" => println!("{}", day15::part2(&mut day15::parse_input(&contents))),
-		"16.1" => println!("{}", day16::part1(&day16::parse_input(&contents))),
-		"16.2" => println!("{}", day16::part2(&day16::parse_input(&contents))),
-		"17.1" => println!("{}", day17::part1(&day17::parse_input(&contents))),
-		"17.2" => println!("{}", day17::part2(&day17::parse_input(&contents))),
-		"18.1" => println!("{}", day18::part1(&day18::parse_input(&contents))),
-		"18.2" => println!("{}", day18::part2(&day18::parse_input(&contents))),
-		"19.1" => println!("{}", day19::part1(&day19::parse_input(&contents))),
-		"19.2" => println!("{}", day19::part2(&day19::parse_input(&contents))),
-		"20.1" => println!("{}", day20::part1(&day20::parse_input(&contents))),
-		"20.2" => println!("{}", day20::part2(&day20::parse_input(&contents))),
-		"21.1" => println!("{}", day21::part1(&day21::parse_input(&contents))),
-		"21.2" => println!("{}", day21::part2(&day21::parse_input(&contents))),
-		"22.1" => println!("{}", day22::part1(&day22::parse_input(&contents))),
-		"22.2" => println!("{}", day22::part2(day22::parse_input(&contents))),
-		"23.1" => println!("{}", day23::part1(day23::parse_input(&contents))),
-		"23.2" => println!("{}", day23::part2(day23::parse_input(&contents))),
-		"24.1" => println!("{}", day24::part1(day24::parse_input(&contents))),
-		"24.2" => println!("{}", day24::part2(day24::parse_input(&contents))),
-		"25.1" => println!("{}", day25::part1(day25::parse_input(&contents))),
-		"25.2" => println!("{}", day25::part2(day25::parse_input(&contents))),
-		_ => println!("Day {} part {} not found", day, part),
+// Removes `<flag> <value>` from the arguments, if it is there
+fn take_flag(args: &mut Vec<String>, flag: &str) -> Option<String> {
+	let index = args.iter().position(|arg| arg == flag)?;
+	args.remove(index);
+	if index == args.len() {
+		panic!("Missing value for {}", flag);
+	}
+	Some(args.remove(index))
+}
+
+// Removes `<flag>` from the arguments, says whether it was there
+fn take_switch(args: &mut Vec<String>, flag: &str) -> bool {
+	match args.iter().position(|arg| arg == flag) {
+		Some(index) => {
+			args.remove(index);
+			true
+		}
+		None => false,
+	}
+}
+
+fn take_output_format(args: &mut Vec<String>) -> OutputFormat {
+	match take_flag(args, "--format").as_deref() {
+		None | Some("text") => OutputFormat::Text,
+		Some("json") => OutputFormat::Json,
+		Some(other) => panic!("Unknown output format '{}'", other),
+	}
+}
+
+// Removes every `--param <name>=<value>`, there can be any number of them
+fn take_params(args: &mut Vec<String>) -> Vec<(String, String)> {
+	let mut settings = vec![];
+	while let Some(setting) = take_flag(args, "--param") {
+		match setting.split_once('=') {
+			Some((name, value)) => settings.push((name.to_string(), value.to_string())),
+			None => panic!(
+				"Expected --param to be given as <name>=<value>, found '{}'",
+				setting
+			),
+		}
+	}
+	settings
+}
+
+fn resolve_inputs(arg: &str) -> Vec<InputSource> {
+	input::resolve(arg).unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		process::exit(1);
+	})
+}
+
+fn read_source(source: &InputSource) -> String {
+	source.read().unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		process::exit(1);
+	})
+}
+
+// Runs the part once for every input. With more than one input each answer
+// is labeled with the file it came from, and a failing input doesn't stop
+// the rest from running.
+fn run(
+	day: u32,
+	part: u32,
+	inputs: &[InputSource],
+	settings: &[(String, String)],
+	format: &OutputFormat,
+) {
+	let solution = find_solution(day);
+	let labeled = inputs.len() > 1;
+
+	let mut failed = false;
+	for source in inputs {
+		let name = source.name();
+		let contents = read_source(source);
+		let report =
+			runner::with_silent_panics(|| runner::run_parts_with(solution, &contents, &[part], settings));
+		match format {
+			OutputFormat::Json => runner::print_input_json(&name, &report),
+			OutputFormat::Text => match &report.parts[0].outcome {
+				Outcome::Solved(answer) => {
+					let answer = answer.to_string();
+					if !labeled {
+						println!("{}", answer);
+					} else if answer.contains('\n') {
+						println!("{}:\n{}", name, answer);
+					} else {
+						println!("{}: {}", name, answer);
+					}
+				}
+				Outcome::Skipped(reason) => match &report.parse_error {
+					Some(error) => eprintln!("{}", error.render(&name, &contents)),
+					None => eprintln!("Day {} part {} failed on {}: {}", day, part, name, reason),
+				},
+				Outcome::Failed(reason) => {
+					eprintln!("Day {} part {} failed on {}: {}", day, part, name, reason)
+				}
+				Outcome::TimedOut(timeout) => eprintln!(
+					"Day {} part {} timed out after {} on {}",
+					day,
+					part,
+					runner::format_timeout(*timeout),
+					name
+				),
+			},
+		}
+		failed |= !matches!(report.parts[0].outcome, Outcome::Solved(_));
+	}
+
+	if failed {
+		process::exit(1);
+	}
+}
+
+fn parse_single_input(solution: &dyn Runner, inputs: &[InputSource]) -> Box<dyn Any> {
+	let source = match inputs {
+		[source] => source,
+		_ => {
+			eprintln!("Visualizing needs a single input");
+			process::exit(1);
+		}
+	};
+	let name = source.name();
+	let contents = read_source(source);
+	solution.parse(&contents).unwrap_or_else(|e| {
+		eprintln!("{}", e.render(&name, &contents));
+		process::exit(1);
+	})
+}
+
+fn part_params(solution: &dyn Runner, part: u32, settings: &[(String, String)]) -> Box<dyn Any> {
+	solution.params(part, settings).unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		process::exit(1);
+	})
+}
+
+// Pushes the frames of the part's simulation, stopping at the `--timeout` the
+// same way solving the part would
+fn record_frames(
+	solution: &dyn Runner,
+	day: u32,
+	part: u32,
+	input: &dyn Any,
+	params: &dyn Any,
+	frames: &mut dyn FrameSink,
+) {
+	let timeout = cancel::timeout();
+	let token = CancelToken::with_timeout(timeout);
+	let visualized = runner::with_silent_panics(|| {
+		runner::catch_panic(|| {
+			cancel::scope(&token, || solution.visualize(input, part, params, frames))
+		})
+	});
+	match (visualized, timeout) {
+		(Ok(true), _) => {}
+		(Ok(false), _) => {
+			eprintln!("Day {} part {} has nothing to visualize", day, part);
+			process::exit(1);
+		}
+		(Err(_), Some(timeout)) if token.is_cancelled() => {
+			eprintln!(
+				"Day {} part {} timed out after {}",
+				day,
+				part,
+				runner::format_timeout(timeout)
+			);
+			process::exit(1);
+		}
+		(Err(e), _) => {
+			eprintln!("Day {} part {} failed: {}", day, part, e);
+			process::exit(1);
+		}
+	}
+}
+
+// Writes a frame for every step of the part's simulation instead of printing
+// the answer
+fn visualize(
+	day: u32,
+	part: u32,
+	inputs: &[InputSource],
+	settings: &[(String, String)],
+	directory: &str,
+	format: FrameFormat,
+) {
+	let solution = find_solution(day);
+	let input = parse_single_input(solution, inputs);
+	let params = part_params(solution, part, settings);
+
+	let mut writer = FrameWriter::new(Path::new(directory), format).unwrap_or_else(|e| {
+		eprintln!("Failed to create '{}': {}", directory, e);
+		process::exit(1);
+	});
+	record_frames(
+		solution,
+		day,
+		part,
+		input.as_ref(),
+		params.as_ref(),
+		&mut writer,
+	);
+	match writer.finish() {
+		Ok(count) => println!("Wrote {} frames to {}", count, directory),
+		Err(e) => {
+			eprintln!("Failed to write frames to '{}': {}", directory, e);
+			process::exit(1);
+		}
+	}
+}
+
+// Prints the report of the part for every input as a line of JSON, for
+// plotting or other processing
+fn report(day: u32, part: u32, inputs: &[InputSource], settings: &[(String, String)]) {
+	let solution = find_solution(day);
+	let params = part_params(solution, part, settings);
+
+	let mut failed = false;
+	for source in inputs {
+		let name = source.name();
+		let contents = read_source(source);
+		let input = match solution.parse(&contents) {
+			Ok(input) => input,
+			Err(e) => {
+				eprintln!("{}", e.render(&name, &contents));
+				failed = true;
+				continue;
+			}
+		};
+		let report = match solution.report(input.as_ref(), part, params.as_ref()) {
+			Some(report) => report,
+			None => {
+				eprintln!("Day {} part {} has no report", day, part);
+				process::exit(1);
+			}
+		};
+		let json = Json::Object(vec![
+			("input", Json::string(name)),
+			("day", Json::number(day)),
+			("part", Json::number(part)),
+			("report", report),
+		]);
+		println!("{}", json);
+	}
+
+	if failed {
+		process::exit(1);
+	}
+}
+
+// Records every step of the part's simulation, which can then be stepped
+// through back and forth with the keyboard
+fn step_through(day: u32, part: u32, inputs: &[InputSource], settings: &[(String, String)]) {
+	let solution = find_solution(day);
+	let input = parse_single_input(solution, inputs);
+	let params = part_params(solution, part, settings);
+
+	let mut recording = Recording::default();
+	record_frames(
+		solution,
+		day,
+		part,
+		input.as_ref(),
+		params.as_ref(),
+		&mut recording,
+	);
+	if let Err(e) = terminal::step_through(&format!("Day {} part {}", day, part), &recording) {
+		eprintln!("Failed to step through the simulation: {}", e);
+		process::exit(1);
+	}
+}
+
+fn bench(args: &[String]) {
+	let mut day = None;
+	let mut iterations = 10;
+	let mut format = bench::BenchFormat::Text;
+	let mut input_arg = None;
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--iterations" | "-n" => {
+				iterations = args
+					.next()
+					.and_then(|n| n.parse().ok())
+					.filter(|n| *n > 0)
+					.expect("Expected a positive number of iterations");
+			}
+			"--format" => {
+				format = match args.next().map(|s| s.as_str()) {
+					Some("text") => bench::BenchFormat::Text,
+					Some("tsv") => bench::BenchFormat::Tsv,
+					_ => panic!("Expected format to be 'text' or 'tsv'"),
+				};
+			}
+			_ if day.is_none() => day = Some(arg.parse::<u32>().expect("Failed to parse day")),
+			_ => input_arg = Some(arg.clone()),
+		}
+	}
+
+	let day = day.expect("Missing day to benchmark");
+	let solution = find_solution(day);
+	let input_arg = input_arg.unwrap_or_else(|| input::default_path(selected_year(), day));
+	let contents = match resolve_inputs(&input_arg).as_slice() {
+		[source] => read_source(source),
+		_ => {
+			eprintln!(
+				"Benchmarking needs a single input, '{}' matches several",
+				input_arg
+			);
+			process::exit(1);
+		}
+	};
+
+	match bench::bench_day(solution, &contents, iterations) {
+		Ok(report) => bench::print_report(&report, &format),
+		Err(e) => {
+			eprintln!("Failed to parse input: {}", e);
+			process::exit(1);
+		}
+	}
+}
+
+// Prints a made up input for the day, the same seed always gives the same input
+fn generate(args: &[String]) {
+	let mut day = None;
+	let mut seed = 0;
+	let mut size = 10;
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--seed" => {
+				seed = args
+					.next()
+					.and_then(|n| n.parse().ok())
+					.expect("Expected a number as the seed");
+			}
+			"--size" => {
+				size = args
+					.next()
+					.and_then(|n| n.parse().ok())
+					.expect("Expected a number as the size");
+			}
+			_ => day = Some(arg.parse::<u32>().expect("Failed to parse day")),
+		}
+	}
+
+	let day = day.expect("Missing day to generate an input for");
+	print!("{}", find_solution(day).generate(&mut Rng::new(seed), size));
+}
+
+fn verify(answers_filename: &str) {
+	let answers = verify::KnownAnswers::parse(&read_input(answers_filename))
+		.unwrap_or_else(|e| panic!("Failed to parse '{}': {}", answers_filename, e));
+	let checks = verify::check_reports(&runner::run_all(solutions()), &answers);
+	verify::print_checks(&checks);
+	process::exit(verify::exit_code(&checks));
+}
+
+fn serve(args: &mut Vec<String>) {
+	let port = match take_flag(args, "--port") {
+		Some(port) => port.parse::<u16>().expect("Failed to parse port"),
+		None => 8080,
+	};
+	let listener = TcpListener::bind(("127.0.0.1", port))
+		.unwrap_or_else(|e| panic!("Failed to listen on port {}: {}", port, e));
+	println!("Listening on http://{}", listener.local_addr().unwrap());
+	if let Err(e) = server::serve(listener, solutions()) {
+		eprintln!("Server stopped: {}", e);
+		process::exit(1);
+	}
+}
+
+// Streams the depths of day 1 instead of reading the whole file, for sensor
+// logs that don't fit into memory
+fn sweep(args: &mut Vec<String>) {
+	let format = take_output_format(args);
+	let window = match take_flag(args, "--window") {
+		Some(window) => window
+			.parse::<usize>()
+			.ok()
+			.filter(|window| *window > 0)
+			.expect("Expected a positive window length"),
+		None => 1,
+	};
+	let input_arg = args
+		.get(2)
+		.cloned()
+		.unwrap_or_else(|| input::default_path(YEAR, 1));
+	let report = if input_arg == "-" {
+		day1::sweep_reader(io::stdin().lock(), window)
+	} else {
+		let file = File::open(&input_arg).unwrap_or_else(|e| {
+			eprintln!("Failed to open '{}': {}", input_arg, e);
+			process::exit(1);
+		});
+		day1::sweep_reader(BufReader::new(file), window)
+	};
+	let report = report.unwrap_or_else(|e| {
+		let name = if input_arg == "-" {
+			"<stdin>"
+		} else {
+			&input_arg
+		};
+		eprintln!("{}: {}", name, e);
+		process::exit(1);
+	});
+
+	match format {
+		OutputFormat::Text => {
+			println!("Increases: {}", report.increases);
+			for (name, run) in [
+				("increasing", report.longest_increasing),
+				("decreasing", report.longest_decreasing),
+			] {
+				println!(
+					"Longest {} run: {} in a row, from window {}",
+					name,
+					run.length,
+					run.start + 1
+				);
+			}
+		}
+		OutputFormat::Json => {
+			let run = |run: day1::Run| {
+				Json::Object(vec![
+					("start", Json::number(run.start)),
+					("length", Json::number(run.length)),
+				])
+			};
+			let json = Json::Object(vec![
+				("window", Json::number(window)),
+				("increases", Json::number(report.increases)),
+				("longest_increasing", run(report.longest_increasing)),
+				("longest_decreasing", run(report.longest_decreasing)),
+			]);
+			println!("{}", json);
+		}
+	}
+}
+
+// Prints every position of the day 2 submarine as CSV
+fn trajectory(args: &mut Vec<String>) {
+	let part = match take_flag(args, "--part") {
+		Some(part) => part
+			.parse::<u32>()
+			.ok()
+			.filter(|part| *part == 1 || *part == 2)
+			.expect("Expected part 1 or 2"),
+		None => 1,
+	};
+	let input_arg = args
+		.get(2)
+		.cloned()
+		.unwrap_or_else(|| input::default_path(YEAR, 2));
+	let inputs = resolve_inputs(&input_arg);
+	let source = match inputs.as_slice() {
+		[source] => source,
+		_ => {
+			eprintln!(
+				"The trajectory needs a single input, '{}' matches several",
+				input_arg
+			);
+			process::exit(1);
+		}
+	};
+	let contents = read_source(source);
+	let script = day2::parse_input(&contents).unwrap_or_else(|e| {
+		eprintln!("{}", e.render(&source.name(), &contents));
+		process::exit(1);
+	});
+
+	let mut submarine = day2::Submarine::new(day2::Mode::for_part(part));
+	let result = submarine.run(&script);
+	// Everything up to the command that failed is still worth looking at
+	print!("{}", submarine.trajectory_csv());
+	if let Err(e) = result {
+		eprintln!("{}", e);
+		process::exit(1);
+	}
+}
+
+fn new_day(args: &[String], year: u32) {
+	let day = args
+		.first()
+		.and_then(|day| day.parse::<u32>().ok())
+		.expect("Expected the day to add");
+	match scaffold::new_day(Path::new("."), year, day) {
+		Ok(files) => {
+			for file in files {
+				println!("Wrote {}", file.display());
+			}
+		}
+		Err(e) => {
+			eprintln!("{}", e);
+			process::exit(1);
+		}
 	}
 }
 
 fn main() {
-	let args: Vec<String> = env::args().collect();
+	let mut args: Vec<String> = env::args().collect();
+	if let Some(jobs) = take_flag(&mut args, "--jobs") {
+		let jobs = jobs
+			.parse()
+			.ok()
+			.filter(|n| *n > 0)
+			.expect("Expected a positive number of jobs");
+		parallel::set_jobs(jobs);
+	}
+	if let Some(seconds) = take_flag(&mut args, "--timeout") {
+		let seconds = seconds
+			.parse::<f64>()
+			.ok()
+			.filter(|seconds| *seconds > 0.0 && seconds.is_finite())
+			.expect("Expected the timeout as a positive number of seconds");
+		cancel::set_timeout(Some(Duration::from_secs_f64(seconds)));
+	}
+	let year =
+		take_flag(&mut args, "--year").map(|year| year.parse::<u32>().expect("Failed to parse year"));
+	// The year of a new day doesn't need to have any days yet
+	if args.len() > 1 && args[1] == "new-day" {
+		new_day(&args[2..], year.unwrap_or(YEAR));
+		return;
+	}
+	if let Some(year) = year {
+		match advent_of_code_2021::solutions_for_year(year) {
+			Some(solutions) => SELECTED_YEAR.get_or_init(|| (year, solutions)),
+			None => {
+				eprintln!(
+					"There are no solutions for {}, add some with `new-day <day> --year {}`",
+					year, year
+				);
+				process::exit(1);
+			}
+		};
+	}
+	if args.len() > 1 && args[1] == "run-all" {
+		let reports = runner::run_all(solutions());
+		match take_output_format(&mut args) {
+			OutputFormat::Text => runner::print_summary(&reports),
+			OutputFormat::Json => runner::print_json(&reports),
+		}
+		return;
+	}
+
+	if args.len() > 1 && args[1] == "verify" {
+		let default_answers = if selected_year() == YEAR {
+			"answers.toml".to_string()
+		} else {
+			format!("answers-{}.toml", selected_year())
+		};
+		verify(args.get(2).unwrap_or(&default_answers));
+		return;
+	}
+
+	if args.len() > 1 && args[1] == "bench" {
+		bench(&args[2..]);
+		return;
+	}
+
+	if args.len() > 1 && args[1] == "generate" {
+		generate(&args[2..]);
+		return;
+	}
+
+	if args.len() > 1 && args[1] == "sweep" {
+		sweep(&mut args);
+		return;
+	}
+
+	if args.len() > 1 && args[1] == "trajectory" {
+		trajectory(&mut args);
+		return;
+	}
+
+	if args.len() > 1 && args[1] == "serve" {
+		serve(&mut args);
+		return;
+	}
+
+	let format = take_output_format(&mut args);
+	let settings = take_params(&mut args);
+	let visualize_directory = take_flag(&mut args, "--visualize");
+	let interactive = take_switch(&mut args, "--interactive");
+	let wants_report = take_switch(&mut args, "--report");
+	let frame_format = match take_flag(&mut args, "--frame-format") {
+		Some(name) => {
+			FrameFormat::parse(&name).unwrap_or_else(|| panic!("Unknown frame format '{}'", name))
+		}
+		None => FrameFormat::Ppm,
+	};
 	if args.len() < 3 {
-		println!("Usage: {} <day> <part> [input-file]", args[0]);
+		print_usage(&args[0]);
 		process::exit(0);
 	}
 
-	let day = args[1].parse::<i32>().expect("Failed to parse day");
+	let day = args[1].parse::<u32>().expect("Failed to parse day");
 
-	let part = args[2].parse::<i32>().expect("Failed to parse part");
+	let part = args[2].parse::<u32>().expect("Failed to parse part");
 
-	let input_filename;
-	if args.len() > 3 {
-		input_filename = args[3].clone();
+	let input_arg = if args.len() > 3 {
+		args[3].clone()
 	} else {
-		input_filename = format!("input/{}.txt", day);
+		input::default_path(selected_year(), day)
+	};
+	let inputs = resolve_inputs(&input_arg);
+	if (interactive || wants_report || visualize_directory.is_some()) && part != 1 && part != 2 {
+		eprintln!("Part {} not found", part);
+		process::exit(1);
+	}
+	match visualize_directory {
+		_ if interactive => step_through(day, part, &inputs, &settings),
+		_ if wants_report => report(day, part, &inputs, &settings),
+		Some(directory) => visualize(day, part, &inputs, &settings, &directory, frame_format),
+		None => run(day, part, &inputs, &settings, &format),
 	}
-	run(day, part, &input_filename);
 }
@@ -0,0 +1,138 @@
+// Counts the bytes that get allocated while parsing and solving, when built
+// with the `memory` feature. The counters are shared by the whole process, so
+// anything allocating on other threads at the same time gets counted too.
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+	/// Most bytes in use at once, on top of what was in use before
+	pub peak: usize,
+	/// Every allocation added up, even the ones that were freed again
+	pub total: usize,
+}
+
+#[cfg(feature = "memory")]
+mod counting {
+	use std::{
+		alloc::{GlobalAlloc, Layout, System},
+		sync::atomic::{AtomicUsize, Ordering},
+	};
+
+	pub static CURRENT: AtomicUsize = AtomicUsize::new(0);
+	pub static PEAK: AtomicUsize = AtomicUsize::new(0);
+	pub static TOTAL: AtomicUsize = AtomicUsize::new(0);
+
+	fn allocated(size: usize) {
+		TOTAL.fetch_add(size, Ordering::Relaxed);
+		let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+		PEAK.fetch_max(current, Ordering::Relaxed);
+	}
+
+	fn freed(size: usize) {
+		CURRENT.fetch_sub(size, Ordering::Relaxed);
+	}
+
+	/// Passes everything on to the system allocator and counts the bytes
+	pub struct CountingAllocator;
+
+	unsafe impl GlobalAlloc for CountingAllocator {
+		unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+			let ptr = System.alloc(layout);
+			if !ptr.is_null() {
+				allocated(layout.size());
+			}
+			ptr
+		}
+
+		unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+			let ptr = System.alloc_zeroed(layout);
+			if !ptr.is_null() {
+				allocated(layout.size());
+			}
+			ptr
+		}
+
+		// A grown vector counts as a new allocation of its new size
+		unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+			let new_ptr = System.realloc(ptr, layout, new_size);
+			if !new_ptr.is_null() {
+				freed(layout.size());
+				allocated(new_size);
+			}
+			new_ptr
+		}
+
+		unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+			System.dealloc(ptr, layout);
+			freed(layout.size());
+		}
+	}
+}
+
+#[cfg(feature = "memory")]
+#[global_allocator]
+static ALLOCATOR: counting::CountingAllocator = counting::CountingAllocator;
+
+pub fn enabled() -> bool {
+	cfg!(feature = "memory")
+}
+
+/// Runs `f` and says how much it allocated, `None` without the `memory` feature
+#[cfg(feature = "memory")]
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<MemoryUsage>) {
+	use counting::{CURRENT, PEAK, TOTAL};
+	use std::sync::atomic::Ordering;
+
+	let start_current = CURRENT.load(Ordering::Relaxed);
+	let start_total = TOTAL.load(Ordering::Relaxed);
+	PEAK.store(start_current, Ordering::Relaxed);
+	let result = f();
+	let usage = MemoryUsage {
+		peak: PEAK.load(Ordering::Relaxed).saturating_sub(start_current),
+		total: TOTAL.load(Ordering::Relaxed) - start_total,
+	};
+	(result, Some(usage))
+}
+
+#[cfg(not(feature = "memory"))]
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<MemoryUsage>) {
+	(f(), None)
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+	let units = ["KiB", "MiB", "GiB"];
+	if bytes < 1024 {
+		return format!("{} B", bytes);
+	}
+	let mut value = bytes as f64 / 1024.0;
+	let mut unit = 0;
+	while value >= 1024.0 && unit < units.len() - 1 {
+		value /= 1024.0;
+		unit += 1;
+	}
+	format!("{:.1} {}", value, units[unit])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn formats_bytes() {
+		assert_eq!(format_bytes(0), "0 B");
+		assert_eq!(format_bytes(1023), "1023 B");
+		assert_eq!(format_bytes(1536), "1.5 KiB");
+		assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+		assert_eq!(format_bytes(5 << 40), "5120.0 GiB");
+	}
+
+	// Other tests allocate at the same time, so only a lower bound is certain
+	#[test]
+	fn measures_allocations() {
+		let (numbers, usage) = measure(|| vec![0u64; 1000]);
+		assert_eq!(numbers.len(), 1000);
+		match usage {
+			Some(usage) => assert!(usage.total >= 8000),
+			None => assert!(!enabled()),
+		}
+	}
+}
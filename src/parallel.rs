@@ -0,0 +1,179 @@
+// Spreads work over as many threads as were asked for with `--jobs`. Results
+// always come back in the order of the items, so the answers don't depend on
+// the number of threads or on how the work got split between them.
+
+use std::{
+	cell::Cell,
+	panic,
+	sync::atomic::{AtomicUsize, Ordering},
+	thread,
+};
+
+use crate::cancel;
+
+static JOBS: AtomicUsize = AtomicUsize::new(1);
+
+thread_local! {
+	// Work started on one of the threads is done right there, otherwise running
+	// days in parallel would multiply the number of threads by the days' own
+	static IN_WORKER: Cell<bool> = const { Cell::new(false) };
+	// Lets a test pick the number of jobs without touching the global one
+	#[cfg(test)]
+	static LOCAL_JOBS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+pub fn set_jobs(jobs: usize) {
+	JOBS.store(jobs.max(1), Ordering::Relaxed);
+}
+
+pub fn jobs() -> usize {
+	#[cfg(test)]
+	if let Some(jobs) = LOCAL_JOBS.with(|local_jobs| local_jobs.get()) {
+		return jobs;
+	}
+	JOBS.load(Ordering::Relaxed)
+}
+
+pub fn map<T: Sync, U: Send>(items: &[T], f: impl Fn(&T) -> U + Sync) -> Vec<U> {
+	let jobs = if IN_WORKER.with(|in_worker| in_worker.get()) {
+		1
+	} else {
+		jobs()
+	};
+	map_with_jobs(jobs, items, f)
+}
+
+/// Items are handed out one at a time, so that a few slow ones don't hold up a
+/// whole thread's share of them
+pub fn map_with_jobs<T: Sync, U: Send>(
+	jobs: usize,
+	items: &[T],
+	f: impl Fn(&T) -> U + Sync,
+) -> Vec<U> {
+	let threads = jobs.min(items.len());
+	if threads <= 1 {
+		return items.iter().map(f).collect();
+	}
+
+	// The workers can be cancelled the same way as the thread that started them
+	let token = cancel::current().unwrap_or_default();
+	let next = AtomicUsize::new(0);
+	let mut results: Vec<(usize, U)> = thread::scope(|scope| {
+		let workers: Vec<_> = (0..threads)
+			.map(|_| {
+				scope.spawn(|| {
+					IN_WORKER.with(|in_worker| in_worker.set(true));
+					cancel::scope(&token, || {
+						let mut done = vec![];
+						loop {
+							let i = next.fetch_add(1, Ordering::Relaxed);
+							if i >= items.len() {
+								return done;
+							}
+							done.push((i, f(&items[i])));
+						}
+					})
+				})
+			})
+			.collect();
+		// A panic is passed on as is, so that its message isn't lost
+		workers
+			.into_iter()
+			.flat_map(|worker| {
+				worker
+					.join()
+					.unwrap_or_else(|payload| panic::resume_unwind(payload))
+			})
+			.collect()
+	});
+	results.sort_by_key(|(i, _)| *i);
+	results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		cancel::CancelToken,
+		generate::Rng,
+		runner::{self, catch_panic, Outcome},
+		solution::Answer,
+		SOLUTIONS,
+	};
+
+	fn with_jobs<T>(jobs: usize, f: impl FnOnce() -> T) -> T {
+		let previous = LOCAL_JOBS.with(|local_jobs| local_jobs.replace(Some(jobs)));
+		let result = f();
+		LOCAL_JOBS.with(|local_jobs| local_jobs.set(previous));
+		result
+	}
+
+	#[test]
+	fn keeps_order() {
+		let items: Vec<u64> = (0..1000).collect();
+		let squares: Vec<u64> = items.iter().map(|n| n * n).collect();
+		for jobs in [1, 2, 7, 2000] {
+			assert_eq!(map_with_jobs(jobs, &items, |n| n * n), squares);
+		}
+		assert!(map_with_jobs(4, &[] as &[u64], |n| *n).is_empty());
+	}
+
+	#[test]
+	fn nested_work_stays_on_its_thread() {
+		let outer = map_with_jobs(4, &[1, 2, 3, 4], |n| {
+			let inner = map(&[10, 20], |m| {
+				(m * n, IN_WORKER.with(|in_worker| in_worker.get()))
+			});
+			inner.iter().all(|(_, in_worker)| *in_worker)
+		});
+		assert_eq!(outer, vec![true; 4]);
+		assert!(!IN_WORKER.with(|in_worker| in_worker.get()));
+	}
+
+	// Days 17 to 20 have parallel loops in them
+	#[test]
+	fn answers_do_not_depend_on_jobs() {
+		let answers = || {
+			let mut answers: Vec<Option<Answer>> = vec![];
+			for solution in &SOLUTIONS[16..20] {
+				let input = solution.generate(&mut Rng::new(0), 8);
+				for part in runner::run_day(*solution, &input).parts {
+					answers.push(match part.outcome {
+						Outcome::Solved(answer) => Some(answer),
+						Outcome::Skipped(_) | Outcome::Failed(_) | Outcome::TimedOut(_) => None,
+					});
+				}
+			}
+			answers
+		};
+		let sequential = with_jobs(1, answers);
+		let parallel = with_jobs(4, answers);
+		assert!(sequential.iter().all(|answer| answer.is_some()));
+		assert_eq!(sequential, parallel);
+	}
+
+	#[test]
+	fn workers_can_be_cancelled() {
+		let token = CancelToken::default();
+		token.cancel();
+		let result = catch_panic(|| {
+			cancel::scope(&token, || {
+				map_with_jobs(2, &[1, 2, 3], |_| cancel::checkpoint())
+			})
+		});
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn panics_keep_their_message() {
+		let result = catch_panic(|| {
+			map_with_jobs(3, &[1, 2, 3, 4, 5], |n| {
+				if *n == 4 {
+					panic!("four is not allowed");
+				}
+				*n
+			})
+		});
+		assert_eq!(result.unwrap_err(), "four is not allowed");
+	}
+}
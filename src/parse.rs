@@ -0,0 +1,161 @@
+use std::{error::Error, fmt, str::FromStr};
+
+/// Why an input couldn't be parsed, and where
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+	pub day: u32,
+	/// Both line and column start from 1
+	pub line: usize,
+	pub column: usize,
+	pub message: String,
+}
+
+impl ParseError {
+	/// Renders the error together with the offending line, like:
+	///
+	/// ```text
+	/// error: invalid number 'x3'
+	///  --> input/1.txt:2:1
+	///   |
+	/// 2 | x3
+	///   | ^
+	/// ```
+	pub fn render(&self, filename: &str, input: &str) -> String {
+		let line = input.lines().nth(self.line - 1).unwrap_or("");
+		let gutter = " ".repeat(self.line.to_string().len());
+		format!(
+			"error: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}^",
+			self.message,
+			gutter,
+			filename,
+			self.line,
+			self.column,
+			gutter,
+			self.line,
+			line,
+			gutter,
+			" ".repeat(self.column - 1)
+		)
+	}
+}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"day {}, line {}, column {}: {}",
+			self.day, self.line, self.column, self.message
+		)
+	}
+}
+
+impl Error for ParseError {}
+
+/// The whole puzzle input of a day. Parsers only ever work with slices of it,
+/// so the position of any token can be worked out from where the slice starts.
+#[derive(Clone, Copy)]
+pub struct Source<'a> {
+	day: u32,
+	input: &'a str,
+}
+
+impl<'a> Source<'a> {
+	pub fn new(day: u32, input: &'a str) -> Source<'a> {
+		Source { day, input }
+	}
+
+	/// Empty slice at the very end of the input, for pointing out missing data
+	pub fn end(&self) -> &'a str {
+		&self.input[self.input.len()..]
+	}
+
+	pub fn error(&self, token: &str, message: impl Into<String>) -> ParseError {
+		let start = self.input.as_ptr() as usize;
+		let offset = (token.as_ptr() as usize)
+			.saturating_sub(start)
+			.min(self.input.len());
+		let before = &self.input[..offset];
+		let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+		ParseError {
+			day: self.day,
+			line: before.matches('\n').count() + 1,
+			column: before[line_start..].chars().count() + 1,
+			message: message.into(),
+		}
+	}
+
+	pub fn number<T: FromStr>(&self, token: &str) -> Result<T, ParseError> {
+		token
+			.trim()
+			.parse()
+			.map_err(|_| self.error(token, format!("invalid number '{}'", token.trim())))
+	}
+
+	pub fn split_once(
+		&self,
+		token: &'a str,
+		delimiter: &str,
+	) -> Result<(&'a str, &'a str), ParseError> {
+		token
+			.split_once(delimiter)
+			.ok_or_else(|| self.error(token, format!("expected '{}'", delimiter)))
+	}
+
+	pub fn strip_prefix(&self, token: &'a str, prefix: &str) -> Result<&'a str, ParseError> {
+		token
+			.strip_prefix(prefix)
+			.ok_or_else(|| self.error(token, format!("expected '{}'", prefix)))
+	}
+
+	pub fn digits(&self, line: &'a str) -> Result<Vec<u32>, ParseError> {
+		line
+			.char_indices()
+			.map(|(i, c)| {
+				c.to_digit(10)
+					.ok_or_else(|| self.error(&line[i..], format!("expected a digit, found '{}'", c)))
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn error_position() {
+		let input = "12\n34\nab5";
+		let source = Source::new(1, input);
+		let line = input.lines().nth(2).unwrap();
+		let error = source.error(&line[1..], "oops");
+		assert_eq!((error.line, error.column), (3, 2));
+		assert_eq!(error.to_string(), "day 1, line 3, column 2: oops");
+
+		let error = source.error(source.end(), "missing");
+		assert_eq!((error.line, error.column), (3, 4));
+	}
+
+	#[test]
+	fn parse_helpers() {
+		let input = "1,x\n42";
+		let source = Source::new(1, input);
+		let (a, b) = source
+			.split_once(input.lines().next().unwrap(), ",")
+			.unwrap();
+		assert_eq!(source.number::<u32>(a), Ok(1));
+		assert_eq!(source.number::<u32>(b).unwrap_err().column, 3);
+		assert_eq!(source.digits("42").unwrap(), vec![4, 2]);
+		assert!(source.split_once("abc", ",").is_err());
+	}
+
+	#[test]
+	fn render_error() {
+		let input = "12\nx3";
+		let source = Source::new(1, input);
+		let error = source.error(&input[3..], "invalid number 'x3'");
+		assert_eq!(
+			error.render("input/1.txt", input),
+			"error: invalid number 'x3'\n --> input/1.txt:2:1\n  |\n2 | x3\n  | ^"
+		);
+	}
+}
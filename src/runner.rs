@@ -0,0 +1,494 @@
+use std::{
+	any::Any,
+	fs,
+	panic::{self, AssertUnwindSafe},
+	time::{Duration, Instant},
+};
+
+use crate::{
+	cancel::{self, CancelToken},
+	input,
+	json::Json,
+	memory::{self, MemoryUsage},
+	parallel,
+	parse::ParseError,
+	solution::{Answer, Runner},
+};
+
+/// How the answers get printed
+pub enum OutputFormat {
+	Text,
+	Json,
+}
+
+/// What came out of running a part
+pub enum Outcome {
+	Solved(Answer),
+	/// The part panicked, isn't there or its input didn't parse
+	Skipped(String),
+	/// The part returned an error, like for an input that has no answer
+	Failed(String),
+	/// Took longer than the `--timeout`
+	TimedOut(Duration),
+}
+
+/// The outcome of a part and what it took to get it
+pub struct PartReport {
+	pub part: u32,
+	pub outcome: Outcome,
+	pub solve_time: Duration,
+	/// Only known with the `memory` feature
+	pub memory: Option<MemoryUsage>,
+}
+
+/// Everything about running both parts of a day
+pub struct DayReport {
+	pub day: u32,
+	pub parse_time: Duration,
+	pub parse_memory: Option<MemoryUsage>,
+	pub parts: Vec<PartReport>,
+	/// Kept around so that the error can be shown together with the input
+	pub parse_error: Option<ParseError>,
+}
+
+/// Runs the given closure, turning a panic (like a `todo!()`) into an error
+/// message instead of bringing down the whole runner.
+pub fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+	panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+		if let Some(message) = payload.downcast_ref::<&str>() {
+			message.to_string()
+		} else if let Some(message) = payload.downcast_ref::<String>() {
+			message.clone()
+		} else {
+			"panicked".into()
+		}
+	})
+}
+
+fn skip_parts(day: u32, parts: &[u32], reason: &str) -> DayReport {
+	let parts = parts
+		.iter()
+		.map(|part| PartReport {
+			part: *part,
+			outcome: Outcome::Skipped(reason.into()),
+			solve_time: Duration::ZERO,
+			memory: None,
+		})
+		.collect();
+	DayReport {
+		day,
+		parse_time: Duration::ZERO,
+		parse_memory: None,
+		parts,
+		parse_error: None,
+	}
+}
+
+pub fn run_day(solution: &dyn Runner, contents: &str) -> DayReport {
+	run_parts(solution, contents, &[1, 2])
+}
+
+pub fn run_parts(solution: &dyn Runner, contents: &str, parts: &[u32]) -> DayReport {
+	run_parts_with(solution, contents, parts, &[])
+}
+
+// Solvers that are still running after `timeout` are stopped at their next
+// `cancel::checkpoint`
+fn solve_part(
+	solution: &dyn Runner,
+	input: &dyn Any,
+	part: u32,
+	settings: &[(String, String)],
+	timeout: Option<Duration>,
+) -> PartReport {
+	let token = CancelToken::with_timeout(timeout);
+	let solve_start = Instant::now();
+	let (answer, memory) = memory::measure(|| {
+		catch_panic(|| cancel::scope(&token, || solution.solve_with(input, part, settings)))
+	});
+	let solve_time = solve_start.elapsed();
+	let outcome = match (answer, timeout) {
+		(Ok(Ok(answer)), _) => Outcome::Solved(answer),
+		(Err(_), Some(timeout)) if token.is_cancelled() => Outcome::TimedOut(timeout),
+		(Ok(Err(e)), _) => Outcome::Failed(e),
+		(Err(e), _) => Outcome::Skipped(e),
+	};
+	PartReport {
+		part,
+		outcome,
+		solve_time,
+		memory,
+	}
+}
+
+/// Same as `run_parts`, with some of the puzzle parameters changed. A parameter
+/// that the day doesn't have fails the part. Every part gets its own timeout.
+pub fn run_parts_with(
+	solution: &dyn Runner,
+	contents: &str,
+	parts: &[u32],
+	settings: &[(String, String)],
+) -> DayReport {
+	let day = solution.day();
+	let part_numbers = parts;
+
+	let parse_start = Instant::now();
+	let (parsed, parse_memory) = memory::measure(|| catch_panic(|| solution.parse(contents)));
+	let parse_time = parse_start.elapsed();
+	let input = match parsed {
+		Ok(Ok(input)) => input,
+		Ok(Err(e)) => {
+			let mut report = skip_parts(day, part_numbers, &format!("parse error: {}", e));
+			report.parse_error = Some(e);
+			return report;
+		}
+		Err(e) => return skip_parts(day, part_numbers, &format!("parse error: {}", e)),
+	};
+
+	let parts = part_numbers
+		.iter()
+		.map(|&part| solve_part(solution, input.as_ref(), part, settings, cancel::timeout()))
+		.collect();
+
+	DayReport {
+		day,
+		parse_time,
+		parse_memory,
+		parts,
+		parse_error: None,
+	}
+}
+
+/// Caught panics get reported by the caller, so don't let the default hook
+/// spam stderr with them.
+pub fn with_silent_panics<T>(f: impl FnOnce() -> T) -> T {
+	let default_hook = panic::take_hook();
+	panic::set_hook(Box::new(|_| {}));
+	let result = f();
+	panic::set_hook(default_hook);
+	result
+}
+
+/// Days run in parallel when there is more than one job, the timings of each
+/// day are then affected by whatever else is running at the same time. Memory
+/// is counted for the whole process, so when it is counted the days run one at
+/// a time.
+pub fn run_all(solutions: &[&dyn Runner]) -> Vec<DayReport> {
+	let jobs = if memory::enabled() {
+		1
+	} else {
+		parallel::jobs()
+	};
+	with_silent_panics(|| {
+		parallel::map_with_jobs(jobs, solutions, |solution| {
+			let input_filename = input::default_path(solution.year(), solution.day());
+			match fs::read_to_string(&input_filename) {
+				Ok(contents) => run_day(*solution, &contents),
+				Err(_) => skip_parts(solution.day(), &[1, 2], "missing input"),
+			}
+		})
+	})
+}
+
+pub fn format_duration(duration: Duration) -> String {
+	format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
+
+pub fn format_timeout(timeout: Duration) -> String {
+	format!("{}s", timeout.as_secs_f64())
+}
+
+fn format_outcome(outcome: &Outcome) -> String {
+	match outcome {
+		Outcome::Solved(Answer::Rendering(rendering)) => {
+			format!("<{} lines>", rendering.trim_end().lines().count())
+		}
+		Outcome::Solved(answer) => answer.to_string(),
+		Outcome::Skipped(reason) => format!("skipped ({})", reason),
+		Outcome::Failed(reason) => format!("failed ({})", reason),
+		Outcome::TimedOut(timeout) => format!("timed out after {}", format_timeout(*timeout)),
+	}
+}
+
+fn format_memory(usage: Option<MemoryUsage>) -> (String, String) {
+	match usage {
+		Some(usage) => (
+			memory::format_bytes(usage.peak),
+			memory::format_bytes(usage.total),
+		),
+		None => (String::new(), String::new()),
+	}
+}
+
+pub fn print_summary(reports: &[DayReport]) {
+	let show_memory = memory::enabled();
+	print!(
+		"{:>3} {:>4}  {:<40} {:>12} {:>12}",
+		"Day", "Part", "Answer", "Parse", "Solve"
+	);
+	if show_memory {
+		print!(
+			" {:>11} {:>11} {:>11} {:>11}",
+			"Parse peak", "Parse alloc", "Solve peak", "Solve alloc"
+		);
+	}
+	println!();
+
+	let mut total_parse = Duration::ZERO;
+	let mut total_solve = Duration::ZERO;
+	let mut solved = 0;
+	let mut skipped = 0;
+	let mut failed = 0;
+	let mut timed_out = 0;
+	for report in reports {
+		total_parse += report.parse_time;
+		for part in &report.parts {
+			total_solve += part.solve_time;
+			match part.outcome {
+				Outcome::Solved(_) => solved += 1,
+				Outcome::Skipped(_) => skipped += 1,
+				Outcome::Failed(_) => failed += 1,
+				Outcome::TimedOut(_) => timed_out += 1,
+			}
+
+			// Parsing happens once per day, so only show it on the first part
+			let (parse_time, parse_memory) = if part.part == 1 {
+				(format_duration(report.parse_time), report.parse_memory)
+			} else {
+				(String::new(), None)
+			};
+			print!(
+				"{:>3} {:>4}  {:<40} {:>12} {:>12}",
+				report.day,
+				part.part,
+				format_outcome(&part.outcome),
+				parse_time,
+				format_duration(part.solve_time)
+			);
+			if show_memory {
+				let (parse_peak, parse_total) = format_memory(parse_memory);
+				let (solve_peak, solve_total) = format_memory(part.memory);
+				print!(
+					" {:>11} {:>11} {:>11} {:>11}",
+					parse_peak, parse_total, solve_peak, solve_total
+				);
+			}
+			println!();
+		}
+	}
+
+	let mut total = format!("Total ({} solved, {} skipped", solved, skipped);
+	if failed > 0 {
+		total += &format!(", {} failed", failed);
+	}
+	if timed_out > 0 {
+		total += &format!(", {} timed out", timed_out);
+	}
+	println!(
+		"{:<49} {:>12} {:>12}",
+		total + ")",
+		format_duration(total_parse),
+		format_duration(total_solve)
+	);
+}
+
+fn duration_ms(duration: Duration) -> Json {
+	Json::number(duration.as_secs_f64() * 1000.0)
+}
+
+fn part_fields(report: &DayReport, part: &PartReport) -> Vec<(&'static str, Json)> {
+	let (answer, kind, error) = match &part.outcome {
+		Outcome::Solved(answer) => {
+			let value = match answer {
+				Answer::Integer(value) => Json::number(value),
+				Answer::Unsigned(value) => Json::number(value),
+				Answer::Text(value) | Answer::Rendering(value) => Json::string(value.as_str()),
+			};
+			(value, Json::string(answer.kind()), Json::Null)
+		}
+		Outcome::Skipped(reason) | Outcome::Failed(reason) => {
+			(Json::Null, Json::Null, Json::string(reason.as_str()))
+		}
+		Outcome::TimedOut(timeout) => {
+			let error = format!("timed out after {}", format_timeout(*timeout));
+			(Json::Null, Json::Null, Json::string(error))
+		}
+	};
+	let mut fields = vec![
+		("day", Json::number(report.day)),
+		("part", Json::number(part.part)),
+		("answer", answer),
+		("answer_kind", kind),
+		("parse_ms", duration_ms(report.parse_time)),
+		("solve_ms", duration_ms(part.solve_time)),
+		("error", error),
+	];
+	// Only there with the `memory` feature
+	if let Some(usage) = report.parse_memory {
+		fields.push(("parse_peak_bytes", Json::number(usage.peak)));
+		fields.push(("parse_allocated_bytes", Json::number(usage.total)));
+	}
+	if let Some(usage) = part.memory {
+		fields.push(("solve_peak_bytes", Json::number(usage.peak)));
+		fields.push(("solve_allocated_bytes", Json::number(usage.total)));
+	}
+	fields
+}
+
+pub fn part_to_json(report: &DayReport, part: &PartReport) -> Json {
+	Json::Object(part_fields(report, part))
+}
+
+/// Prints one JSON object per line, so that scripts can process the results
+/// as they come in.
+pub fn print_json(reports: &[DayReport]) {
+	for report in reports {
+		for part in &report.parts {
+			println!("{}", part_to_json(report, part));
+		}
+	}
+}
+
+/// Same as `print_json`, but also says which input the answers belong to
+pub fn print_input_json(input_name: &str, report: &DayReport) {
+	for part in &report.parts {
+		let mut fields = vec![("input", Json::string(input_name))];
+		fields.extend(part_fields(report, part));
+		println!("{}", Json::Object(fields));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{day1::Day1, day25::Day25, day6::Day6};
+
+	#[test]
+	fn run_day_reports_answers() {
+		let report = run_day(&Day1, "199\n200\n208\n210\n200\n207\n240\n269\n260\n263");
+		assert_eq!(report.day, 1);
+		assert_eq!(report.parts.len(), 2);
+		assert!(matches!(
+			report.parts[0].outcome,
+			Outcome::Solved(Answer::Integer(7))
+		));
+		assert!(matches!(
+			report.parts[1].outcome,
+			Outcome::Solved(Answer::Integer(5))
+		));
+	}
+
+	#[test]
+	fn run_day_skips_unimplemented_part() {
+		let report = run_day(&Day25, "v>\n>v");
+		assert!(matches!(report.parts[0].outcome, Outcome::Solved(_)));
+		assert!(matches!(report.parts[1].outcome, Outcome::Skipped(_)));
+	}
+
+	#[test]
+	fn run_parts_with_params() {
+		let settings = [("days".to_string(), "18".to_string())];
+		let report = run_parts_with(&Day6, "3,4,3,1,2", &[1, 2], &settings);
+		assert!(matches!(
+			report.parts[0].outcome,
+			Outcome::Solved(Answer::Integer(26))
+		));
+		assert!(matches!(
+			report.parts[1].outcome,
+			Outcome::Solved(Answer::Integer(26))
+		));
+
+		let settings = [
+			("days".to_string(), "18".to_string()),
+			("size".to_string(), "3".to_string()),
+		];
+		let report = run_parts_with(&Day6, "3,4,3,1,2", &[1], &settings);
+		match &report.parts[0].outcome {
+			Outcome::Failed(reason) => {
+				assert_eq!(reason, "unknown parameter 'size', expected one of: days")
+			}
+			_ => panic!("Expected the unknown parameter to fail the part"),
+		}
+
+		let report = run_parts_with(&Day1, "199\n200", &[1], &settings);
+		assert!(matches!(report.parts[0].outcome, Outcome::Failed(_)));
+	}
+
+	// The east-facing sea cucumber goes around in circles forever
+	#[test]
+	fn endless_part_times_out() {
+		let input = Day25.parse(">.").unwrap();
+		let timeout = Some(Duration::from_millis(50));
+		let report = solve_part(&Day25, input.as_ref(), 1, &[], timeout);
+		assert!(matches!(report.outcome, Outcome::TimedOut(_)));
+		assert!(report.solve_time >= Duration::from_millis(50));
+		assert_eq!(format_outcome(&report.outcome), "timed out after 0.05s");
+
+		let input = Day1.parse("199\n200").unwrap();
+		let report = solve_part(&Day1, input.as_ref(), 1, &[], timeout);
+		assert!(matches!(
+			report.outcome,
+			Outcome::Solved(Answer::Integer(1))
+		));
+	}
+
+	#[test]
+	fn run_day_skips_on_parse_error() {
+		let report = run_day(&Day1, "1\nnot a number");
+		assert!(report
+			.parts
+			.iter()
+			.all(|p| matches!(p.outcome, Outcome::Skipped(_))));
+		let error = report.parse_error.unwrap();
+		assert_eq!((error.day, error.line, error.column), (1, 2, 1));
+	}
+
+	#[test]
+	fn part_json() {
+		let day_report = |day, parse_time, parse_memory| DayReport {
+			day,
+			parse_time,
+			parse_memory,
+			parts: vec![],
+			parse_error: None,
+		};
+		let part = PartReport {
+			part: 2,
+			outcome: Outcome::Solved(Answer::Rendering("#.\n".into())),
+			solve_time: Duration::from_millis(2),
+			memory: None,
+		};
+		assert_eq!(
+			part_to_json(&day_report(13, Duration::from_millis(1), None), &part).to_string(),
+			r##"{"day":13,"part":2,"answer":"#.\n","answer_kind":"rendering","parse_ms":1,"solve_ms":2,"error":null}"##
+		);
+
+		let part = PartReport {
+			part: 2,
+			outcome: Outcome::Skipped("not yet implemented".into()),
+			solve_time: Duration::ZERO,
+			memory: None,
+		};
+		assert_eq!(
+			part_to_json(&day_report(25, Duration::ZERO, None), &part).to_string(),
+			r#"{"day":25,"part":2,"answer":null,"answer_kind":null,"parse_ms":0,"solve_ms":0,"error":"not yet implemented"}"#
+		);
+
+		let part = PartReport {
+			part: 1,
+			outcome: Outcome::Solved(Answer::Integer(7)),
+			solve_time: Duration::ZERO,
+			memory: Some(MemoryUsage {
+				peak: 16,
+				total: 48,
+			}),
+		};
+		let usage = Some(MemoryUsage {
+			peak: 1024,
+			total: 2048,
+		});
+		assert!(part_to_json(&day_report(1, Duration::ZERO, usage), &part).to_string().ends_with(
+			r#""error":null,"parse_peak_bytes":1024,"parse_allocated_bytes":2048,"solve_peak_bytes":16,"solve_allocated_bytes":48}"#
+		));
+	}
+}
@@ -0,0 +1,449 @@
+// Adds a new day to the crate: writes its module from a template, registers it
+// in the list of solutions and makes an empty input file for it. Days of other
+// years than the crate's own go into a `yearNNNN` module, which gets made and
+// added to `YEARS` the first time a day of that year is added.
+
+use std::{
+	fs, io,
+	path::{Path, PathBuf},
+};
+
+use crate::YEAR;
+
+fn year_module_name(year: u32) -> String {
+	format!("year{}", year)
+}
+
+pub fn day_module(year: u32, day: u32) -> String {
+	let year_const = if year == YEAR {
+		String::new()
+	} else {
+		format!("\tconst YEAR: u32 = {};\n", year)
+	};
+	format!(
+		r#"//! Day {day}: TODO
+//!
+//! TODO: describe the puzzle.
+
+use crate::{{
+	generate::Rng,
+	parse::{{ParseError, Source}},
+	solution::Solution,
+}};
+
+pub fn parse_input(input: &str) -> Result<Vec<u32>, ParseError> {{
+	let source = Source::new(Day{day}::DAY, input);
+	input.split_whitespace().map(|s| source.number(s)).collect()
+}}
+
+pub fn part1(_numbers: &[u32]) -> u32 {{
+	todo!();
+}}
+
+pub fn part2(_numbers: &[u32]) -> u32 {{
+	todo!();
+}}
+
+pub fn generate_input(_rng: &mut Rng, _size: usize) -> String {{
+	todo!();
+}}
+
+pub struct Day{day};
+
+impl Solution for Day{day} {{
+	const DAY: u32 = {day};
+{year_const}
+	type Input = Vec<u32>;
+	type Output1 = u32;
+	type Output2 = u32;
+	type Params = ();
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError> {{
+		parse_input(input)
+	}}
+
+	fn part1(input: &Self::Input, _params: &Self::Params) -> Self::Output1 {{
+		part1(input)
+	}}
+
+	fn part2(input: &Self::Input, _params: &Self::Params) -> Self::Output2 {{
+		part2(input)
+	}}
+
+	fn generate(rng: &mut Rng, size: usize) -> String {{
+		generate_input(rng, size)
+	}}
+}}
+
+#[cfg(test)]
+mod tests {{
+	use super::*;
+
+	const EXAMPLE: &str = "";
+
+	#[test]
+	#[ignore = "not solved yet"]
+	fn part1_example() {{
+		let input = parse_input(EXAMPLE).unwrap();
+		assert_eq!(part1(&input), 0);
+	}}
+
+	#[test]
+	#[ignore = "not solved yet"]
+	fn part2_example() {{
+		let input = parse_input(EXAMPLE).unwrap();
+		assert_eq!(part2(&input), 0);
+	}}
+}}
+"#,
+		day = day,
+		year_const = year_const
+	)
+}
+
+pub fn year_module(year: u32) -> String {
+	format!(
+		r#"//! Solutions to [Advent of Code {year}](https://adventofcode.com/{year}).
+
+use crate::solution::Runner;
+
+/// Every day, in order
+pub static SOLUTIONS: [&dyn Runner; 0] = [];
+"#,
+		year = year
+	)
+}
+
+// Adds `pub mod <name>;` among the modules with a `key`, so that they stay in
+// order. The first one goes after the other modules, or before the first `use`
+// if there are none.
+fn add_module(
+	source: &str,
+	name: &str,
+	key: impl Fn(&str) -> Option<u32>,
+	new_key: u32,
+) -> Result<String, String> {
+	let mut lines: Vec<&str> = source.lines().collect();
+	let line = format!("pub mod {};", name);
+	if lines.contains(&line.as_str()) {
+		return Err(format!("`{}` is already there", line));
+	}
+
+	let modules: Vec<(usize, Option<u32>)> = lines
+		.iter()
+		.enumerate()
+		.filter_map(|(i, line)| {
+			let name = line.strip_prefix("pub mod ")?.strip_suffix(';')?;
+			Some((i, key(name)))
+		})
+		.collect();
+	let before = modules
+		.iter()
+		.rev()
+		.find(|(_, key)| matches!(key, Some(key) if *key < new_key));
+	let after = modules.iter().find(|(_, key)| key.is_some());
+	let index = match (before, after, modules.last()) {
+		(Some((i, _)), _, _) => i + 1,
+		(None, Some((i, _)), _) => *i,
+		(None, None, Some((i, _))) => i + 1,
+		(None, None, None) => {
+			let first_use = lines
+				.iter()
+				.position(|line| line.starts_with("use "))
+				.unwrap_or(0);
+			lines.insert(first_use, "");
+			first_use
+		}
+	};
+	lines.insert(index, &line);
+	Ok(lines.join("\n") + "\n")
+}
+
+// Splits the entries of an array on the commas that aren't inside of one of
+// them, so that it doesn't matter how many of them are on a line
+fn split_entries(body: &str) -> Vec<&str> {
+	let mut entries = vec![];
+	let mut depth = 0;
+	let mut start = 0;
+	for (i, c) in body.char_indices() {
+		match c {
+			'(' | '[' => depth += 1,
+			')' | ']' => depth -= 1,
+			',' if depth == 0 => {
+				entries.push(body[start..i].trim());
+				start = i + 1;
+			}
+			_ => {}
+		}
+	}
+	entries.push(body[start..].trim());
+	entries.retain(|entry| !entry.is_empty());
+	entries
+}
+
+// Adds an entry to a static array, keeping the entries sorted by `key` and its
+// length up to date. The array is written back with one entry per line.
+fn add_entry(
+	source: &str,
+	name: &str,
+	entry: &str,
+	key: impl Fn(&str) -> u32,
+) -> Result<String, String> {
+	let header = format!("pub static {}: [", name);
+	let start = source
+		.find(&header)
+		.ok_or_else(|| format!("couldn't find the `{}` list", name))?;
+	// Looks like `pub static NAME: [<type>; <length>] = [<entries>];`
+	let type_end = source[start..].find("; ").map(|i| start + i);
+	let body_start = source[start..].find("= [").map(|i| start + i + 3);
+	let body_end =
+		body_start.and_then(|body_start| source[body_start..].find("];").map(|i| body_start + i));
+	let (type_end, body_start, body_end) = match (type_end, body_start, body_end) {
+		(Some(type_end), Some(body_start), Some(body_end)) => (type_end, body_start, body_end),
+		_ => return Err(format!("couldn't make sense of the `{}` list", name)),
+	};
+	let element_type = &source[start + header.len()..type_end];
+
+	let mut entries = split_entries(&source[body_start..body_end]);
+	if entries.contains(&entry) {
+		return Err(format!("`{}` is already in `{}`", entry, name));
+	}
+	entries.push(entry);
+	entries.sort_by_key(|entry| key(entry));
+
+	let mut list = format!("{}{}; {}] = [\n", header, element_type, entries.len());
+	for entry in entries {
+		list += &format!("\t{},\n", entry);
+	}
+	Ok(format!(
+		"{}{}{}",
+		&source[..start],
+		list,
+		&source[body_end..]
+	))
+}
+
+fn number_after(prefix: &str, text: &str) -> Option<u32> {
+	let rest = text.strip_prefix(prefix)?;
+	let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+	digits.parse().ok()
+}
+
+/// Registers the day in the source of the module that has the year's days
+pub fn register_day(source: &str, day: u32) -> Result<String, String> {
+	let source = add_module(
+		source,
+		&format!("day{}", day),
+		|name| number_after("day", name),
+		day,
+	)?;
+	add_entry(
+		&source,
+		"SOLUTIONS",
+		&format!("&day{}::Day{}", day, day),
+		|entry| number_after("&day", entry).unwrap_or(0),
+	)
+}
+
+/// Registers a year module in the source of `lib.rs`
+pub fn register_year(source: &str, year: u32) -> Result<String, String> {
+	let name = year_module_name(year);
+	let source = add_module(source, &name, |name| number_after("year", name), year)?;
+	add_entry(
+		&source,
+		"YEARS",
+		&format!("({}, &{}::SOLUTIONS)", year, name),
+		|entry| {
+			match number_after("(", entry) {
+				Some(year) => year,
+				// `YEAR` itself
+				None => YEAR,
+			}
+		},
+	)
+}
+
+fn create_new(path: &Path, contents: &str) -> io::Result<()> {
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+	fs::OpenOptions::new()
+		.write(true)
+		.create_new(true)
+		.open(path)
+		.and_then(|mut file| io::Write::write_all(&mut file, contents.as_bytes()))
+}
+
+fn update(path: &Path, f: impl FnOnce(&str) -> Result<String, String>) -> io::Result<()> {
+	let source = fs::read_to_string(path)?;
+	let updated = f(&source).map_err(|e| {
+		io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("{}: {}", path.display(), e),
+		)
+	})?;
+	fs::write(path, updated)
+}
+
+// Takes back a file made before `error` happened
+fn remove(path: &Path, error: io::Error) -> io::Error {
+	let _ = fs::remove_file(path);
+	error
+}
+
+/// Does everything in the crate at `root`, returns the files that it made or
+/// changed. An existing input file is left as it is.
+pub fn new_day(root: &Path, year: u32, day: u32) -> io::Result<Vec<PathBuf>> {
+	if !(1..=25).contains(&day) {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			format!("There is no day {}", day),
+		));
+	}
+	let lib = root.join("src").join("lib.rs");
+	if !lib.is_file() {
+		return Err(io::Error::new(
+			io::ErrorKind::NotFound,
+			format!(
+				"'{}' not found, run this from the root of the crate",
+				lib.display()
+			),
+		));
+	}
+
+	let mut changed = vec![];
+	let module_dir = if year == YEAR {
+		root.join("src")
+	} else {
+		root.join("src").join(year_module_name(year))
+	};
+	let registry = if year == YEAR {
+		lib.clone()
+	} else {
+		let registry = module_dir.join("mod.rs");
+		if !registry.exists() {
+			create_new(&registry, &year_module(year))?;
+			update(&lib, |source| register_year(source, year)).map_err(|e| remove(&registry, e))?;
+			changed.push(registry.clone());
+			changed.push(lib.clone());
+		}
+		registry
+	};
+
+	// The module is made first, so that the registry never points to a module
+	// that isn't there
+	let module = module_dir.join(format!("day{}.rs", day));
+	create_new(&module, &day_module(year, day)).map_err(|e| match e.kind() {
+		io::ErrorKind::AlreadyExists => io::Error::new(
+			io::ErrorKind::AlreadyExists,
+			format!("'{}' already exists", module.display()),
+		),
+		_ => e,
+	})?;
+	update(&registry, |source| register_day(source, day)).map_err(|e| remove(&module, e))?;
+	changed.push(module);
+	if !changed.contains(&registry) {
+		changed.push(registry);
+	}
+
+	let input = root.join(crate::input::default_path(year, day));
+	if !input.exists() {
+		create_new(&input, "")?;
+		changed.push(input);
+	}
+	Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn day_template() {
+		let module = day_module(2022, 5);
+		assert!(module.starts_with("//! Day 5: TODO\n"));
+		assert!(module.contains(
+			"impl Solution for Day5 {\n\tconst DAY: u32 = 5;\n\tconst YEAR: u32 = 2022;\n\n\ttype Input"
+		));
+		assert!(!day_module(YEAR, 5).contains("const YEAR"));
+	}
+
+	#[test]
+	fn registers_days_in_order() {
+		let source = "pub mod day1;\npub mod day3;\n\npub mod grid;\n\nuse solution::Runner;\n\npub static SOLUTIONS: [&dyn Runner; 2] = [\n\t&day1::Day1,\n\t&day3::Day3,\n];\n";
+		let registered = register_day(source, 2).unwrap();
+		assert_eq!(
+			registered,
+			"pub mod day1;\npub mod day2;\npub mod day3;\n\npub mod grid;\n\nuse solution::Runner;\n\npub static SOLUTIONS: [&dyn Runner; 3] = [\n\t&day1::Day1,\n\t&day2::Day2,\n\t&day3::Day3,\n];\n"
+		);
+		assert!(register_day(&registered, 2).is_err());
+
+		let registered = register_day(&year_module(2022), 1).unwrap();
+		assert!(registered.contains("\npub mod day1;\n\nuse crate::solution::Runner;\n"));
+		assert!(
+			registered.ends_with("pub static SOLUTIONS: [&dyn Runner; 1] = [\n\t&day1::Day1,\n];\n")
+		);
+	}
+
+	// Laid out like `lib.rs`, with the lists on one line the way rustfmt writes
+	// them when they are short
+	const LIB: &str = "//! Docs\n\npub mod day1;\npub mod day2;\n\npub mod solution;\npub mod visualize;\n\nuse solution::Runner;\n\npub const YEAR: u32 = 2021;\n\npub static SOLUTIONS: [&dyn Runner; 2] = [&day1::Day1, &day2::Day2];\n\npub static YEARS: [(u32, &[&dyn Runner]); 1] = [(YEAR, &SOLUTIONS)];\n";
+
+	#[test]
+	fn splits_entries() {
+		assert_eq!(
+			split_entries(" (2015, &year2015::SOLUTIONS), (YEAR, &SOLUTIONS)"),
+			vec!["(2015, &year2015::SOLUTIONS)", "(YEAR, &SOLUTIONS)"]
+		);
+		assert_eq!(split_entries("\n\t&day1::Day1,\n"), vec!["&day1::Day1"]);
+		assert!(split_entries("").is_empty());
+	}
+
+	#[test]
+	fn registers_years() {
+		let lib = register_year(LIB, 2022).unwrap();
+		assert!(lib.contains("pub mod visualize;\npub mod year2022;\n"));
+		let lib = register_year(&lib, 2015).unwrap();
+		assert!(lib.contains("pub mod visualize;\npub mod year2015;\npub mod year2022;\n"));
+		assert!(lib.contains(
+			"pub static YEARS: [(u32, &[&dyn Runner]); 3] = [\n\t(2015, &year2015::SOLUTIONS),\n\t(YEAR, &SOLUTIONS),\n\t(2022, &year2022::SOLUTIONS),\n];\n"
+		));
+	}
+
+	#[test]
+	fn adds_a_day_to_a_new_year() {
+		let root = std::env::temp_dir().join(format!("aoc-scaffold-test-{}", std::process::id()));
+		fs::create_dir_all(root.join("src")).unwrap();
+		fs::write(root.join("src/lib.rs"), LIB).unwrap();
+
+		let changed = new_day(&root, 2022, 1).unwrap();
+		assert_eq!(changed.len(), 4);
+		assert!(root.join("src/year2022/day1.rs").is_file());
+		assert!(root.join("input/2022/1.txt").is_file());
+		let registry = fs::read_to_string(root.join("src/year2022/mod.rs")).unwrap();
+		assert!(registry.contains("&day1::Day1,"));
+
+		// The year is only made once
+		assert_eq!(new_day(&root, 2022, 2).unwrap().len(), 3);
+		assert_eq!(
+			new_day(&root, 2022, 1).unwrap_err().kind(),
+			io::ErrorKind::AlreadyExists
+		);
+		assert_eq!(
+			new_day(&root, 2022, 26).unwrap_err().kind(),
+			io::ErrorKind::InvalidInput
+		);
+		let lib = fs::read_to_string(root.join("src/lib.rs")).unwrap();
+		assert_eq!(lib.matches("pub mod year2022;").count(), 1);
+
+		// A registry that can't be updated leaves no module behind
+		fs::write(root.join("src/year2022/mod.rs"), "// nothing here\n").unwrap();
+		assert_eq!(
+			new_day(&root, 2022, 3).unwrap_err().kind(),
+			io::ErrorKind::InvalidData
+		);
+		assert!(!root.join("src/year2022/day3.rs").exists());
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+}
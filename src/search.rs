@@ -0,0 +1,238 @@
+use priority_queue::PriorityQueue;
+use std::{
+	cmp::Reverse,
+	collections::{hash_map::Entry, HashMap, VecDeque},
+	hash::Hash,
+};
+
+use crate::cancel;
+
+/// How much work a search did
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SearchStats {
+	/// How many states had their successors looked at
+	pub expanded: usize,
+	pub max_frontier: usize,
+}
+
+/// The cheapest path found, and what it took to find it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult<S> {
+	pub cost: u32,
+	/// Every state from the start to the goal, both included
+	pub path: Vec<S>,
+	pub stats: SearchStats,
+}
+
+struct Node<S> {
+	state: S,
+	cost: u32,
+	parent: Option<usize>,
+}
+
+// States are stored once and referred to by their index everywhere else, so
+// that they don't have to be cloned for every lookup.
+struct Nodes<S> {
+	nodes: Vec<Node<S>>,
+	indices: HashMap<S, usize>,
+}
+
+impl<S: Clone + Eq + Hash> Nodes<S> {
+	fn new() -> Nodes<S> {
+		Nodes {
+			nodes: Vec::new(),
+			indices: HashMap::new(),
+		}
+	}
+
+	// Returns the index of the state and whether it was seen for the first time
+	fn insert(&mut self, state: S, cost: u32, parent: Option<usize>) -> (usize, bool) {
+		match self.indices.entry(state) {
+			Entry::Occupied(entry) => (*entry.get(), false),
+			Entry::Vacant(entry) => {
+				let index = self.nodes.len();
+				self.nodes.push(Node {
+					state: entry.key().clone(),
+					cost,
+					parent,
+				});
+				entry.insert(index);
+				(index, true)
+			}
+		}
+	}
+
+	fn path_to(&self, mut index: usize) -> Vec<S> {
+		let mut path = vec![self.nodes[index].state.clone()];
+		while let Some(parent) = self.nodes[index].parent {
+			path.push(self.nodes[parent].state.clone());
+			index = parent;
+		}
+		path.reverse();
+		path
+	}
+}
+
+/// Finds the cheapest path from `start` to a state for which `is_goal` holds.
+/// `successors` gives the neighbouring states together with the cost of moving
+/// to them. `heuristic` has to never overestimate the remaining cost, otherwise
+/// the found path might not be the cheapest one.
+pub fn astar<S, I>(
+	start: S,
+	mut successors: impl FnMut(&S) -> I,
+	mut heuristic: impl FnMut(&S) -> u32,
+	mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<SearchResult<S>>
+where
+	S: Clone + Eq + Hash,
+	I: IntoIterator<Item = (S, u32)>,
+{
+	let mut stats = SearchStats::default();
+	let mut nodes = Nodes::new();
+	let mut frontier = PriorityQueue::new();
+
+	let start_priority = heuristic(&start);
+	let (start, _) = nodes.insert(start, 0, None);
+	frontier.push(start, Reverse(start_priority));
+	stats.max_frontier = 1;
+
+	while let Some((index, _)) = frontier.pop() {
+		cancel::checkpoint();
+		if is_goal(&nodes.nodes[index].state) {
+			return Some(SearchResult {
+				cost: nodes.nodes[index].cost,
+				path: nodes.path_to(index),
+				stats,
+			});
+		}
+		stats.expanded += 1;
+
+		let cost = nodes.nodes[index].cost;
+		for (next_state, step_cost) in successors(&nodes.nodes[index].state) {
+			let next_cost = cost + step_cost;
+			let estimate = heuristic(&next_state);
+			let (next, is_new) = nodes.insert(next_state, next_cost, Some(index));
+			if !is_new {
+				if next_cost >= nodes.nodes[next].cost {
+					continue;
+				}
+				nodes.nodes[next].cost = next_cost;
+				nodes.nodes[next].parent = Some(index);
+			}
+			// Only changes the priority if it is now cheaper to get there
+			frontier.push_increase(next, Reverse(next_cost + estimate));
+		}
+		stats.max_frontier = stats.max_frontier.max(frontier.len());
+	}
+
+	None
+}
+
+/// A* without any knowledge about how far away the goal is
+pub fn dijkstra<S, I>(
+	start: S,
+	successors: impl FnMut(&S) -> I,
+	is_goal: impl FnMut(&S) -> bool,
+) -> Option<SearchResult<S>>
+where
+	S: Clone + Eq + Hash,
+	I: IntoIterator<Item = (S, u32)>,
+{
+	astar(start, successors, |_| 0, is_goal)
+}
+
+/// For when every step costs the same, the cost is the number of steps taken
+pub fn bfs<S, I>(
+	start: S,
+	mut successors: impl FnMut(&S) -> I,
+	mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<SearchResult<S>>
+where
+	S: Clone + Eq + Hash,
+	I: IntoIterator<Item = S>,
+{
+	let mut stats = SearchStats::default();
+	let mut nodes = Nodes::new();
+	let mut frontier = VecDeque::new();
+
+	let (start, _) = nodes.insert(start, 0, None);
+	frontier.push_back(start);
+	stats.max_frontier = 1;
+
+	while let Some(index) = frontier.pop_front() {
+		cancel::checkpoint();
+		if is_goal(&nodes.nodes[index].state) {
+			return Some(SearchResult {
+				cost: nodes.nodes[index].cost,
+				path: nodes.path_to(index),
+				stats,
+			});
+		}
+		stats.expanded += 1;
+
+		let cost = nodes.nodes[index].cost;
+		for next_state in successors(&nodes.nodes[index].state) {
+			let (next, is_new) = nodes.insert(next_state, cost + 1, Some(index));
+			if is_new {
+				frontier.push_back(next);
+			}
+		}
+		stats.max_frontier = stats.max_frontier.max(frontier.len());
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// 0 -1-> 1 -1-> 2 -1-> 3
+	//  \------- 5 -------/
+	fn successors(node: &u32) -> Vec<(u32, u32)> {
+		match node {
+			0 => vec![(1, 1), (3, 5)],
+			1 => vec![(2, 1)],
+			2 => vec![(3, 1)],
+			_ => vec![],
+		}
+	}
+
+	#[test]
+	fn dijkstra_finds_cheapest_path() {
+		let result = dijkstra(0, successors, |node| *node == 3).unwrap();
+		assert_eq!(result.cost, 3);
+		assert_eq!(result.path, vec![0, 1, 2, 3]);
+		assert_eq!(result.stats.expanded, 3);
+		assert_eq!(result.stats.max_frontier, 2);
+	}
+
+	#[test]
+	fn astar_uses_heuristic() {
+		let line = |x: &i32| vec![(x - 1, 1), (x + 1, 1)];
+		let with_heuristic = astar(0, line, |x| (10 - x).unsigned_abs(), |x| *x == 10).unwrap();
+		let without_heuristic = dijkstra(0, line, |x| *x == 10).unwrap();
+		assert_eq!(with_heuristic.cost, 10);
+		assert_eq!(without_heuristic.cost, 10);
+		assert_eq!(with_heuristic.path, (0..=10).collect::<Vec<_>>());
+		assert!(with_heuristic.stats.expanded < without_heuristic.stats.expanded);
+	}
+
+	#[test]
+	fn bfs_counts_steps() {
+		let result = bfs(
+			0,
+			|node| successors(node).into_iter().map(|(n, _)| n),
+			|node| *node == 3,
+		)
+		.unwrap();
+		assert_eq!(result.cost, 1);
+		assert_eq!(result.path, vec![0, 3]);
+	}
+
+	#[test]
+	fn unreachable_goal() {
+		assert!(dijkstra(0, successors, |node| *node == 4).is_none());
+		assert!(bfs(1u32, |_| Vec::new(), |node| *node == 0).is_none());
+	}
+}
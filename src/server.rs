@@ -0,0 +1,372 @@
+// Small HTTP server, so that other tools can use the solvers without running
+// the binary for every input. Only what the API needs is supported: one
+// request per connection and bodies with a `Content-Length`.
+//
+//   GET  /days                   the days there are solutions for
+//   POST /day/{n}/part/{p}       solves the input in the body, parameters of
+//                                the day can be given in the query string,
+//                                like `/day/6/part/1?days=18`
+
+use std::{
+	io::{self, BufRead, BufReader, Write},
+	net::{TcpListener, TcpStream},
+	thread,
+};
+
+use crate::{
+	json::Json,
+	memory,
+	runner::{self, Outcome},
+	solution::{self, Runner},
+};
+
+// Puzzle inputs are a few kilobytes, this is plenty
+const MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+/// The parts of an HTTP request the server looks at
+#[derive(Debug)]
+pub struct Request {
+	pub method: String,
+	pub path: String,
+	pub query: Vec<(String, String)>,
+	pub body: String,
+}
+
+/// Status code and JSON body of an answer to a request
+pub struct Response {
+	pub status: u16,
+	pub body: Json,
+}
+
+impl Response {
+	fn error(status: u16, message: impl Into<String>) -> Response {
+		Response {
+			status,
+			body: Json::Object(vec![("error", Json::string(message))]),
+		}
+	}
+}
+
+fn status_text(status: u16) -> &'static str {
+	match status {
+		200 => "OK",
+		400 => "Bad Request",
+		404 => "Not Found",
+		405 => "Method Not Allowed",
+		411 => "Length Required",
+		413 => "Payload Too Large",
+		422 => "Unprocessable Entity",
+		504 => "Gateway Timeout",
+		_ => "Internal Server Error",
+	}
+}
+
+fn bad_request(message: impl Into<String>) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+// Turns `+` into a space and `%XX` into the byte it stands for. Anything that
+// isn't a valid escape is kept as it is.
+fn percent_decode(text: &str) -> String {
+	let bytes = text.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+	while i < bytes.len() {
+		let escaped = bytes
+			.get(i + 1..i + 3)
+			.filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+			.and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).unwrap(), 16).ok());
+		match (bytes[i], escaped) {
+			(b'%', Some(byte)) => {
+				decoded.push(byte);
+				i += 3;
+			}
+			(b'+', _) => {
+				decoded.push(b' ');
+				i += 1;
+			}
+			(byte, _) => {
+				decoded.push(byte);
+				i += 1;
+			}
+		}
+	}
+	String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// The first `=` of a pair splits it, so values can contain escaped ones
+fn parse_query(query: &str) -> Vec<(String, String)> {
+	query
+		.split('&')
+		.filter(|pair| !pair.is_empty())
+		.map(|pair| {
+			let pair = percent_decode(pair);
+			match pair.split_once('=') {
+				Some((name, value)) => (name.to_string(), value.to_string()),
+				None => (pair, String::new()),
+			}
+		})
+		.collect()
+}
+
+pub fn read_request(reader: &mut impl BufRead) -> io::Result<Request> {
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line)?;
+	let mut words = request_line.split_whitespace();
+	let (method, target) = match (words.next(), words.next()) {
+		(Some(method), Some(target)) => (method.to_string(), target),
+		_ => return Err(bad_request("malformed request line")),
+	};
+	let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+	let mut content_length = None;
+	loop {
+		let mut header = String::new();
+		if reader.read_line(&mut header)? == 0 {
+			return Err(bad_request(
+				"connection closed in the middle of the headers",
+			));
+		}
+		let header = header.trim_end();
+		if header.is_empty() {
+			break;
+		}
+		if let Some((name, value)) = header.split_once(':') {
+			if name.eq_ignore_ascii_case("content-length") {
+				let length = value
+					.trim()
+					.parse()
+					.map_err(|_| bad_request("invalid Content-Length"))?;
+				content_length = Some(length);
+			}
+		}
+	}
+
+	let body = match content_length {
+		Some(length) if length > MAX_BODY_SIZE => return Err(bad_request("body too large")),
+		Some(length) => {
+			let mut body = vec![0; length];
+			reader.read_exact(&mut body)?;
+			String::from_utf8(body).map_err(|_| bad_request("body is not UTF-8"))?
+		}
+		None if method == "POST" => return Err(bad_request("missing Content-Length")),
+		None => String::new(),
+	};
+
+	Ok(Request {
+		method,
+		path: path.to_string(),
+		query: parse_query(query),
+		body,
+	})
+}
+
+pub fn write_response(writer: &mut impl Write, response: &Response) -> io::Result<()> {
+	let body = response.body.to_string();
+	write!(
+		writer,
+		"HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+		response.status,
+		status_text(response.status),
+		body.len(),
+		body
+	)?;
+	writer.flush()
+}
+
+fn list_days(solutions: &[&dyn Runner]) -> Response {
+	let days = solutions
+		.iter()
+		.map(|solution| Json::number(solution.day()))
+		.collect();
+	Response {
+		status: 200,
+		body: Json::Object(vec![("days", Json::Array(days))]),
+	}
+}
+
+fn solve(solutions: &[&dyn Runner], day: &str, part: &str, request: &Request) -> Response {
+	let solution = match day
+		.parse()
+		.ok()
+		.and_then(|day| solution::find_solution(solutions, day))
+	{
+		Some(solution) => solution,
+		None => return Response::error(404, format!("day {} not found", day)),
+	};
+	let part = match part.parse::<u32>() {
+		Ok(part) if part == 1 || part == 2 => part,
+		_ => return Response::error(404, format!("part {} not found", part)),
+	};
+	if let Err(e) = solution.params(part, &request.query) {
+		return Response::error(400, e);
+	}
+
+	let report = runner::run_parts_with(solution, &request.body, &[part], &request.query);
+	if let Some(error) = &report.parse_error {
+		return Response::error(422, error.render("<body>", &request.body));
+	}
+	let part = &report.parts[0];
+	let status = match part.outcome {
+		Outcome::Solved(_) => 200,
+		Outcome::Failed(_) => 422,
+		Outcome::Skipped(_) => 500,
+		Outcome::TimedOut(_) => 504,
+	};
+	Response {
+		status,
+		body: runner::part_to_json(&report, part),
+	}
+}
+
+pub fn handle(solutions: &[&dyn Runner], request: &Request) -> Response {
+	let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+	match (request.method.as_str(), segments.as_slice()) {
+		("GET", ["days"]) => list_days(solutions),
+		("POST", ["day", day, "part", part]) => solve(solutions, day, part, request),
+		(_, ["days"]) | (_, ["day", _, "part", _]) => Response::error(405, "method not allowed"),
+		_ => Response::error(404, format!("no such endpoint '{}'", request.path)),
+	}
+}
+
+fn handle_connection(solutions: &[&dyn Runner], stream: TcpStream) -> io::Result<()> {
+	let mut reader = BufReader::new(stream.try_clone()?);
+	let response = match read_request(&mut reader) {
+		Ok(request) => handle(solutions, &request),
+		Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+			let status = match e.to_string().as_str() {
+				"missing Content-Length" => 411,
+				"body too large" => 413,
+				_ => 400,
+			};
+			Response::error(status, e.to_string())
+		}
+		Err(e) => return Err(e),
+	};
+	write_response(&mut &stream, &response)
+}
+
+/// Every request gets its own thread, so that a slow day doesn't hold up the
+/// others. Memory is counted for the whole process, so when it is counted the
+/// requests are answered one at a time instead, the same way `run_all` runs one
+/// day at a time. Runs until the process is stopped.
+pub fn serve(listener: TcpListener, solutions: &'static [&'static dyn Runner]) -> io::Result<()> {
+	runner::with_silent_panics(|| {
+		for stream in listener.incoming() {
+			// A connection that failed before it was accepted doesn't affect the others
+			let stream = match stream {
+				Ok(stream) => stream,
+				Err(e) => {
+					eprintln!("Failed to accept a connection: {}", e);
+					continue;
+				}
+			};
+			let answer = move || {
+				if let Err(e) = handle_connection(solutions, stream) {
+					eprintln!("Failed to answer a request: {}", e);
+				}
+			};
+			if memory::enabled() {
+				answer();
+			} else {
+				thread::spawn(answer);
+			}
+		}
+		Ok(())
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::SOLUTIONS;
+	use std::io::Read;
+
+	fn request(method: &str, path: &str, body: &str) -> Response {
+		let (path, query) = path.split_once('?').unwrap_or((path, ""));
+		let request = Request {
+			method: method.into(),
+			path: path.into(),
+			query: parse_query(query),
+			body: body.into(),
+		};
+		handle(&SOLUTIONS, &request)
+	}
+
+	#[test]
+	fn reads_requests() {
+		let raw = "POST /day/6/part/1?days=18&x HTTP/1.1\r\nHost: localhost\r\ncontent-length: 9\r\n\r\n3,4,3,1,2";
+		let request = read_request(&mut raw.as_bytes()).unwrap();
+		assert_eq!(request.method, "POST");
+		assert_eq!(request.path, "/day/6/part/1");
+		assert_eq!(
+			request.query,
+			vec![("days".into(), "18".into()), ("x".into(), String::new())]
+		);
+		assert_eq!(request.body, "3,4,3,1,2");
+
+		let error = read_request(&mut "POST /day/1/part/1 HTTP/1.1\r\n\r\n".as_bytes()).unwrap_err();
+		assert_eq!(error.to_string(), "missing Content-Length");
+		assert!(read_request(&mut "nonsense\r\n\r\n".as_bytes()).is_err());
+	}
+
+	#[test]
+	fn decodes_query() {
+		assert_eq!(
+			parse_query("param=steps%3D10&input=a%2Fb&name=two+words%21"),
+			vec![
+				("param".into(), "steps=10".into()),
+				("input".into(), "a/b".into()),
+				("name".into(), "two words!".into()),
+			]
+		);
+		assert_eq!(percent_decode("100%&%zz%+1%4"), "100%&%zz% 1%4");
+		assert_eq!(percent_decode("%C3%A9"), "é");
+	}
+
+	#[test]
+	fn routes() {
+		let response = request("GET", "/days", "");
+		assert_eq!(response.status, 200);
+		assert!(response.body.to_string().starts_with(r#"{"days":[1,2,3,"#));
+
+		let response = request("POST", "/day/6/part/1?days=18", "3,4,3,1,2");
+		assert_eq!(response.status, 200);
+		assert!(response
+			.body
+			.to_string()
+			.starts_with(r#"{"day":6,"part":1,"answer":26,"#));
+
+		assert_eq!(request("POST", "/day/6/part/1?weeks=2", "3,4").status, 400);
+		assert_eq!(request("POST", "/day/6/part/1", "3,x").status, 422);
+		assert_eq!(request("POST", "/day/25/part/2", "v.\n.>").status, 500);
+		assert_eq!(request("POST", "/day/2/part/1", "up 1").status, 422);
+		assert_eq!(request("POST", "/day/26/part/1", "").status, 404);
+		assert_eq!(request("POST", "/day/1/part/3", "").status, 404);
+		assert_eq!(request("GET", "/day/1/part/1", "").status, 405);
+		assert_eq!(request("GET", "/", "").status, 404);
+	}
+
+	#[test]
+	fn serves_over_tcp() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = listener.local_addr().unwrap();
+		// `serve` would keep the panic hook replaced for the rest of the tests
+		let server = thread::spawn(move || handle_connection(&SOLUTIONS, listener.accept().unwrap().0));
+
+		let mut stream = TcpStream::connect(address).unwrap();
+		let body = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+		write!(
+			stream,
+			"POST /day/1/part/2 HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+			body.len(),
+			body
+		)
+		.unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+		assert!(response.contains("\r\n\r\n{\"day\":1,\"part\":2,\"answer\":5,"));
+		server.join().unwrap().unwrap();
+	}
+}
@@ -0,0 +1,292 @@
+use std::{any::Any, convert::TryFrom, fmt, str::FromStr};
+
+use crate::{generate::Rng, json::Json, parse::ParseError, visualize::FrameSink};
+
+/// Common answer type of all days. Most days have a number as an answer, but
+/// some have to be read off of a rendering instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+	Integer(i128),
+	/// Only the integers that are too big for `Integer`
+	Unsigned(u128),
+	Text(String),
+	Rendering(String),
+}
+
+impl Answer {
+	pub fn kind(&self) -> &'static str {
+		match self {
+			Answer::Integer(_) | Answer::Unsigned(_) => "integer",
+			Answer::Text(_) => "string",
+			Answer::Rendering(_) => "rendering",
+		}
+	}
+}
+
+impl fmt::Display for Answer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Answer::Integer(value) => write!(f, "{}", value),
+			Answer::Unsigned(value) => write!(f, "{}", value),
+			Answer::Text(value) => write!(f, "{}", value),
+			Answer::Rendering(value) => write!(f, "{}", value.trim_end()),
+		}
+	}
+}
+
+macro_rules! impl_integer_answer {
+	($($t:ty),*) => {
+		$(
+			impl From<$t> for Answer {
+				fn from(value: $t) -> Self {
+					Answer::Integer(value as i128)
+				}
+			}
+		)*
+	};
+}
+
+impl_integer_answer!(i32, i64, i128, u32, u64, usize);
+
+impl From<u128> for Answer {
+	fn from(value: u128) -> Self {
+		match i128::try_from(value) {
+			Ok(value) => Answer::Integer(value),
+			Err(_) => Answer::Unsigned(value),
+		}
+	}
+}
+
+impl From<String> for Answer {
+	fn from(value: String) -> Self {
+		Answer::Text(value)
+	}
+}
+
+/// What a part can return. Parts whose input can turn out to have no answer
+/// return a `Result`, and the error is reported instead of the answer.
+pub trait PartOutput {
+	fn into_answer(self) -> Result<Answer, String>;
+}
+
+impl<T: Into<Answer>> PartOutput for T {
+	fn into_answer(self) -> Result<Answer, String> {
+		Ok(self.into())
+	}
+}
+
+impl<T: Into<Answer>, E: fmt::Display> PartOutput for Result<T, E> {
+	fn into_answer(self) -> Result<Answer, String> {
+		self.map(Into::into).map_err(|e| e.to_string())
+	}
+}
+
+/// Numbers from the puzzle text that a day can be asked to use different values
+/// for, like the number of steps to simulate. Days without any use `()`.
+pub trait PuzzleParams: 'static {
+	/// The values from the puzzle for the given part
+	fn puzzle(part: u32) -> Self;
+	/// Changes a parameter given as `name=value` on the command line
+	fn set(&mut self, name: &str, value: &str) -> Result<(), String>;
+}
+
+impl PuzzleParams for () {
+	fn puzzle(_part: u32) {}
+
+	fn set(&mut self, name: &str, _value: &str) -> Result<(), String> {
+		Err(format!("unknown parameter '{}', this day has none", name))
+	}
+}
+
+pub fn parse_param<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+	value
+		.parse()
+		.map_err(|_| format!("invalid value '{}' for parameter '{}'", value, name))
+}
+
+pub fn unknown_param(name: &str, known: &[&str]) -> String {
+	format!(
+		"unknown parameter '{}', expected one of: {}",
+		name,
+		known.join(", ")
+	)
+}
+
+/// Every day implements this trait, so that the runner doesn't need to know
+/// anything about the specific input or answer types of a day.
+pub trait Solution {
+	const DAY: u32;
+	/// Only the days of other years need to set this
+	const YEAR: u32 = crate::YEAR;
+
+	type Input: 'static;
+	type Output1: PartOutput;
+	type Output2: PartOutput;
+	type Params: PuzzleParams;
+
+	fn parse(input: &str) -> Result<Self::Input, ParseError>;
+	fn part1(input: &Self::Input, params: &Self::Params) -> Self::Output1;
+	fn part2(input: &Self::Input, params: &Self::Params) -> Self::Output2;
+
+	/// Makes up a valid input, `size` roughly being the number of things in it
+	fn generate(rng: &mut Rng, size: usize) -> String;
+
+	/// Days that simulate something step by step push a frame for every step of
+	/// the given part. Returns false when there is nothing to show.
+	fn visualize(
+		_input: &Self::Input,
+		_part: u32,
+		_params: &Self::Params,
+		_frames: &mut dyn FrameSink,
+	) -> bool {
+		false
+	}
+
+	/// More about the input than just the answer, as JSON so that it can be
+	/// plotted. `None` for days that have nothing more to say.
+	fn report(_input: &Self::Input, _part: u32, _params: &Self::Params) -> Option<Json> {
+		None
+	}
+}
+
+/// Type erased version of `Solution`, so that all of the days could be stored
+/// in a single list. The parsed input and the parameters are passed around as
+/// `Any`.
+pub trait Runner: Sync {
+	fn day(&self) -> u32;
+	fn year(&self) -> u32;
+	fn parse(&self, input: &str) -> Result<Box<dyn Any>, ParseError>;
+	/// Parameters of the part, with the puzzle values changed by `settings`
+	fn params(&self, part: u32, settings: &[(String, String)]) -> Result<Box<dyn Any>, String>;
+	fn part1(&self, input: &dyn Any, params: &dyn Any) -> Result<Answer, String>;
+	fn part2(&self, input: &dyn Any, params: &dyn Any) -> Result<Answer, String>;
+	fn generate(&self, rng: &mut Rng, size: usize) -> String;
+	fn visualize(
+		&self,
+		input: &dyn Any,
+		part: u32,
+		params: &dyn Any,
+		frames: &mut dyn FrameSink,
+	) -> bool;
+	fn report(&self, input: &dyn Any, part: u32, params: &dyn Any) -> Option<Json>;
+
+	fn solve(&self, input: &dyn Any, part: u32) -> Option<Answer> {
+		self.solve_with(input, part, &[]).ok()
+	}
+
+	fn solve_with(
+		&self,
+		input: &dyn Any,
+		part: u32,
+		settings: &[(String, String)],
+	) -> Result<Answer, String> {
+		let params = self.params(part, settings)?;
+		match part {
+			1 => self.part1(input, params.as_ref()),
+			2 => self.part2(input, params.as_ref()),
+			_ => Err(format!("part {} not found", part)),
+		}
+	}
+}
+
+impl<S: Solution + Sync> Runner for S {
+	fn day(&self) -> u32 {
+		S::DAY
+	}
+
+	fn year(&self) -> u32 {
+		S::YEAR
+	}
+
+	fn parse(&self, input: &str) -> Result<Box<dyn Any>, ParseError> {
+		Ok(Box::new(S::parse(input)?))
+	}
+
+	fn params(&self, part: u32, settings: &[(String, String)]) -> Result<Box<dyn Any>, String> {
+		let mut params = S::Params::puzzle(part);
+		for (name, value) in settings {
+			params.set(name, value)?;
+		}
+		Ok(Box::new(params))
+	}
+
+	fn part1(&self, input: &dyn Any, params: &dyn Any) -> Result<Answer, String> {
+		S::part1(downcast_input::<S>(input), downcast_params::<S>(params)).into_answer()
+	}
+
+	fn part2(&self, input: &dyn Any, params: &dyn Any) -> Result<Answer, String> {
+		S::part2(downcast_input::<S>(input), downcast_params::<S>(params)).into_answer()
+	}
+
+	fn generate(&self, rng: &mut Rng, size: usize) -> String {
+		S::generate(rng, size)
+	}
+
+	fn visualize(
+		&self,
+		input: &dyn Any,
+		part: u32,
+		params: &dyn Any,
+		frames: &mut dyn FrameSink,
+	) -> bool {
+		S::visualize(
+			downcast_input::<S>(input),
+			part,
+			downcast_params::<S>(params),
+			frames,
+		)
+	}
+
+	fn report(&self, input: &dyn Any, part: u32, params: &dyn Any) -> Option<Json> {
+		S::report(
+			downcast_input::<S>(input),
+			part,
+			downcast_params::<S>(params),
+		)
+	}
+}
+
+fn downcast_input<S: Solution>(input: &dyn Any) -> &S::Input {
+	input
+		.downcast_ref::<S::Input>()
+		.expect("Input was parsed by a different solution")
+}
+
+fn downcast_params<S: Solution>(params: &dyn Any) -> &S::Params {
+	params
+		.downcast_ref::<S::Params>()
+		.expect("Parameters are of a different solution")
+}
+
+pub fn find_solution<'a>(solutions: &[&'a dyn Runner], day: u32) -> Option<&'a dyn Runner> {
+	solutions.iter().find(|s| s.day() == day).copied()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::day1::Day1;
+
+	#[test]
+	fn runner_dispatch() {
+		let solutions: [&dyn Runner; 1] = [&Day1];
+		let solution = find_solution(&solutions, 1).unwrap();
+		let input = solution
+			.parse("199\n200\n208\n210\n200\n207\n240\n269\n260\n263")
+			.unwrap();
+		assert_eq!(solution.solve(input.as_ref(), 1), Some(Answer::Integer(7)));
+		assert_eq!(solution.solve(input.as_ref(), 2), Some(Answer::Integer(5)));
+		assert_eq!(solution.solve(input.as_ref(), 3), None);
+		assert!(find_solution(&solutions, 2).is_none());
+	}
+
+	#[test]
+	fn answer_display() {
+		assert_eq!(Answer::from(42u64).to_string(), "42");
+		assert_eq!(Answer::from(-3).kind(), "integer");
+		assert_eq!(Answer::from(5u128), Answer::Integer(5));
+		assert_eq!(Answer::from(u128::MAX).to_string(), u128::MAX.to_string());
+		assert_eq!(Answer::from(u128::MAX).kind(), "integer");
+		assert_eq!(Answer::Rendering("#.\n.#\n".into()).to_string(), "#.\n.#");
+	}
+}
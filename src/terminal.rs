@@ -0,0 +1,217 @@
+// Steps through a recorded simulation in the terminal, drawn with plain ANSI
+// escape codes. The terminal is taken out of line mode with `stty`, so that
+// key presses can be read as they happen instead of after enter.
+
+use std::{
+	fmt::Write as _,
+	io::{self, IsTerminal, Read, Write},
+	process::{Command, Stdio},
+};
+
+use crate::visualize::Recording;
+
+/// The keys that `step_through` reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+	Forward,
+	Back,
+	First,
+	Last,
+	Quit,
+}
+
+/// Arrow keys, home and end come in as escape sequences, the rest as single bytes
+pub fn parse_key(bytes: &[u8]) -> Option<Key> {
+	match bytes {
+		b"\x1b[C" | b"l" | b"n" | b" " => Some(Key::Forward),
+		b"\x1b[D" | b"h" | b"p" | b"\x7f" => Some(Key::Back),
+		b"\x1b[H" | b"g" => Some(Key::First),
+		b"\x1b[F" | b"G" => Some(Key::Last),
+		b"q" | b"\x1b" | b"\x03" => Some(Key::Quit),
+		_ => None,
+	}
+}
+
+pub fn next_step(step: usize, last: usize, key: Key) -> usize {
+	match key {
+		Key::Forward => (step + 1).min(last),
+		Key::Back => step.saturating_sub(1),
+		Key::First => 0,
+		Key::Last => last,
+		Key::Quit => step,
+	}
+}
+
+/// The whole screen for a step: a status line, the counters and as much of the
+/// frame as fits. Cells are drawn on their color, with the symbol in black or
+/// white, whichever is easier to read.
+pub fn render_screen(
+	title: &str,
+	recording: &Recording,
+	step: usize,
+	columns: usize,
+	rows: usize,
+) -> String {
+	let frame = &recording.frames[step];
+	let mut screen = String::from("\x1b[H\x1b[2J");
+	write!(
+		screen,
+		"{} - step {}/{}\r\n",
+		title,
+		step,
+		recording.frames.len() - 1
+	)
+	.unwrap();
+	let counters: Vec<String> = recording.counters[step]
+		.iter()
+		.map(|(name, value)| format!("{}: {}", name, value))
+		.collect();
+	write!(screen, "{}\r\n", counters.join(", ")).unwrap();
+
+	for y in 0..frame.height().min(rows.saturating_sub(3)) {
+		let mut last_color = None;
+		for x in 0..frame.width().min(columns) {
+			let cell = frame[(x, y)];
+			if last_color != Some(cell.color) {
+				let [r, g, b] = cell.color;
+				let foreground = if cell.gray() > 127 { 0 } else { 255 };
+				write!(
+					screen,
+					"\x1b[48;2;{};{};{}m\x1b[38;2;{f};{f};{f}m",
+					r,
+					g,
+					b,
+					f = foreground
+				)
+				.unwrap();
+				last_color = Some(cell.color);
+			}
+			screen.push(cell.symbol);
+		}
+		screen.push_str("\x1b[0m\r\n");
+	}
+	screen.push_str("left/right or h/l to step, g/G for the first/last step, q to quit");
+	screen
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+	let output = Command::new("stty")
+		.args(args)
+		.stdin(Stdio::inherit())
+		.output()?;
+	if !output.status.success() {
+		return Err(io::Error::other(
+			String::from_utf8_lossy(&output.stderr).trim().to_string(),
+		));
+	}
+	Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Rows and columns of the terminal
+fn terminal_size() -> Option<(usize, usize)> {
+	let size = stty(&["size"]).ok()?;
+	let (rows, columns) = size.split_once(' ')?;
+	Some((rows.parse().ok()?, columns.parse().ok()?))
+}
+
+// Puts the terminal back the way it was when dropped, even after a panic
+struct RawMode {
+	saved: String,
+}
+
+impl RawMode {
+	fn enable() -> io::Result<RawMode> {
+		let saved = stty(&["-g"])?;
+		stty(&["-icanon", "-echo", "-isig", "min", "1", "time", "0"])?;
+		// Alternate screen, without the cursor
+		print!("\x1b[?1049h\x1b[?25l");
+		Ok(RawMode { saved })
+	}
+}
+
+impl Drop for RawMode {
+	fn drop(&mut self) {
+		print!("\x1b[?25h\x1b[?1049l");
+		let _ = io::stdout().flush();
+		let _ = stty(&[&self.saved]);
+	}
+}
+
+pub fn step_through(title: &str, recording: &Recording) -> io::Result<()> {
+	if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+		return Err(io::Error::other("stepping through needs a terminal"));
+	}
+	if recording.frames.is_empty() {
+		return Ok(());
+	}
+
+	let _raw_mode = RawMode::enable()?;
+	let mut stdout = io::stdout();
+	let mut stdin = io::stdin();
+	let last = recording.frames.len() - 1;
+	let mut step = 0;
+	let mut buffer = [0; 8];
+	loop {
+		// Asked every time, in case the terminal got resized
+		let (rows, columns) = terminal_size().unwrap_or((24, 80));
+		stdout.write_all(render_screen(title, recording, step, columns, rows).as_bytes())?;
+		stdout.flush()?;
+
+		let count = stdin.read(&mut buffer)?;
+		match parse_key(&buffer[..count]) {
+			_ if count == 0 => return Ok(()),
+			Some(Key::Quit) => return Ok(()),
+			Some(key) => step = next_step(step, last, key),
+			None => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		grid::Grid,
+		visualize::{Cell, FrameSink},
+	};
+
+	#[test]
+	fn keys() {
+		assert_eq!(parse_key(b"\x1b[C"), Some(Key::Forward));
+		assert_eq!(parse_key(b"h"), Some(Key::Back));
+		assert_eq!(parse_key(b"q"), Some(Key::Quit));
+		assert_eq!(parse_key(b"\x1b"), Some(Key::Quit));
+		assert_eq!(parse_key(b"x"), None);
+
+		assert_eq!(next_step(3, 3, Key::Forward), 3);
+		assert_eq!(next_step(0, 3, Key::Back), 0);
+		assert_eq!(next_step(1, 3, Key::Forward), 2);
+		assert_eq!(next_step(1, 3, Key::Last), 3);
+		assert_eq!(next_step(2, 3, Key::First), 0);
+	}
+
+	#[test]
+	fn screen() {
+		let mut recording = Recording::default();
+		let white = Cell::new('#', [255, 255, 255]);
+		let black = Cell::new('.', [0, 0, 0]);
+		recording.push(
+			&Grid::from_cells(3, 2, vec![white, white, black, black, black, black]),
+			&[],
+		);
+		recording.push(
+			&Grid::new(3, 2, black),
+			&[("dots", "0".into()), ("step", "1".into())],
+		);
+
+		let screen = render_screen("Day 13 part 2", &recording, 0, 2, 24);
+		assert!(screen.contains("Day 13 part 2 - step 0/1\r\n"));
+		// Cropped to two columns, and the color only changes once
+		assert!(screen.contains("\x1b[48;2;255;255;255m\x1b[38;2;0;0;0m##\x1b[0m\r\n"));
+		assert!(screen.contains("\x1b[48;2;0;0;0m\x1b[38;2;255;255;255m..\x1b[0m\r\n"));
+
+		let screen = render_screen("Day 13 part 2", &recording, 1, 80, 4);
+		assert!(screen.contains("dots: 0, step: 1\r\n"));
+		assert_eq!(screen.matches("\x1b[0m\r\n").count(), 1);
+	}
+}
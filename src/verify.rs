@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use crate::runner::{format_timeout, DayReport, Outcome};
+
+/// Known correct answers, read from a small subset of TOML:
+///
+/// ```text
+/// [13]
+/// part1 = 850
+/// part2 = """
+/// #..#
+/// """
+/// ```
+///
+/// Every table is a day, every key is a part.
+pub struct KnownAnswers(HashMap<(u32, u32), String>);
+
+fn parse_part_key(key: &str) -> Option<u32> {
+	key.strip_prefix("part")?.parse().ok()
+}
+
+impl KnownAnswers {
+	pub fn parse(input: &str) -> Result<KnownAnswers, String> {
+		let mut answers = HashMap::new();
+		let mut day = None;
+		let mut lines = input.lines().enumerate();
+		while let Some((i, line)) = lines.next() {
+			let line_number = i + 1;
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+				let parsed = header
+					.trim()
+					.parse()
+					.map_err(|_| format!("line {}: invalid day '{}'", line_number, header))?;
+				day = Some(parsed);
+				continue;
+			}
+
+			let day = day.ok_or(format!(
+				"line {}: answer outside of a [day] table",
+				line_number
+			))?;
+			let (key, value) = line
+				.split_once('=')
+				.ok_or(format!("line {}: expected 'partN = answer'", line_number))?;
+			let part = parse_part_key(key.trim()).ok_or(format!(
+				"line {}: invalid part '{}'",
+				line_number,
+				key.trim()
+			))?;
+
+			let value = value.trim();
+			let answer = if value == "\"\"\"" {
+				let mut rows = Vec::new();
+				loop {
+					match lines.next() {
+						Some((_, row)) if row.trim() == "\"\"\"" => break,
+						Some((_, row)) => rows.push(row),
+						None => {
+							return Err(format!(
+								"line {}: unterminated multi-line answer",
+								line_number
+							))
+						}
+					}
+				}
+				rows.join("\n")
+			} else if let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+				quoted.to_string()
+			} else {
+				value.to_string()
+			};
+
+			if answers.insert((day, part), answer).is_some() {
+				return Err(format!(
+					"line {}: duplicate answer for day {} part {}",
+					line_number, day, part
+				));
+			}
+		}
+		Ok(KnownAnswers(answers))
+	}
+
+	pub fn get(&self, day: u32, part: u32) -> Option<&str> {
+		self.0.get(&(day, part)).map(|s| s.as_str())
+	}
+}
+
+/// How an answer compares to the known one
+pub enum Verdict {
+	Pass,
+	Mismatch {
+		expected: String,
+		actual: String,
+	},
+	Fail(String),
+	/// There is no known answer to compare against
+	Untracked,
+}
+
+/// The verdict for one part of a day
+pub struct Check {
+	pub day: u32,
+	pub part: u32,
+	pub verdict: Verdict,
+}
+
+pub fn check_reports(reports: &[DayReport], answers: &KnownAnswers) -> Vec<Check> {
+	let mut checks = Vec::new();
+	for report in reports {
+		for part in &report.parts {
+			let verdict = match (answers.get(report.day, part.part), &part.outcome) {
+				(None, _) => Verdict::Untracked,
+				(Some(_), Outcome::Skipped(reason) | Outcome::Failed(reason)) => {
+					Verdict::Fail(reason.clone())
+				}
+				(Some(_), Outcome::TimedOut(timeout)) => {
+					Verdict::Fail(format!("timed out after {}", format_timeout(*timeout)))
+				}
+				(Some(expected), Outcome::Solved(actual)) => {
+					let actual = actual.to_string();
+					// Renderings might differ in trailing newlines, those don't matter
+					if expected.trim_end() == actual.trim_end() {
+						Verdict::Pass
+					} else {
+						Verdict::Mismatch {
+							expected: expected.into(),
+							actual,
+						}
+					}
+				}
+			};
+			checks.push(Check {
+				day: report.day,
+				part: part.part,
+				verdict,
+			});
+		}
+	}
+	checks
+}
+
+pub fn print_checks(checks: &[Check]) {
+	for check in checks {
+		let status = match &check.verdict {
+			Verdict::Pass => "pass".to_string(),
+			Verdict::Mismatch { expected, actual } => format!(
+				"MISMATCH\n  expected: {}\n  actual:   {}",
+				expected.replace('\n', "\n            "),
+				actual.trim_end().replace('\n', "\n            ")
+			),
+			Verdict::Fail(reason) => format!("FAIL ({})", reason),
+			Verdict::Untracked => "untracked".to_string(),
+		};
+		println!("Day {:>2} part {}: {}", check.day, check.part, status);
+	}
+
+	let count = |f: fn(&Verdict) -> bool| checks.iter().filter(|c| f(&c.verdict)).count();
+	println!(
+		"{} passed, {} mismatched, {} failed, {} untracked",
+		count(|v| matches!(v, Verdict::Pass)),
+		count(|v| matches!(v, Verdict::Mismatch { .. })),
+		count(|v| matches!(v, Verdict::Fail(_))),
+		count(|v| matches!(v, Verdict::Untracked)),
+	);
+}
+
+/// 0 - everything that has a known answer passed
+/// 1 - at least one answer was wrong
+/// 2 - no wrong answers, but at least one solver failed to produce one
+pub fn exit_code(checks: &[Check]) -> i32 {
+	if checks
+		.iter()
+		.any(|c| matches!(c.verdict, Verdict::Mismatch { .. }))
+	{
+		1
+	} else if checks.iter().any(|c| matches!(c.verdict, Verdict::Fail(_))) {
+		2
+	} else {
+		0
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{runner::PartReport, solution::Answer};
+	use std::time::Duration;
+
+	fn report(day: u32, outcomes: Vec<Outcome>) -> DayReport {
+		let parts = outcomes
+			.into_iter()
+			.enumerate()
+			.map(|(i, outcome)| PartReport {
+				part: i as u32 + 1,
+				outcome,
+				solve_time: Duration::ZERO,
+				memory: None,
+			})
+			.collect();
+		DayReport {
+			day,
+			parse_time: Duration::ZERO,
+			parse_memory: None,
+			parts,
+			parse_error: None,
+		}
+	}
+
+	#[test]
+	fn parse_known_answers() {
+		let answers = KnownAnswers::parse(
+			"# comment\n[1]\npart1 = 7\npart2 = \"5\"\n\n[13]\npart2 = \"\"\"\n#.#\n.#.\n\"\"\"\n",
+		)
+		.unwrap();
+		assert_eq!(answers.get(1, 1), Some("7"));
+		assert_eq!(answers.get(1, 2), Some("5"));
+		assert_eq!(answers.get(13, 2), Some("#.#\n.#."));
+		assert_eq!(answers.get(13, 1), None);
+	}
+
+	#[test]
+	fn parse_known_answers_errors() {
+		assert!(KnownAnswers::parse("part1 = 7").is_err());
+		assert!(KnownAnswers::parse("[x]").is_err());
+		assert!(KnownAnswers::parse("[1]\nfoo = 7").is_err());
+		assert!(KnownAnswers::parse("[1]\npart1 = 7\npart1 = 8").is_err());
+		assert!(KnownAnswers::parse("[1]\npart1 = \"\"\"\n#").is_err());
+	}
+
+	#[test]
+	fn verdicts_and_exit_codes() {
+		let answers = KnownAnswers::parse("[1]\npart1 = 7\npart2 = 5\n[2]\npart1 = 1").unwrap();
+		let passing = [report(
+			1,
+			vec![
+				Outcome::Solved(Answer::Integer(7)),
+				Outcome::Solved(Answer::Integer(5)),
+			],
+		)];
+		assert_eq!(exit_code(&check_reports(&passing, &answers)), 0);
+
+		let failing = [report(
+			2,
+			vec![
+				Outcome::Skipped("oops".into()),
+				Outcome::Solved(Answer::Integer(3)),
+			],
+		)];
+		let checks = check_reports(&failing, &answers);
+		assert!(matches!(checks[0].verdict, Verdict::Fail(_)));
+		assert!(matches!(checks[1].verdict, Verdict::Untracked));
+		assert_eq!(exit_code(&checks), 2);
+
+		let mismatched = [report(
+			1,
+			vec![
+				Outcome::Solved(Answer::Integer(8)),
+				Outcome::Skipped("oops".into()),
+			],
+		)];
+		assert_eq!(exit_code(&check_reports(&mismatched, &answers)), 1);
+	}
+}
@@ -0,0 +1,261 @@
+// Frames of the step by step simulations. A day turns its state into a grid
+// of cells, which can be written out as images or as text, so the same frame
+// works for both.
+
+use std::{
+	fs::{self, File},
+	io::{self, BufWriter, Write},
+	path::{Path, PathBuf},
+};
+
+use crate::grid::Grid;
+
+/// A character for the terminal and a color for the images
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+	pub symbol: char,
+	pub color: [u8; 3],
+}
+
+impl Cell {
+	pub fn new(symbol: char, color: [u8; 3]) -> Cell {
+		Cell { symbol, color }
+	}
+
+	/// Perceived brightness, for the grayscale images
+	pub fn gray(&self) -> u8 {
+		let [r, g, b] = self.color;
+		((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+	}
+}
+
+/// A single frame of a visualization
+pub type Frame = Grid<Cell>;
+
+/// Where the frames of a simulation go. Every frame comes with counters of
+/// what is going on in it, like the number of flashes or dots.
+pub trait FrameSink {
+	fn push(&mut self, frame: &Frame, counters: &[(&str, String)]);
+}
+
+impl FrameSink for Vec<Frame> {
+	fn push(&mut self, frame: &Frame, _counters: &[(&str, String)]) {
+		Vec::push(self, frame.clone());
+	}
+}
+
+/// Keeps every frame with its counters, so that it can be stepped through in
+/// both directions
+#[derive(Default)]
+pub struct Recording {
+	pub frames: Vec<Frame>,
+	pub counters: Vec<Vec<(String, String)>>,
+}
+
+impl FrameSink for Recording {
+	fn push(&mut self, frame: &Frame, counters: &[(&str, String)]) {
+		self.frames.push(frame.clone());
+		self.counters.push(
+			counters
+				.iter()
+				.map(|(name, value)| (name.to_string(), value.clone()))
+				.collect(),
+		);
+	}
+}
+
+/// What the frames get written as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+	Ppm,
+	Pgm,
+	Text,
+}
+
+impl FrameFormat {
+	pub fn parse(name: &str) -> Option<FrameFormat> {
+		match name {
+			"ppm" => Some(FrameFormat::Ppm),
+			"pgm" => Some(FrameFormat::Pgm),
+			"text" => Some(FrameFormat::Text),
+			_ => None,
+		}
+	}
+}
+
+// Small grids would make tiny images, so every cell is drawn as a square of
+// pixels, with the larger side of the image being around 400 pixels
+fn pixel_scale(frame: &Frame) -> usize {
+	(400 / frame.width().max(frame.height()).max(1)).max(1)
+}
+
+fn encode_image(frame: &Frame, magic: &str, mut pixel: impl FnMut(&Cell, &mut Vec<u8>)) -> Vec<u8> {
+	let scale = pixel_scale(frame);
+	let mut bytes = format!(
+		"{}\n{} {}\n255\n",
+		magic,
+		frame.width() * scale,
+		frame.height() * scale
+	)
+	.into_bytes();
+	for y in 0..frame.height() {
+		let mut row = vec![];
+		for x in 0..frame.width() {
+			for _ in 0..scale {
+				pixel(&frame[(x, y)], &mut row);
+			}
+		}
+		for _ in 0..scale {
+			bytes.extend_from_slice(&row);
+		}
+	}
+	bytes
+}
+
+/// Binary PPM, which most image viewers (and ffmpeg) can open
+pub fn encode_ppm(frame: &Frame) -> Vec<u8> {
+	encode_image(frame, "P6", |cell, bytes| {
+		bytes.extend_from_slice(&cell.color)
+	})
+}
+
+pub fn encode_pgm(frame: &Frame) -> Vec<u8> {
+	encode_image(frame, "P5", |cell, bytes| bytes.push(cell.gray()))
+}
+
+pub fn render_text(frame: &Frame) -> String {
+	frame.render(|cell| cell.symbol)
+}
+
+/// Writes every frame into a directory, images as `frame_00000.ppm` and so on,
+/// text frames all into `frames.txt` with a header line of counters before each one.
+/// Pushing a frame can't fail, the first error is kept and returned by `finish`.
+pub struct FrameWriter {
+	directory: PathBuf,
+	format: FrameFormat,
+	text_file: Option<BufWriter<File>>,
+	count: usize,
+	error: Option<io::Error>,
+}
+
+impl FrameWriter {
+	pub fn new(directory: &Path, format: FrameFormat) -> io::Result<FrameWriter> {
+		fs::create_dir_all(directory)?;
+		let text_file = match format {
+			FrameFormat::Text => Some(BufWriter::new(File::create(directory.join("frames.txt"))?)),
+			_ => None,
+		};
+		Ok(FrameWriter {
+			directory: directory.to_path_buf(),
+			format,
+			text_file,
+			count: 0,
+			error: None,
+		})
+	}
+
+	fn write(&mut self, frame: &Frame, counters: &[(&str, String)]) -> io::Result<()> {
+		match self.format {
+			FrameFormat::Ppm => fs::write(
+				self.directory.join(format!("frame_{:05}.ppm", self.count)),
+				encode_ppm(frame),
+			),
+			FrameFormat::Pgm => fs::write(
+				self.directory.join(format!("frame_{:05}.pgm", self.count)),
+				encode_pgm(frame),
+			),
+			FrameFormat::Text => {
+				let file = self.text_file.as_mut().unwrap();
+				write!(file, "--- frame {}", self.count)?;
+				for (name, value) in counters {
+					write!(file, ", {}: {}", name, value)?;
+				}
+				writeln!(file, " ---")?;
+				write!(file, "{}", render_text(frame))
+			}
+		}
+	}
+
+	/// Number of frames written
+	pub fn finish(mut self) -> io::Result<usize> {
+		if let Some(error) = self.error {
+			return Err(error);
+		}
+		if let Some(file) = self.text_file.as_mut() {
+			file.flush()?;
+		}
+		Ok(self.count)
+	}
+}
+
+impl FrameSink for FrameWriter {
+	fn push(&mut self, frame: &Frame, counters: &[(&str, String)]) {
+		if self.error.is_some() {
+			return;
+		}
+		match self.write(frame, counters) {
+			Ok(()) => self.count += 1,
+			Err(error) => self.error = Some(error),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn checkerboard(width: usize, height: usize) -> Frame {
+		let cells = (0..width * height)
+			.map(|i| {
+				if (i % width + i / width) % 2 == 1 {
+					Cell::new('.', [0, 0, 0])
+				} else {
+					Cell::new('#', [255, 255, 255])
+				}
+			})
+			.collect();
+		Grid::from_cells(width, height, cells)
+	}
+
+	#[test]
+	fn images() {
+		// 200x100 cells are drawn with 2x2 pixels
+		let frame = checkerboard(200, 100);
+		let ppm = encode_ppm(&frame);
+		let header = b"P6\n400 200\n255\n";
+		assert_eq!(&ppm[..header.len()], header);
+		assert_eq!(ppm.len(), header.len() + 400 * 200 * 3);
+		assert_eq!(
+			&ppm[header.len()..header.len() + 9],
+			&[255, 255, 255, 255, 255, 255, 0, 0, 0]
+		);
+
+		let pgm = encode_pgm(&checkerboard(400, 1));
+		let header = b"P5\n400 1\n255\n";
+		assert_eq!(&pgm[..header.len()], header);
+		assert_eq!(&pgm[header.len()..header.len() + 3], &[255, 0, 255]);
+
+		assert_eq!(Cell::new('x', [255, 0, 0]).gray(), 76);
+	}
+
+	#[test]
+	fn writes_frames_to_directory() {
+		let directory = std::env::temp_dir().join(format!("aoc-frames-{}", std::process::id()));
+		for format in [FrameFormat::Pgm, FrameFormat::Text] {
+			let mut writer = FrameWriter::new(&directory, format).unwrap();
+			writer.push(&checkerboard(2, 2), &[]);
+			writer.push(
+				&checkerboard(3, 1),
+				&[("dots", "2".into()), ("step", "1".into())],
+			);
+			assert_eq!(writer.finish().unwrap(), 2);
+		}
+		assert!(directory.join("frame_00001.pgm").exists());
+		let text = fs::read_to_string(directory.join("frames.txt")).unwrap();
+		assert_eq!(
+			text,
+			"--- frame 0 ---\n#.\n.#\n--- frame 1, dots: 2, step: 1 ---\n#.#\n"
+		);
+		fs::remove_dir_all(&directory).unwrap();
+	}
+}